@@ -0,0 +1,261 @@
+//! Heuristics for guessing which Booth item a downloaded archive belongs to.
+//!
+//! Booth does not embed item IDs in download names, so files are matched on the
+//! creator's naming instead: an explicit item ID in the name wins, then the item name
+//! appearing in the file name, then overlap of the meaningful words, with a bonus when
+//! the shop name is present as well.
+
+use std::collections::HashSet;
+
+#[derive(Debug, Clone)]
+pub struct MatchCandidate {
+    pub item_id: i64,
+    pub name: String,
+    pub shop_name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemMatch {
+    pub item_id: i64,
+    /// 0.0–1.0
+    pub score: f64,
+    pub reason: String,
+}
+
+/// Minimum score for a match to be recorded.
+pub const MATCH_THRESHOLD: f64 = 0.6;
+
+/// Words that appear in many unrelated item names and carry no signal on their own.
+const STOPWORDS: &[&str] = &[
+    "for",
+    "the",
+    "and",
+    "ver",
+    "version",
+    "vrchat",
+    "vrc",
+    "unity",
+    "unitypackage",
+    "package",
+    "avatar",
+    "3d",
+    "free",
+    "set",
+    "full",
+    "data",
+    "zip",
+    "fbx",
+    "pc",
+    "quest",
+    "対応",
+    "向け",
+    "3d衣装",
+    "3dモデル",
+    "無料",
+];
+
+/// Lowercase, fold full-width ASCII to half-width and turn everything that is not a
+/// letter or digit (brackets, underscores, punctuation) into spaces.
+pub fn normalize(text: &str) -> String {
+    text.chars()
+        .map(|c| match c as u32 {
+            0xFF01..=0xFF5E => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            _ => c,
+        })
+        .flat_map(char::to_lowercase)
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect()
+}
+
+fn is_version_token(token: &str) -> bool {
+    let digits = token.strip_prefix('v').unwrap_or(token);
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+fn tokens(text: &str) -> Vec<String> {
+    normalize(text)
+        .split_whitespace()
+        .filter(|t| t.chars().count() >= 2)
+        .filter(|t| !is_version_token(t) && !STOPWORDS.contains(t))
+        .map(str::to_string)
+        .collect()
+}
+
+fn compact(text: &str) -> String {
+    normalize(text).split_whitespace().collect()
+}
+
+/// Drop `【...】`/`[...]` labels ("【3D衣装】", "[PB対応]") that describe the category
+/// rather than the item, unless nothing else is left.
+fn strip_labels(name: &str) -> String {
+    let mut out = String::new();
+    let mut depth = 0usize;
+    for c in name.chars() {
+        match c {
+            '【' | '[' | '［' => depth += 1,
+            '】' | ']' | '］' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => out.push(c),
+            _ => {}
+        }
+    }
+    if out.trim().is_empty() {
+        name.to_string()
+    } else {
+        out
+    }
+}
+
+fn file_stem(file_name: &str) -> &str {
+    file_name
+        .rsplit_once('.')
+        .map(|(stem, _)| stem)
+        .unwrap_or(file_name)
+}
+
+fn score_candidate(
+    file_compact: &str,
+    file_tokens: &HashSet<String>,
+    c: &MatchCandidate,
+) -> Option<ItemMatch> {
+    let name = strip_labels(&c.name);
+    let name_compact = compact(&name);
+
+    let (mut score, mut reason) =
+        if name_compact.chars().count() >= 4 && file_compact.contains(&name_compact) {
+            (0.9, "name".to_string())
+        } else {
+            let name_tokens = tokens(&name);
+            if name_tokens.is_empty() {
+                return None;
+            }
+            let matched = name_tokens
+                .iter()
+                .filter(|t| {
+                    file_tokens.contains(*t)
+                        || (t.chars().count() >= 3 && file_compact.contains(t.as_str()))
+                })
+                .count();
+            let ratio = matched as f64 / name_tokens.len() as f64;
+            // A single shared word ("Kipfel") says more about the avatar than the item;
+            // single-word names are covered by the substring check above
+            if matched < 2 {
+                return None;
+            }
+            (0.3 + 0.6 * ratio, "name words".to_string())
+        };
+
+    if let Some(shop) = c.shop_name.as_deref() {
+        let shop_compact = compact(shop);
+        if shop_compact.chars().count() >= 3 && file_compact.contains(&shop_compact) {
+            score += 0.15;
+            reason.push_str(" + shop");
+        }
+    }
+
+    Some(ItemMatch {
+        item_id: c.item_id,
+        score: score.min(1.0),
+        reason,
+    })
+}
+
+/// Pick the item a file most likely belongs to, or `None` when nothing clears
+/// [`MATCH_THRESHOLD`] or two different items score the same.
+pub fn best_match(file_name: &str, candidates: &[MatchCandidate]) -> Option<ItemMatch> {
+    let stem = file_stem(file_name);
+
+    // An explicit item ID ("5012345_coat.zip") is unambiguous
+    let numbers: HashSet<i64> = stem
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|n| (5..=9).contains(&n.len()))
+        .filter_map(|n| n.parse().ok())
+        .collect();
+    if let Some(c) = candidates.iter().find(|c| numbers.contains(&c.item_id)) {
+        return Some(ItemMatch {
+            item_id: c.item_id,
+            score: 1.0,
+            reason: "item id".to_string(),
+        });
+    }
+
+    let file_compact = compact(stem);
+    let file_tokens: HashSet<String> = tokens(stem).into_iter().collect();
+
+    let mut best: Option<ItemMatch> = None;
+    let mut tied = false;
+    for candidate in candidates {
+        let Some(m) = score_candidate(&file_compact, &file_tokens, candidate) else {
+            continue;
+        };
+        match &best {
+            Some(b) if (m.score - b.score).abs() < f64::EPSILON => {
+                tied |= b.item_id != m.item_id;
+            }
+            Some(b) if m.score < b.score => {}
+            _ => {
+                tied = false;
+                best = Some(m);
+            }
+        }
+    }
+
+    best.filter(|b| !tied && b.score >= MATCH_THRESHOLD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(item_id: i64, name: &str, shop: Option<&str>) -> MatchCandidate {
+        MatchCandidate {
+            item_id,
+            name: name.to_string(),
+            shop_name: shop.map(str::to_string),
+        }
+    }
+
+    fn candidates() -> Vec<MatchCandidate> {
+        vec![
+            candidate(
+                5012345,
+                "【3D衣装】ニットセーター",
+                Some("もちもちショップ"),
+            ),
+            candidate(4800001, "Knit Sweater for Kipfel", Some("KnitWorks")),
+            candidate(3999999, "Shader Pack", None),
+            candidate(6000001, "Kipfel Hair", None),
+        ]
+    }
+
+    #[test]
+    fn explicit_item_id_wins() {
+        let m = best_match("3999999_whatever.zip", &candidates()).unwrap();
+        assert_eq!((m.item_id, m.reason.as_str()), (3999999, "item id"));
+    }
+
+    #[test]
+    fn matches_item_name_inside_file_name() {
+        let m = best_match("ニットセーター_v1.2.unitypackage", &candidates()).unwrap();
+        assert_eq!(m.item_id, 5012345);
+        let m = best_match("ShaderPack_ver2.0.zip", &candidates()).unwrap();
+        assert_eq!(m.item_id, 3999999);
+    }
+
+    #[test]
+    fn matches_on_word_overlap_with_shop_bonus() {
+        let m = best_match("KnitWorks_Kipfel_KnitSweater.7z", &candidates()).unwrap();
+        assert_eq!(m.item_id, 4800001);
+        assert!(m.reason.ends_with("+ shop"));
+    }
+
+    #[test]
+    fn rejects_generic_or_ambiguous_names() {
+        assert_eq!(best_match("VRChat_Avatar_v1.zip", &candidates()), None);
+        assert_eq!(best_match("Kipfel.zip", &candidates()), None);
+    }
+
+    #[test]
+    fn normalize_folds_full_width_characters() {
+        assert_eq!(normalize("ＫＮＩＴ_Ｖ１"), "knit v1");
+    }
+}
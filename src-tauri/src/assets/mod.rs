@@ -0,0 +1,3 @@
+pub mod matcher;
+pub mod scanner;
+pub mod unitypackage;
@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Archive types Booth items are distributed as.
+pub const ASSET_EXTENSIONS: &[&str] = &["zip", "unitypackage", "7z"];
+
+/// Guards against accidentally scanning a whole drive.
+const MAX_DEPTH: usize = 8;
+const MAX_FILES: usize = 20_000;
+
+#[derive(Debug, Clone)]
+pub struct FoundFile {
+    pub path: PathBuf,
+    pub file_name: String,
+    pub size_bytes: u64,
+    /// Unix seconds
+    pub modified: Option<i64>,
+}
+
+/// What a scan found, and what it could not look at.
+#[derive(Debug, Default)]
+pub struct DirectoryScan {
+    pub files: Vec<FoundFile>,
+    /// Directories that were not listed: unreadable, or below the depth limit
+    pub skipped: Vec<PathBuf>,
+    /// False when the scan stopped at the file limit, so any directory may be partial
+    pub complete: bool,
+}
+
+pub fn is_asset_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| ASSET_EXTENSIONS.iter().any(|x| x.eq_ignore_ascii_case(e)))
+        .unwrap_or(false)
+}
+
+/// Recursively collect asset archives under `root`, skipping hidden directories.
+/// Unreadable directories are skipped rather than failing the whole scan, and reported
+/// in `skipped` so their files are not taken for deleted.
+pub fn scan_directory(root: &Path) -> DirectoryScan {
+    let mut scan = DirectoryScan {
+        complete: true,
+        ..Default::default()
+    };
+    let mut stack = vec![(root.to_path_buf(), 0usize)];
+    while let Some((dir, depth)) = stack.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("Skipping unreadable directory {}: {}", dir.display(), e);
+                scan.skipped.push(dir);
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if file_type.is_dir() {
+                if name.starts_with('.') {
+                    continue;
+                }
+                if depth < MAX_DEPTH {
+                    stack.push((path, depth + 1));
+                } else {
+                    scan.skipped.push(path);
+                }
+            } else if file_type.is_file() && is_asset_file(&path) {
+                let meta = entry.metadata().ok();
+                scan.files.push(FoundFile {
+                    file_name: name,
+                    size_bytes: meta.as_ref().map(|m| m.len()).unwrap_or(0),
+                    modified: meta
+                        .and_then(|m| m.modified().ok())
+                        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                        .map(|d| d.as_secs() as i64),
+                    path,
+                });
+                if scan.files.len() >= MAX_FILES {
+                    log::warn!(
+                        "Stopped scanning {} after {} files",
+                        root.display(),
+                        MAX_FILES
+                    );
+                    scan.complete = false;
+                    return scan;
+                }
+            }
+        }
+    }
+    scan
+}
@@ -0,0 +1,241 @@
+//! Reads the asset list out of `.unitypackage` files without importing them into Unity.
+//!
+//! A unitypackage is a gzipped tarball with one directory per asset GUID; each directory
+//! holds an `asset` blob, its `asset.meta` and a `pathname` file containing the project
+//! path the asset is imported to (`Assets/Author/Item/Prefab.prefab`). Only the
+//! `pathname` entries are kept, so memory stays small, but the whole gzip stream still
+//! has to be decompressed to reach them: inspecting a multi-GB package takes a while.
+
+use std::collections::BTreeSet;
+use std::io::Read;
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+
+/// `pathname` files are a single line; anything larger is not a pathname.
+const MAX_PATHNAME_BYTES: u64 = 4096;
+const MAX_ASSETS: usize = 100_000;
+
+/// Avatar bases commonly targeted by Booth outfits, with the spellings used in folder names.
+const KNOWN_AVATARS: &[(&str, &[&str])] = &[
+    ("Airi", &["airi", "愛莉"]),
+    ("Chiffon", &["chiffon", "シフォン"]),
+    ("Grus", &["grus", "グルス"]),
+    ("Hakka", &["hakka", "薄荷"]),
+    ("Imeris", &["imeris", "イメリス"]),
+    ("Karin", &["karin", "カリン"]),
+    ("Kikyo", &["kikyo", "桔梗"]),
+    ("Kipfel", &["kipfel", "キプフェル"]),
+    ("Lasyusha", &["lasyusha", "ラシューシャ"]),
+    ("Lime", &["lime", "ライム"]),
+    ("Manuka", &["manuka", "マヌカ"]),
+    ("Mafuyu", &["mafuyu", "真冬"]),
+    ("Maya", &["maya", "舞夜"]),
+    ("Milltina", &["milltina", "ミルティナ"]),
+    ("Mizuki", &["mizuki", "瑞希"]),
+    ("Moe", &["moe", "萌"]),
+    ("Rindo", &["rindo", "竜胆"]),
+    ("Rusk", &["rusk", "ラスク"]),
+    ("Selestia", &["selestia", "セレスティア"]),
+    ("Shinano", &["shinano", "しなの"]),
+    ("Sio", &["sio", "しお"]),
+    ("Sophina", &["sophina", "ソフィナ"]),
+    ("Uzuki", &["uzuki", "卯月"]),
+];
+
+/// Shaders whose presence (bundled copy or folder/material naming) marks a dependency.
+const KNOWN_SHADERS: &[(&str, &[&str])] = &[
+    ("lilToon", &["liltoon"]),
+    ("Poiyomi", &["poiyomi"]),
+    ("UnlitWF", &["unlitwf"]),
+    ("UTS2", &["unitychantoonshader", "uts2"]),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PackageAnalysis {
+    pub asset_count: usize,
+    pub avatars: Vec<String>,
+    pub shaders: Vec<String>,
+    pub has_prefab: bool,
+    pub prefab_paths: Vec<String>,
+}
+
+/// Project paths of all assets in a unitypackage read from `reader` (gzip-compressed).
+pub fn read_asset_paths<R: Read>(reader: R) -> AppResult<Vec<String>> {
+    let mut archive = tar::Archive::new(GzDecoder::new(reader));
+    let entries = archive
+        .entries()
+        .map_err(|e| AppError::ParseError(format!("Not a unitypackage: {}", e)))?;
+
+    let mut paths = Vec::new();
+    for entry in entries {
+        let entry =
+            entry.map_err(|e| AppError::ParseError(format!("Corrupt unitypackage: {}", e)))?;
+        let is_pathname = entry
+            .path()
+            .map(|p| p.file_name().is_some_and(|n| n == "pathname"))
+            .unwrap_or(false);
+        if !is_pathname || entry.size() > MAX_PATHNAME_BYTES {
+            continue;
+        }
+        let mut content = String::new();
+        entry
+            .take(MAX_PATHNAME_BYTES)
+            .read_to_string(&mut content)
+            .map_err(|e| AppError::ParseError(format!("Corrupt unitypackage: {}", e)))?;
+        // Older exporters append a second "00" line
+        if let Some(path) = content
+            .lines()
+            .next()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+        {
+            paths.push(path.to_string());
+        }
+        if paths.len() >= MAX_ASSETS {
+            break;
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+pub fn read_package_file(path: &Path) -> AppResult<Vec<String>> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| AppError::NotFound(format!("{}: {}", path.display(), e)))?;
+    read_asset_paths(std::io::BufReader::new(file))
+}
+
+fn path_words(path: &str) -> impl Iterator<Item = String> + '_ {
+    path.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+}
+
+/// Detect target avatars, shader dependencies and prefabs from asset paths.
+pub fn analyze_paths(paths: &[String]) -> PackageAnalysis {
+    let mut avatars = BTreeSet::new();
+    let mut shaders = BTreeSet::new();
+    let mut prefab_paths = Vec::new();
+
+    for path in paths {
+        let lower = path.to_lowercase();
+        let words: Vec<String> = path_words(path).collect();
+        for (name, aliases) in KNOWN_AVATARS {
+            // Names must be whole segments: "Lime" in "Sublime" is not the avatar, nor is
+            // 萌 in 萌え袖 or 真冬 in 真冬のコート. Japanese folders often read "<avatar>用"
+            // or "<avatar>対応" (for <avatar>), so those suffixes are allowed.
+            let hit = aliases.iter().any(|alias| {
+                words.iter().any(|w| {
+                    w == alias
+                        || (!alias.is_ascii()
+                            && w.strip_prefix(alias)
+                                .is_some_and(|rest| rest == "用" || rest == "対応"))
+                })
+            });
+            if hit {
+                avatars.insert(name.to_string());
+            }
+        }
+        for (name, markers) in KNOWN_SHADERS {
+            if markers.iter().any(|m| lower.contains(m)) {
+                shaders.insert(name.to_string());
+            }
+        }
+        if lower.ends_with(".prefab") {
+            prefab_paths.push(path.clone());
+        }
+    }
+
+    PackageAnalysis {
+        asset_count: paths.len(),
+        avatars: avatars.into_iter().collect(),
+        shaders: shaders.into_iter().collect(),
+        has_prefab: !prefab_paths.is_empty(),
+        prefab_paths,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    fn build_package(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::fast()));
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn reads_pathname_entries_only() {
+        let bytes = build_package(&[
+            (
+                "0a1b/pathname",
+                "Assets/Shop/Coat/Kipfel/Coat_Kipfel.prefab\n00",
+            ),
+            ("0a1b/asset", "binary"),
+            ("0a1b/asset.meta", "guid: 0a1b"),
+            ("9f8e/pathname", "Assets/Shop/Coat/Textures/coat.png"),
+        ]);
+        assert_eq!(
+            read_asset_paths(bytes.as_slice()).unwrap(),
+            vec![
+                "Assets/Shop/Coat/Kipfel/Coat_Kipfel.prefab".to_string(),
+                "Assets/Shop/Coat/Textures/coat.png".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_non_gzip_input() {
+        assert!(read_asset_paths(b"PK\x03\x04 not a tarball".as_slice()).is_err());
+    }
+
+    #[test]
+    fn detects_avatars_shaders_and_prefabs() {
+        let paths: Vec<String> = [
+            "Assets/Shop/Coat/For_Kipfel/Coat.prefab",
+            "Assets/Shop/Coat/桔梗/Coat.fbx",
+            "Assets/Shop/Coat/Materials/lilToon/coat.mat",
+            "Assets/_PoiyomiShaders/Shaders/Poiyomi.shader",
+            "Assets/Shop/Sublime/readme.txt",
+        ]
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+        let analysis = analyze_paths(&paths);
+        assert_eq!(analysis.asset_count, 5);
+        assert_eq!(analysis.avatars, vec!["Kikyo", "Kipfel"]);
+        assert_eq!(analysis.shaders, vec!["Poiyomi", "lilToon"]);
+        assert!(analysis.has_prefab);
+        assert_eq!(analysis.prefab_paths.len(), 1);
+    }
+
+    #[test]
+    fn japanese_names_must_be_whole_segments() {
+        let paths: Vec<String> = [
+            "Assets/Shop/萌え袖パーカー/Hoodie.fbx",
+            "Assets/Shop/真冬のコート/Coat.fbx",
+            "Assets/Shop/しおり/Bookmark.fbx",
+            "Assets/Shop/Hoodie/【しなの】/Hoodie.prefab",
+            "Assets/Shop/Hoodie/瑞希用/Hoodie.prefab",
+        ]
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+        assert_eq!(analyze_paths(&paths).avatars, vec!["Mizuki", "Shinano"]);
+    }
+}
@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use tauri_plugin_http::reqwest::{self, StatusCode};
+
+use super::models::{BoothItem, BoothJsonItemDetail, ItemAvailability};
+use crate::error::{AppError, AppResult};
+
+/// Same browser UA the frontend sends; Booth rejects requests without one.
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Default delay between consecutive requests; the `request_interval_ms` setting overrides it.
+pub const RATE_LIMIT: Duration = Duration::from_millis(1000);
+
+/// Markers of Booth's age confirmation interstitial, served instead of the item for R-18 pages.
+const ADULT_GATE_MARKERS: &[&str] = &["adult_confirmation", "age_confirmation", "年齢確認"];
+
+/// Result of fetching a single item: either the item itself or why it can no longer be viewed.
+#[derive(Debug)]
+pub enum ItemFetch {
+    Found(Box<BoothItem>),
+    Unavailable(ItemAvailability),
+}
+
+pub struct BoothClient {
+    http: reqwest::Client,
+}
+
+impl BoothClient {
+    pub fn new() -> AppResult<Self> {
+        let http = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| AppError::Network(format!("Failed to create HTTP client: {}", e)))?;
+        Ok(Self { http })
+    }
+
+    pub async fn fetch_item(&self, item_id: i64) -> AppResult<ItemFetch> {
+        let url = format!("https://booth.pm/ja/items/{}.json", item_id);
+        let resp = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| AppError::Network(e.to_string()))?;
+
+        match resp.status() {
+            StatusCode::NOT_FOUND | StatusCode::GONE => {
+                return Ok(ItemFetch::Unavailable(ItemAvailability::Deleted))
+            }
+            StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => {
+                return Ok(ItemFetch::Unavailable(ItemAvailability::Private))
+            }
+            StatusCode::TOO_MANY_REQUESTS => {
+                return Err(AppError::Network("Rate limited by Booth.pm".to_string()))
+            }
+            status if !status.is_success() => {
+                return Err(AppError::Network(format!(
+                    "Item {} returned {}",
+                    item_id, status
+                )))
+            }
+            _ => {}
+        }
+
+        // Redirects are followed, so an age gate shows up as a successful HTML response
+        let final_url = resp.url().to_string();
+        let body = resp
+            .text()
+            .await
+            .map_err(|e| AppError::Network(e.to_string()))?;
+        if is_adult_gate(&final_url, &body) {
+            return Ok(ItemFetch::Unavailable(ItemAvailability::AdultGated));
+        }
+        let data: BoothJsonItemDetail = serde_json::from_str(&body).map_err(|e| {
+            AppError::ParseError(format!("Invalid item JSON for {}: {}", item_id, e))
+        })?;
+        Ok(ItemFetch::Found(Box::new(data.into())))
+    }
+
+    /// HTML of one page of a shop's item listing (`page` starts at 1).
+    pub async fn fetch_shop_page(&self, subdomain: &str, page: u32) -> AppResult<String> {
+        let url = format!("https://{}.booth.pm/items?page={}", subdomain, page);
+        let resp = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| AppError::Network(e.to_string()))?;
+        match resp.status() {
+            StatusCode::NOT_FOUND => return Err(AppError::NotFound(format!("Shop {}", subdomain))),
+            StatusCode::TOO_MANY_REQUESTS => {
+                return Err(AppError::Network("Rate limited by Booth.pm".to_string()))
+            }
+            status if !status.is_success() => {
+                return Err(AppError::Network(format!(
+                    "Shop {} returned {}",
+                    subdomain, status
+                )))
+            }
+            _ => {}
+        }
+        resp.text()
+            .await
+            .map_err(|e| AppError::Network(e.to_string()))
+    }
+}
+
+fn is_adult_gate(final_url: &str, body: &str) -> bool {
+    let path = final_url.split('?').next().unwrap_or_default();
+    if !path.ends_with(".json") && path.contains("adult") {
+        return true;
+    }
+    let trimmed = body.trim_start();
+    !trimmed.starts_with('{') && ADULT_GATE_MARKERS.iter().any(|m| body.contains(m))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_age_gate_pages() {
+        assert!(is_adult_gate(
+            "https://booth.pm/ja/adult_confirmation?return_to=%2Fja%2Fitems%2F1.json",
+            ""
+        ));
+        assert!(is_adult_gate(
+            "https://booth.pm/ja/items/1.json",
+            "<html><title>年齢確認</title></html>"
+        ));
+        assert!(!is_adult_gate(
+            "https://booth.pm/ja/items/1.json",
+            r#"{"id":1,"description":"年齢確認 not required"}"#
+        ));
+    }
+}
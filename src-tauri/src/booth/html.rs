@@ -0,0 +1,24 @@
+//! Text helpers shared by the parsers that scan Booth pages without a DOM.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// Any tag, including ones spanning lines.
+pub(crate) fn tag_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?s)<[^>]*>").unwrap())
+}
+
+/// Decode the entities Booth pages and emails use. `&amp;` goes last so `&amp;lt;` stays
+/// `&lt;`.
+pub(crate) fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&yen;", "¥")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&amp;", "&")
+}
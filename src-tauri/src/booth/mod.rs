@@ -1 +1,5 @@
+pub mod client;
+pub mod html;
 pub mod models;
+pub mod purchase_parser;
+pub mod shop_parser;
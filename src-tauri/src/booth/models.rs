@@ -1,5 +1,14 @@
 use serde::{Deserialize, Serialize};
 
+/// Title, tag and category markers creators use for R-18 items (ASCII ones match
+/// case-insensitively).
+pub const ADULT_MARKERS: &[&str] = &["R-18", "R18", "18禁", "成人向け", "アダルト"];
+
+pub fn has_adult_marker(text: &str) -> bool {
+    let upper = text.to_ascii_uppercase();
+    ADULT_MARKERS.iter().any(|m| upper.contains(m))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BoothItem {
     pub id: i64,
@@ -13,4 +22,356 @@ pub struct BoothItem {
     pub tags: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub wish_lists_count: Option<i64>,
+    /// Purchasable variations; empty for single-price items and rows cached before they existed
+    #[serde(default)]
+    pub variations: Vec<ItemVariation>,
+    /// R-18 item
+    #[serde(default)]
+    pub is_adult: bool,
+    pub published_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub shop_subdomain: Option<String>,
+    pub shop_thumbnail_url: Option<String>,
+    /// Booth event the item is sold at, if any
+    pub event_name: Option<String>,
+    #[serde(default)]
+    pub is_sold_out: bool,
+    /// The creator has ended sales, though the page is still up
+    #[serde(default)]
+    pub is_end_of_sale: bool,
+    /// Set by the content policy on R-18 items the UI should blur
+    #[serde(default)]
+    pub blurred: bool,
+}
+
+impl BoothItem {
+    /// Booth's adult flag, or an adult marker in the name, tags or category.
+    pub fn looks_adult(&self) -> bool {
+        self.is_adult
+            || has_adult_marker(&self.name)
+            || self.tags.iter().any(|t| has_adult_marker(t))
+            || self.category_name.as_deref().is_some_and(has_adult_marker)
+    }
+
+    /// Cheapest variation, or `price` when the item has none.
+    pub fn min_price(&self) -> i64 {
+        self.variations
+            .iter()
+            .map(|v| v.price)
+            .min()
+            .unwrap_or(self.price)
+    }
+
+    pub fn max_price(&self) -> i64 {
+        self.variations
+            .iter()
+            .map(|v| v.price)
+            .max()
+            .unwrap_or(self.price)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VariationType {
+    Download,
+    /// Shipped by the shop, pixiv FACTORY or sold at events
+    Physical,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemVariation {
+    pub name: String,
+    pub price: i64,
+    #[serde(rename = "type")]
+    pub variation_type: VariationType,
+    /// Remaining stock when Booth shows it (`0` when sold out); `None` means no stock limit
+    pub stock: Option<i64>,
+}
+
+/// Whether a Booth item can still be viewed, as observed on the last fetch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemAvailability {
+    Available,
+    /// The item page returns 404 (taken down or removed by the creator)
+    Deleted,
+    /// The item exists but is hidden from the public
+    Private,
+    /// Booth serves its R-18 age confirmation page instead of the item
+    AdultGated,
+}
+
+impl ItemAvailability {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ItemAvailability::Available => "available",
+            ItemAvailability::Deleted => "deleted",
+            ItemAvailability::Private => "private",
+            ItemAvailability::AdultGated => "adult_gated",
+        }
+    }
+
+    /// Unknown values (e.g. from a newer app version) are treated as available.
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "deleted" => ItemAvailability::Deleted,
+            "private" => ItemAvailability::Private,
+            "adult_gated" => ItemAvailability::AdultGated,
+            _ => ItemAvailability::Available,
+        }
+    }
+}
+
+// ── Booth item JSON (`https://booth.pm/ja/items/<id>.json`) ──
+
+#[derive(Debug, Deserialize)]
+pub struct BoothJsonItemDetail {
+    pub id: i64,
+    #[serde(default)]
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub price: serde_json::Value,
+    pub category: Option<BoothJsonNamed>,
+    pub shop: Option<BoothJsonShop>,
+    pub url: Option<String>,
+    #[serde(default)]
+    pub images: Vec<BoothJsonImage>,
+    #[serde(default)]
+    pub tags: Vec<BoothJsonNamed>,
+    pub wish_lists_count: Option<i64>,
+    #[serde(default)]
+    pub variations: Vec<BoothJsonVariation>,
+    pub is_adult: Option<bool>,
+    pub published_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub event: Option<BoothJsonNamed>,
+    pub is_sold_out: Option<bool>,
+    pub is_end_of_sale: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BoothJsonShop {
+    pub name: Option<String>,
+    pub subdomain: Option<String>,
+    pub thumbnail_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BoothJsonVariation {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub price: serde_json::Value,
+    /// `digital` for downloads; `direct`, `factory` or `event` for physical goods
+    #[serde(rename = "type")]
+    pub variation_type: Option<String>,
+    pub status: Option<String>,
+    pub is_empty_stock: Option<bool>,
+    pub small_stock: Option<i64>,
+}
+
+impl From<BoothJsonVariation> for ItemVariation {
+    fn from(data: BoothJsonVariation) -> Self {
+        let sold_out =
+            data.is_empty_stock == Some(true) || data.status.as_deref() == Some("soldout");
+        ItemVariation {
+            name: data.name.unwrap_or_default(),
+            price: parse_price(&data.price),
+            variation_type: match data.variation_type.as_deref() {
+                Some("digital") | None => VariationType::Download,
+                Some(_) => VariationType::Physical,
+            },
+            stock: if sold_out { Some(0) } else { data.small_stock },
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BoothJsonNamed {
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BoothJsonImage {
+    pub original: Option<String>,
+    pub resized: Option<String>,
+}
+
+/// Booth sends the price either as a number or as display text like "¥ 1,500".
+fn parse_price(value: &serde_json::Value) -> i64 {
+    match value {
+        serde_json::Value::Number(n) => n.as_i64().unwrap_or(0),
+        serde_json::Value::String(s) => {
+            let digits: String = s.chars().filter(|c| c.is_ascii_digit()).collect();
+            digits.parse().unwrap_or(0)
+        }
+        _ => 0,
+    }
+}
+
+impl From<BoothJsonItemDetail> for BoothItem {
+    fn from(data: BoothJsonItemDetail) -> Self {
+        let variations: Vec<ItemVariation> = data.variations.into_iter().map(Into::into).collect();
+        // The top-level price is display text ("¥ 500~") when variations differ
+        let price = variations
+            .iter()
+            .map(|v| v.price)
+            .min()
+            .unwrap_or_else(|| parse_price(&data.price));
+        let non_empty = |v: Option<String>| v.filter(|v| !v.is_empty());
+        let shop = data.shop.unwrap_or(BoothJsonShop {
+            name: None,
+            subdomain: None,
+            thumbnail_url: None,
+        });
+        BoothItem {
+            id: data.id,
+            name: data.name,
+            description: data.description.filter(|d| !d.is_empty()),
+            price,
+            category_name: data.category.and_then(|c| c.name).filter(|n| !n.is_empty()),
+            shop_name: non_empty(shop.name),
+            url: data
+                .url
+                .filter(|u| !u.is_empty())
+                .unwrap_or_else(|| format!("https://booth.pm/ja/items/{}", data.id)),
+            images: data
+                .images
+                .into_iter()
+                .filter_map(|img| img.original.or(img.resized))
+                .collect(),
+            tags: data.tags.into_iter().filter_map(|t| t.name).collect(),
+            wish_lists_count: data.wish_lists_count,
+            variations,
+            is_adult: data.is_adult.unwrap_or(false),
+            published_at: non_empty(data.published_at),
+            updated_at: non_empty(data.updated_at),
+            shop_subdomain: non_empty(shop.subdomain),
+            shop_thumbnail_url: non_empty(shop.thumbnail_url),
+            event_name: non_empty(data.event.and_then(|e| e.name)),
+            is_sold_out: data.is_sold_out.unwrap_or(false),
+            is_end_of_sale: data.is_end_of_sale.unwrap_or(false),
+            blurred: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_item_detail_converts_to_booth_item() {
+        let json = r#"{
+            "id": 123,
+            "name": "Outfit",
+            "description": "",
+            "price": "¥ 1,500",
+            "category": { "name": "3D衣装" },
+            "url": "https://shop.booth.pm/items/123",
+            "images": [{ "original": "a.png", "resized": "a_s.png" }, { "resized": "b_s.png" }],
+            "tags": [{ "name": "VRChat" }],
+            "wish_lists_count": 42,
+            "is_adult": true,
+            "published_at": "2024-05-01T12:00:00.000+09:00",
+            "shop": { "name": "Shop", "subdomain": "shop", "thumbnail_url": "" },
+            "event": { "name": "Vket" },
+            "is_end_of_sale": true
+        }"#;
+        let item: BoothItem = serde_json::from_str::<BoothJsonItemDetail>(json)
+            .unwrap()
+            .into();
+        assert_eq!(item.price, 1500);
+        assert_eq!(item.description, None);
+        assert_eq!(item.category_name.as_deref(), Some("3D衣装"));
+        assert_eq!(item.images, vec!["a.png", "b_s.png"]);
+        assert_eq!(item.tags, vec!["VRChat"]);
+        assert_eq!(item.wish_lists_count, Some(42));
+        assert!(item.is_adult && item.is_end_of_sale && !item.is_sold_out);
+        assert_eq!(item.shop_name.as_deref(), Some("Shop"));
+        assert_eq!(item.shop_subdomain.as_deref(), Some("shop"));
+        assert_eq!(item.shop_thumbnail_url, None);
+        assert_eq!(item.event_name.as_deref(), Some("Vket"));
+        assert!(item.published_at.is_some() && item.updated_at.is_none());
+    }
+
+    #[test]
+    fn json_item_detail_fills_missing_fields() {
+        let item: BoothItem =
+            serde_json::from_str::<BoothJsonItemDetail>(r#"{"id": 7, "price": 0}"#)
+                .unwrap()
+                .into();
+        assert_eq!(item.url, "https://booth.pm/ja/items/7");
+        assert_eq!(item.price, 0);
+        assert!(item.images.is_empty());
+        assert_eq!(item.shop_name, None);
+    }
+
+    #[test]
+    fn variations_set_the_price_range() {
+        let json = r#"{
+            "id": 9,
+            "price": "¥ 500~",
+            "variations": [
+                { "name": "Full set", "price": 3000, "type": "digital", "status": "on_sale" },
+                { "name": "Single", "price": 500, "type": "digital" },
+                { "name": "Acrylic stand", "price": 1200, "type": "direct", "is_empty_stock": true },
+                { "name": "Limited", "price": 2000, "type": "direct", "small_stock": 3 }
+            ]
+        }"#;
+        let item: BoothItem = serde_json::from_str::<BoothJsonItemDetail>(json)
+            .unwrap()
+            .into();
+        assert_eq!(
+            (item.price, item.min_price(), item.max_price()),
+            (500, 500, 3000)
+        );
+        assert_eq!(item.variations[2].variation_type, VariationType::Physical);
+        assert_eq!(item.variations[2].stock, Some(0));
+        assert_eq!(item.variations[3].stock, Some(3));
+        assert_eq!(item.variations[0].stock, None);
+    }
+
+    #[test]
+    fn items_serialized_by_older_versions_still_deserialize() {
+        let item: BoothItem = serde_json::from_str(
+            r#"{"id": 1, "name": "a", "description": null, "price": 800, "category_name": null,
+                "shop_name": null, "url": "u", "images": [], "tags": []}"#,
+        )
+        .unwrap();
+        assert!(item.variations.is_empty());
+        assert_eq!((item.min_price(), item.max_price()), (800, 800));
+        assert!(!item.is_adult);
+        assert_eq!(item.published_at, None);
+    }
+
+    #[test]
+    fn adult_markers_match_titles_and_tags() {
+        assert!(has_adult_marker("Outfit [r-18 ver.]"));
+        assert!(has_adult_marker("成人向けセット"));
+        assert!(!has_adult_marker("Summer outfit"));
+        let mut item: BoothItem = serde_json::from_str::<BoothJsonItemDetail>(r#"{"id": 1}"#)
+            .unwrap()
+            .into();
+        assert!(!item.looks_adult());
+        item.tags.push("R18".to_string());
+        assert!(item.looks_adult());
+    }
+
+    #[test]
+    fn availability_round_trips_through_db_strings() {
+        for a in [
+            ItemAvailability::Available,
+            ItemAvailability::Deleted,
+            ItemAvailability::Private,
+            ItemAvailability::AdultGated,
+        ] {
+            assert_eq!(ItemAvailability::from_db(a.as_str()), a);
+        }
+        assert_eq!(
+            ItemAvailability::from_db("unknown"),
+            ItemAvailability::Available
+        );
+    }
 }
@@ -0,0 +1,300 @@
+//! Offline parser for Booth purchase records the user saved locally: the library page,
+//! order history pages and order confirmation emails (HTML or plain text).
+//!
+//! The documents are scanned as a flat token stream rather than a DOM, so the same pass
+//! handles both markup and emails: dates set the purchase date of the items that follow,
+//! item links start a record, and the first price after an item link becomes its price.
+//! Prices labelled as totals, subtotals or shipping belong to the order and are skipped.
+
+use std::sync::OnceLock;
+
+use regex::{Captures, Regex};
+use serde::Serialize;
+
+use super::html::{decode_entities, tag_re};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParsedPurchase {
+    pub item_id: i64,
+    pub name: Option<String>,
+    /// `YYYY-MM-DD`
+    pub purchased_at: Option<String>,
+    pub price_paid: Option<i64>,
+}
+
+const ITEM_MARK: char = '\u{1}';
+const NAME_MARK: char = '\u{2}';
+const MAX_PRICE: i64 = 10_000_000;
+
+fn anchor_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"(?is)<a\s[^>]*?href\s*=\s*["']([^"']*)["'][^>]*>(.*?)</a\s*>"#).unwrap()
+    })
+}
+
+fn skipped_block_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?is)<script\b.*?</script\s*>|<style\b.*?</style\s*>|<!--.*?-->").unwrap()
+    })
+}
+
+fn block_break_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)<br\s*/?>|</(?:p|div|li|tr|h\d)\s*>").unwrap())
+}
+
+fn item_url_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"https?://(?:[a-z0-9-]+\.)?booth\.pm/(?:[a-z]{2}(?:-[a-zA-Z]+)?/)?items/(\d+)")
+            .unwrap()
+    })
+}
+
+fn token_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(concat!(
+            r"\x{1}(?P<mid>\d+)\x{2}(?P<mname>[^\x{1}]*)\x{1}",
+            r"|https?://(?:[a-z0-9-]+\.)?booth\.pm/(?:[a-z]{2}(?:-[a-zA-Z]+)?/)?items/(?P<uid>\d+)",
+            r"|(?P<y>\d{4})\s*[/\-.年]\s*(?P<m>\d{1,2})\s*[/\-.月]\s*(?P<d>\d{1,2})",
+            r"|(?P<total>お支払い?金額|お支払い?合計|合計金額|合計|小計|送料|(?i:sub\s*total|total|shipping))",
+            r"\s*(?:[(（][^)）]*[)）])?\s*[:：]?\s*(?:[¥￥]\s*\d[\d,]*|\d[\d,]*\s*円)",
+            r"|[¥￥]\s*(?P<p1>\d[\d,]*)|(?P<p2>\d[\d,]*)\s*円",
+            r"|商品名\s*[:：]\s*(?P<label>[^\r\n]+)",
+        ))
+        .unwrap()
+    })
+}
+
+/// Strip tags and collapse whitespace. Entities stay encoded until the final pass so
+/// decoded `<`/`>` are not mistaken for tags.
+fn strip_tags(html: &str) -> String {
+    let stripped = tag_re().replace_all(html, " ");
+    stripped.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Flatten HTML to text, replacing links to items with `\x01<id>\x02<link text>\x01`
+/// markers so the link text survives as the item name.
+fn flatten_html(html: &str) -> String {
+    let without_blocks = skipped_block_re().replace_all(html, " ");
+    let marked = anchor_re().replace_all(&without_blocks, |caps: &Captures| {
+        let inner = strip_tags(&caps[2]);
+        match item_url_re().captures(&caps[1]) {
+            Some(url) => format!(
+                " {}{}{}{}{} ",
+                ITEM_MARK,
+                &url[1],
+                NAME_MARK,
+                inner.replace(ITEM_MARK, ""),
+                ITEM_MARK
+            ),
+            None => format!(" {} ", inner),
+        }
+    });
+    // Keep line breaks between block elements so label lines stay separate
+    let with_breaks = block_break_re().replace_all(&marked, "\n");
+    decode_entities(&tag_re().replace_all(&with_breaks, " "))
+}
+
+fn looks_like_html(content: &str) -> bool {
+    let head = content.get(..content.len().min(4096)).unwrap_or(content);
+    let lower = head.to_ascii_lowercase();
+    lower.contains("<html") || lower.contains("<!doctype") || lower.contains("</a>")
+}
+
+fn parse_price(digits: &str) -> Option<i64> {
+    digits
+        .replace(',', "")
+        .parse::<i64>()
+        .ok()
+        .filter(|p| (0..=MAX_PRICE).contains(p))
+}
+
+fn parse_date(caps: &Captures) -> Option<String> {
+    let y: u32 = caps.name("y")?.as_str().parse().ok()?;
+    let m: u32 = caps.name("m")?.as_str().parse().ok()?;
+    let d: u32 = caps.name("d")?.as_str().parse().ok()?;
+    if !(2000..=2100).contains(&y) || !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+    Some(format!("{:04}-{:02}-{:02}", y, m, d))
+}
+
+/// Extract purchases from a saved library page, order page or order email.
+pub fn parse_purchase_document(content: &str) -> Vec<ParsedPurchase> {
+    let text = if looks_like_html(content) {
+        flatten_html(content)
+    } else {
+        content.to_string()
+    };
+
+    let mut records: Vec<ParsedPurchase> = Vec::new();
+    let mut current_date: Option<String> = None;
+    let mut pending_name: Option<String> = None;
+
+    for caps in token_re().captures_iter(&text) {
+        let item = caps
+            .name("mid")
+            .map(|id| (id.as_str(), caps.name("mname").map(|n| n.as_str())))
+            .or_else(|| caps.name("uid").map(|id| (id.as_str(), None)));
+
+        if let Some((id, name)) = item {
+            let Ok(item_id) = id.parse::<i64>() else {
+                continue;
+            };
+            let name = name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+            // Thumbnail and title usually link to the same item back to back; once a price
+            // followed, another link is a second purchase (e.g. another variation)
+            if let Some(last) = records.last_mut() {
+                if last.item_id == item_id
+                    && last.purchased_at == current_date
+                    && last.price_paid.is_none()
+                {
+                    if last.name.is_none() {
+                        last.name = name.or_else(|| pending_name.take());
+                    }
+                    continue;
+                }
+            }
+            records.push(ParsedPurchase {
+                item_id,
+                name: name.or_else(|| pending_name.take()),
+                purchased_at: current_date.clone(),
+                price_paid: None,
+            });
+        } else if caps.name("y").is_some() {
+            if let Some(date) = parse_date(&caps) {
+                // A date printed after the items (e.g. email footers) still applies to them
+                for record in records.iter_mut().filter(|r| r.purchased_at.is_none()) {
+                    record.purchased_at = Some(date.clone());
+                }
+                current_date = Some(date);
+            }
+        } else if caps.name("total").is_some() {
+            // Order totals, subtotals and shipping are not the price of the last item
+        } else if let Some(digits) = caps.name("p1").or_else(|| caps.name("p2")) {
+            if let Some(last) = records.last_mut() {
+                if last.price_paid.is_none() {
+                    last.price_paid = parse_price(digits.as_str());
+                }
+            }
+        } else if let Some(label) = caps.name("label") {
+            let name = label.as_str().trim();
+            if !name.is_empty() {
+                pending_name = Some(name.to_string());
+            }
+        }
+    }
+
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn purchase(
+        item_id: i64,
+        name: Option<&str>,
+        purchased_at: Option<&str>,
+        price_paid: Option<i64>,
+    ) -> ParsedPurchase {
+        ParsedPurchase {
+            item_id,
+            name: name.map(str::to_string),
+            purchased_at: purchased_at.map(str::to_string),
+            price_paid,
+        }
+    }
+
+    #[test]
+    fn parses_order_history_page() {
+        let html = include_str!("../../tests/fixtures/purchases/order_history.html");
+        assert_eq!(
+            parse_purchase_document(html),
+            vec![
+                purchase(
+                    5012345,
+                    Some("【3D衣装】Winter Coat & Scarf"),
+                    Some("2024-01-05"),
+                    Some(2500)
+                ),
+                purchase(
+                    4800001,
+                    Some("Free Hair Accessory"),
+                    Some("2024-01-05"),
+                    Some(0)
+                ),
+                purchase(3999999, Some("Shader Pack"), Some("2023-12-24"), Some(1000)),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_library_page_without_dates_or_prices() {
+        let html = include_str!("../../tests/fixtures/purchases/library.html");
+        assert_eq!(
+            parse_purchase_document(html),
+            vec![
+                purchase(6000001, Some("Avatar Base Model"), None, None),
+                purchase(6000002, Some("Gesture Set <Free>"), None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_plain_text_order_email() {
+        let text = include_str!("../../tests/fixtures/purchases/order_email.txt");
+        assert_eq!(
+            parse_purchase_document(text),
+            vec![
+                purchase(
+                    5555555,
+                    Some("Knit Sweater for Kipfel"),
+                    Some("2024-03-09"),
+                    Some(1800)
+                ),
+                purchase(
+                    5555556,
+                    Some("Knit Sweater Texture Pack"),
+                    Some("2024-03-09"),
+                    Some(500)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_variations_bought_together_apart() {
+        let html = include_str!("../../tests/fixtures/purchases/order_variations.html");
+        assert_eq!(
+            parse_purchase_document(html),
+            vec![
+                purchase(7100001, Some("Outfit Set"), Some("2024-02-10"), Some(1500)),
+                purchase(7100001, Some("Outfit Set"), Some("2024-02-10"), Some(2000)),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_order_totals_as_item_prices() {
+        let text = include_str!("../../tests/fixtures/purchases/order_email_total.txt");
+        assert_eq!(
+            parse_purchase_document(text),
+            vec![purchase(
+                7200002,
+                Some("Hair Texture Set"),
+                Some("2024-04-01"),
+                None
+            )]
+        );
+    }
+
+    #[test]
+    fn ignores_documents_without_item_links() {
+        assert!(parse_purchase_document("2024/01/01 ¥1,000 https://booth.pm/ja/").is_empty());
+    }
+}
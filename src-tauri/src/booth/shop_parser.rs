@@ -0,0 +1,202 @@
+//! Parser for a shop's item listing (`https://<shop>.booth.pm/items`).
+//!
+//! Shop pages use per-shop themes, so instead of relying on class names the page is cut
+//! into one segment per item, starting at the first link to that item. Within a segment
+//! the link text (or image alt text) is the name, the first yen amount is the price and
+//! the first `booth.pximg.net` image is the thumbnail.
+
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+
+use super::html::{decode_entities, tag_re};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ShopListing {
+    pub item_id: i64,
+    pub name: Option<String>,
+    pub price: Option<i64>,
+    pub thumbnail_url: Option<String>,
+}
+
+fn item_link_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r#"href\s*=\s*["'](?:https?://[a-z0-9-]+\.booth\.pm)?(?:/[a-z]{2}(?:-[a-zA-Z]+)?)?/items/(\d+)["']"#,
+        )
+        .unwrap()
+    })
+}
+
+fn anchor_text_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)^[^>]*>(.*?)</a\s*>").unwrap())
+}
+
+fn alt_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"(?i)<img\s[^>]*?alt\s*=\s*["']([^"']+)["']"#).unwrap())
+}
+
+fn price_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?:[¥￥]|&yen;)\s*(\d[\d,]*)|(\d[\d,]*)\s*円").unwrap())
+}
+
+fn thumbnail_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"https://booth\.pximg\.net/[^"'\s)]+"#).unwrap())
+}
+
+fn title_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title\s*>").unwrap())
+}
+
+fn clean_text(html: &str) -> Option<String> {
+    let text = decode_entities(&tag_re().replace_all(html, " "));
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+/// Shop subdomain from `foo`, `foo.booth.pm` or any `https://foo.booth.pm/...` URL.
+pub fn normalize_shop(input: &str) -> AppResult<String> {
+    let trimmed = input.trim().to_ascii_lowercase();
+    let without_scheme = trimmed
+        .strip_prefix("https://")
+        .or_else(|| trimmed.strip_prefix("http://"))
+        .unwrap_or(&trimmed);
+    let host = without_scheme.split('/').next().unwrap_or_default();
+    let subdomain = host.strip_suffix(".booth.pm").unwrap_or(host);
+    let valid = !subdomain.is_empty()
+        && subdomain.len() <= 63
+        && subdomain != "www"
+        && !subdomain.starts_with('-')
+        && subdomain
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-');
+    if !valid {
+        return Err(AppError::ParseError(format!(
+            "Not a Booth shop: '{}'",
+            input.trim()
+        )));
+    }
+    Ok(subdomain.to_string())
+}
+
+pub fn shop_url(subdomain: &str) -> String {
+    format!("https://{}.booth.pm/", subdomain)
+}
+
+/// Shop name from the page title (`Shop Name - BOOTH`).
+pub fn parse_shop_name(html: &str) -> Option<String> {
+    let title = clean_text(&title_re().captures(html)?[1])?;
+    let name = title
+        .rsplit_once(" - BOOTH")
+        .map(|(name, _)| name.trim().to_string())
+        .unwrap_or(title);
+    (!name.is_empty()).then_some(name)
+}
+
+/// Items listed on a shop page, in page order (newest first on Booth).
+pub fn parse_shop_items(html: &str) -> Vec<ShopListing> {
+    // First link to each item marks where its card starts
+    let mut seen = HashSet::new();
+    let starts: Vec<(usize, i64)> = item_link_re()
+        .captures_iter(html)
+        .filter_map(|caps| {
+            let id: i64 = caps[1].parse().ok()?;
+            seen.insert(id).then(|| (caps.get(0).unwrap().start(), id))
+        })
+        .collect();
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, &(start, item_id))| {
+            let end = starts.get(i + 1).map(|(s, _)| *s).unwrap_or(html.len());
+            let segment = &html[start..end];
+            let name = item_link_re()
+                .captures_iter(segment)
+                .filter(|caps| caps[1].parse() == Ok(item_id))
+                .filter_map(|caps| {
+                    let rest = &segment[caps.get(0).unwrap().end()..];
+                    clean_text(&anchor_text_re().captures(rest)?[1])
+                })
+                .next()
+                .or_else(|| alt_re().captures(segment).and_then(|c| clean_text(&c[1])));
+            let price = price_re().captures(segment).and_then(|c| {
+                c.get(1)
+                    .or_else(|| c.get(2))
+                    .and_then(|d| d.as_str().replace(',', "").parse().ok())
+            });
+            let thumbnail_url = thumbnail_re()
+                .find(segment)
+                .map(|m| decode_entities(m.as_str()));
+            ShopListing {
+                item_id,
+                name,
+                price,
+                thumbnail_url,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_shop_inputs() {
+        assert_eq!(normalize_shop("Foo-Bar").unwrap(), "foo-bar");
+        assert_eq!(normalize_shop("foo.booth.pm").unwrap(), "foo");
+        assert_eq!(
+            normalize_shop("https://foo.booth.pm/items/123").unwrap(),
+            "foo"
+        );
+        assert!(normalize_shop("https://booth.pm/ja/items/1").is_err());
+        assert!(normalize_shop("foo bar").is_err());
+    }
+
+    #[test]
+    fn parses_item_cards() {
+        let html = r#"
+            <html><head><title>Cozy Shop - BOOTH</title></head><body>
+            <li class="item">
+              <a href="https://cozy.booth.pm/items/200"><img src="https://booth.pximg.net/c/a/200.jpg" alt="Winter Coat"></a>
+              <div class="item-name"><a href="/items/200">Winter <b>Coat</b></a></div>
+              <div class="price">¥ 1,500</div>
+            </li>
+            <li class="item">
+              <a href="/ja/items/150"><img src="https://booth.pximg.net/c/a/150.jpg" alt="Free Hair"></a>
+              <span>0 円</span>
+            </li>
+            <a href="https://cozy.booth.pm/items/200">again</a>
+            </body></html>"#;
+        assert_eq!(parse_shop_name(html).as_deref(), Some("Cozy Shop"));
+        let items = parse_shop_items(html);
+        assert_eq!(
+            items,
+            vec![
+                ShopListing {
+                    item_id: 200,
+                    name: Some("Winter Coat".to_string()),
+                    price: Some(1500),
+                    thumbnail_url: Some("https://booth.pximg.net/c/a/200.jpg".to_string()),
+                },
+                ShopListing {
+                    item_id: 150,
+                    name: Some("Free Hair".to_string()),
+                    price: Some(0),
+                    thumbnail_url: Some("https://booth.pximg.net/c/a/150.jpg".to_string()),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,460 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::database::AppDatabase;
+use crate::error::{AppError, AppResult};
+
+use super::refresh::FieldChange;
+
+// ── Types ──────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    /// Price falls to `threshold` yen or less
+    Below,
+    /// Price falls by at least `threshold` percent in one change
+    DropPercent,
+    /// Price falls to zero
+    Free,
+}
+
+impl AlertKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertKind::Below => "below",
+            AlertKind::DropPercent => "drop_percent",
+            AlertKind::Free => "free",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "below" => Some(AlertKind::Below),
+            "drop_percent" => Some(AlertKind::DropPercent),
+            "free" => Some(AlertKind::Free),
+            _ => None,
+        }
+    }
+
+    /// When several rules match one price change only the most specific is reported.
+    fn rank(&self) -> u8 {
+        match self {
+            AlertKind::Free => 0,
+            AlertKind::DropPercent => 1,
+            AlertKind::Below => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertRule {
+    pub id: i64,
+    pub kind: AlertKind,
+    pub threshold: Option<i64>,
+    /// `None` applies the rule to every favorite
+    pub item_id: Option<i64>,
+    pub enabled: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AlertRuleParams {
+    pub kind: AlertKind,
+    pub threshold: Option<i64>,
+    pub item_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceAlert {
+    pub id: i64,
+    pub rule_id: Option<i64>,
+    pub item_id: i64,
+    pub name: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub kind: AlertKind,
+    pub threshold: Option<i64>,
+    pub old_price: i64,
+    pub new_price: i64,
+    pub triggered_at: String,
+    pub read_at: Option<String>,
+}
+
+/// Payload of the `price-alert` event; field names match `PriceAlert`.
+#[derive(Clone, Serialize)]
+pub struct PriceAlertEvent {
+    pub id: i64,
+    pub item_id: i64,
+    pub name: Option<String>,
+    pub kind: AlertKind,
+    pub threshold: Option<i64>,
+    pub old_price: i64,
+    pub new_price: i64,
+}
+
+// ── Helpers ────────────────────────────────────────────
+
+fn validate_rule(kind: AlertKind, threshold: Option<i64>) -> AppResult<Option<i64>> {
+    match kind {
+        AlertKind::Free => Ok(None),
+        AlertKind::Below => match threshold {
+            Some(t) if t > 0 => Ok(Some(t)),
+            _ => Err(AppError::ParseError(
+                "A price threshold above 0 is required".to_string(),
+            )),
+        },
+        AlertKind::DropPercent => match threshold {
+            Some(t) if (1..=100).contains(&t) => Ok(Some(t)),
+            _ => Err(AppError::ParseError(
+                "Drop percentage must be between 1 and 100".to_string(),
+            )),
+        },
+    }
+}
+
+fn rule_matches(rule: &AlertRule, old_price: i64, new_price: i64) -> bool {
+    if new_price >= old_price {
+        return false;
+    }
+    match (rule.kind, rule.threshold) {
+        (AlertKind::Free, _) => new_price == 0,
+        // Only on crossing the threshold, so a price that stays low does not alert again
+        (AlertKind::Below, Some(t)) => new_price <= t && old_price > t,
+        (AlertKind::DropPercent, Some(t)) => {
+            old_price > 0 && (old_price - new_price) * 100 >= t * old_price
+        }
+        _ => false,
+    }
+}
+
+/// The most specific enabled rule matching a price change, if any: rules for the item beat
+/// global ones, then free beats a percentage drop beats a threshold.
+fn best_match(rules: &[AlertRule], old_price: i64, new_price: i64) -> Option<&AlertRule> {
+    rules
+        .iter()
+        .filter(|r| r.enabled && rule_matches(r, old_price, new_price))
+        .min_by_key(|r| (r.item_id.is_none(), r.kind.rank(), r.id))
+}
+
+fn rule_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<AlertRule> {
+    let kind: String = row.get(1)?;
+    Ok(AlertRule {
+        id: row.get(0)?,
+        kind: AlertKind::from_db(&kind).unwrap_or(AlertKind::Free),
+        threshold: row.get(2)?,
+        item_id: row.get(3)?,
+        enabled: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+/// Check a favorite's refreshed snapshot changes against the alert rules and log the alert
+/// it triggers, if any. Muted favorites never alert.
+pub(crate) fn record_price_alert(
+    conn: &Connection,
+    item_id: i64,
+    changes: &[FieldChange],
+) -> AppResult<Option<PriceAlertEvent>> {
+    let parse = |v: &Option<String>| v.as_deref().and_then(|p| p.parse::<i64>().ok());
+    let Some((old_price, new_price)) = changes
+        .iter()
+        .find(|c| c.field == "price")
+        .and_then(|c| Some((parse(&c.old_value)?, parse(&c.new_value)?)))
+    else {
+        return Ok(None);
+    };
+    if new_price >= old_price {
+        return Ok(None);
+    }
+
+    let (name, muted): (Option<String>, bool) = match conn.query_row(
+        "SELECT name, alerts_muted FROM favorites WHERE item_id = ?1 AND deleted_at IS NULL",
+        params![item_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ) {
+        Ok(v) => v,
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if muted {
+        return Ok(None);
+    }
+
+    let rules: Vec<AlertRule> = {
+        let mut stmt = conn.prepare(
+            "SELECT id, kind, threshold, item_id, enabled, created_at FROM price_alert_rules
+             WHERE enabled = 1 AND (item_id IS NULL OR item_id = ?1)",
+        )?;
+        let rows = stmt
+            .query_map(params![item_id], rule_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+    let Some(rule) = best_match(&rules, old_price, new_price) else {
+        return Ok(None);
+    };
+    conn.execute(
+        "INSERT INTO price_alerts (rule_id, item_id, kind, threshold, old_price, new_price)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            rule.id,
+            item_id,
+            rule.kind.as_str(),
+            rule.threshold,
+            old_price,
+            new_price
+        ],
+    )?;
+    Ok(Some(PriceAlertEvent {
+        id: conn.last_insert_rowid(),
+        item_id,
+        name,
+        kind: rule.kind,
+        threshold: rule.threshold,
+        old_price,
+        new_price,
+    }))
+}
+
+/// Emit `price-alert` to the frontend and show an OS notification.
+pub(crate) fn deliver_price_alert(app: &AppHandle, alert: PriceAlertEvent) {
+    let body = if alert.new_price == 0 {
+        format!("¥{} → ¥0", alert.old_price)
+    } else {
+        let percent = (alert.old_price - alert.new_price) * 100 / alert.old_price.max(1);
+        format!(
+            "¥{} → ¥{} (-{}%)",
+            alert.old_price, alert.new_price, percent
+        )
+    };
+    let title = alert
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("#{}", alert.item_id));
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        log::warn!("Failed to show price alert notification: {}", e);
+    }
+    let _ = app.emit("price-alert", alert);
+}
+
+// ── Commands ───────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_price_alert_rules(db: State<'_, AppDatabase>) -> AppResult<Vec<AlertRule>> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT id, kind, threshold, item_id, enabled, created_at FROM price_alert_rules
+         ORDER BY item_id IS NOT NULL, created_at ASC, id ASC",
+    )?;
+    let rows = stmt
+        .query_map([], rule_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Add a rule for one favorite (`item_id`) or for all of them. Returns the rule id.
+#[tauri::command]
+pub fn add_price_alert_rule(db: State<'_, AppDatabase>, params: AlertRuleParams) -> AppResult<i64> {
+    let threshold = validate_rule(params.kind, params.threshold)?;
+    let conn = db.conn()?;
+    conn.execute(
+        "INSERT INTO price_alert_rules (kind, threshold, item_id) VALUES (?1, ?2, ?3)",
+        params![params.kind.as_str(), threshold, params.item_id],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+#[tauri::command]
+pub fn set_price_alert_rule_enabled(
+    db: State<'_, AppDatabase>,
+    id: i64,
+    enabled: bool,
+) -> AppResult<()> {
+    let conn = db.conn()?;
+    let updated = conn.execute(
+        "UPDATE price_alert_rules SET enabled = ?1 WHERE id = ?2",
+        params![enabled, id],
+    )?;
+    if updated == 0 {
+        return Err(AppError::NotFound(format!("Alert rule {}", id)));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_price_alert_rule(db: State<'_, AppDatabase>, id: i64) -> AppResult<()> {
+    let conn = db.conn()?;
+    conn.execute("DELETE FROM price_alert_rules WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+/// Alert log, newest first.
+#[tauri::command]
+pub fn get_price_alerts(
+    db: State<'_, AppDatabase>,
+    unread_only: Option<bool>,
+    limit: Option<i64>,
+) -> AppResult<Vec<PriceAlert>> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT a.id, a.rule_id, a.item_id, f.name, f.thumbnail_url, a.kind, a.threshold,
+                a.old_price, a.new_price, a.triggered_at, a.read_at
+         FROM price_alerts a
+         LEFT JOIN favorites f ON f.item_id = a.item_id
+         WHERE ?1 = 0 OR a.read_at IS NULL
+         ORDER BY a.triggered_at DESC, a.id DESC
+         LIMIT ?2",
+    )?;
+    let rows = stmt
+        .query_map(
+            params![
+                unread_only.unwrap_or(false),
+                limit.unwrap_or(200).clamp(1, 1000)
+            ],
+            |row| {
+                let kind: String = row.get(5)?;
+                Ok(PriceAlert {
+                    id: row.get(0)?,
+                    rule_id: row.get(1)?,
+                    item_id: row.get(2)?,
+                    name: row.get(3)?,
+                    thumbnail_url: row.get(4)?,
+                    kind: AlertKind::from_db(&kind).unwrap_or(AlertKind::Free),
+                    threshold: row.get(6)?,
+                    old_price: row.get(7)?,
+                    new_price: row.get(8)?,
+                    triggered_at: row.get(9)?,
+                    read_at: row.get(10)?,
+                })
+            },
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Mark the given alerts (or all of them) as read.
+#[tauri::command]
+pub fn mark_price_alerts_read(db: State<'_, AppDatabase>, ids: Option<Vec<i64>>) -> AppResult<()> {
+    let mut conn = db.conn_mut()?;
+    let tx = conn.transaction()?;
+    match ids {
+        Some(ids) => {
+            for id in ids {
+                tx.execute(
+                    "UPDATE price_alerts SET read_at = datetime('now')
+                     WHERE id = ?1 AND read_at IS NULL",
+                    params![id],
+                )?;
+            }
+        }
+        None => {
+            tx.execute(
+                "UPDATE price_alerts SET read_at = datetime('now') WHERE read_at IS NULL",
+                [],
+            )?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn clear_price_alerts(db: State<'_, AppDatabase>) -> AppResult<()> {
+    let conn = db.conn()?;
+    conn.execute("DELETE FROM price_alerts", [])?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_item_alerts_muted(
+    db: State<'_, AppDatabase>,
+    item_id: i64,
+    muted: bool,
+) -> AppResult<()> {
+    let conn = db.conn()?;
+    let updated = conn.execute(
+        "UPDATE favorites SET alerts_muted = ?1 WHERE item_id = ?2 AND deleted_at IS NULL",
+        params![muted, item_id],
+    )?;
+    if updated == 0 {
+        return Err(AppError::NotFound(item_id.to_string()));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_muted_alert_items(db: State<'_, AppDatabase>) -> AppResult<Vec<i64>> {
+    let conn = db.conn()?;
+    let mut stmt = conn
+        .prepare("SELECT item_id FROM favorites WHERE alerts_muted = 1 AND deleted_at IS NULL")?;
+    let rows = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<i64>, _>>()?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: i64, kind: AlertKind, threshold: Option<i64>, item_id: Option<i64>) -> AlertRule {
+        AlertRule {
+            id,
+            kind,
+            threshold,
+            item_id,
+            enabled: true,
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn below_fires_only_when_crossing() {
+        let r = rule(1, AlertKind::Below, Some(1000), None);
+        assert!(rule_matches(&r, 1500, 1000));
+        assert!(!rule_matches(&r, 900, 800));
+        assert!(!rule_matches(&r, 1500, 1200));
+    }
+
+    #[test]
+    fn drop_percent_and_free() {
+        let pct = rule(1, AlertKind::DropPercent, Some(30), None);
+        assert!(rule_matches(&pct, 1000, 700));
+        assert!(!rule_matches(&pct, 1000, 701));
+        assert!(!rule_matches(&pct, 1000, 1200));
+        let free = rule(2, AlertKind::Free, None, None);
+        assert!(rule_matches(&free, 500, 0));
+        assert!(!rule_matches(&free, 0, 0));
+    }
+
+    #[test]
+    fn most_specific_rule_wins() {
+        let rules = vec![
+            rule(1, AlertKind::Below, Some(1000), None),
+            rule(2, AlertKind::DropPercent, Some(10), None),
+            rule(3, AlertKind::DropPercent, Some(10), Some(7)),
+            rule(4, AlertKind::Free, None, None),
+        ];
+        assert_eq!(best_match(&rules, 2000, 900).map(|r| r.id), Some(3));
+        assert_eq!(best_match(&rules, 2000, 0).map(|r| r.id), Some(3));
+        assert_eq!(best_match(&rules, 2000, 1950).map(|r| r.id), None);
+    }
+
+    #[test]
+    fn item_rule_beats_global_rule_of_any_kind() {
+        let rules = vec![
+            rule(1, AlertKind::Free, None, None),
+            rule(2, AlertKind::Below, Some(1000), Some(7)),
+        ];
+        assert_eq!(best_match(&rules, 2000, 0).map(|r| r.id), Some(2));
+    }
+
+    #[test]
+    fn rejects_rules_without_usable_threshold() {
+        assert!(validate_rule(AlertKind::Below, None).is_err());
+        assert!(validate_rule(AlertKind::DropPercent, Some(0)).is_err());
+        assert_eq!(validate_rule(AlertKind::Free, Some(5)).unwrap(), None);
+    }
+}
@@ -0,0 +1,360 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::State;
+
+use crate::database::AppDatabase;
+use crate::dates::validate_month;
+use crate::error::{AppError, AppResult};
+
+pub const MIN_PRIORITY: i64 = 1;
+pub const MAX_PRIORITY: i64 = 5;
+
+// ── Types ──────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct MonthlyBudget {
+    /// `YYYY-MM`; the amount applies from this month until the next entry
+    pub effective_month: String,
+    pub amount: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BudgetMonth {
+    pub month: String,
+    /// Budget in effect for the month, if one was set by then
+    pub budget: Option<i64>,
+    pub spent: i64,
+    pub purchases: i64,
+    pub remaining: Option<i64>,
+    pub cumulative_budget: i64,
+    pub cumulative_spent: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedItem {
+    pub item_id: i64,
+    pub name: String,
+    pub price: i64,
+    pub priority: i64,
+    pub thumbnail_url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PurchasePlan {
+    pub budget: i64,
+    /// Chosen items, highest priority first
+    pub selected: Vec<PlannedItem>,
+    /// Prioritized items that did not fit
+    pub deferred: Vec<PlannedItem>,
+    pub total_price: i64,
+    pub total_priority: i64,
+    pub remaining: i64,
+    /// Prioritized favorites skipped because they are already owned
+    pub already_owned: i64,
+    /// Prioritized favorites skipped because they were deleted or made private on Booth
+    pub unavailable: i64,
+}
+
+// ── Helpers ────────────────────────────────────────────
+
+fn current_month(conn: &Connection) -> AppResult<String> {
+    Ok(conn.query_row("SELECT strftime('%Y-%m', 'now')", [], |row| row.get(0))?)
+}
+
+/// Budget in effect for `month` (`YYYY-MM`).
+pub(crate) fn budget_for(conn: &Connection, month: &str) -> AppResult<Option<i64>> {
+    let amount = conn
+        .query_row(
+            "SELECT amount FROM monthly_budgets WHERE effective_month <= ?1
+             ORDER BY effective_month DESC LIMIT 1",
+            params![month],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(amount)
+}
+
+/// Amount spent and number of purchases in `month`; purchases without a date count in the
+/// month they were added.
+fn spending_in(conn: &Connection, month: &str) -> AppResult<(i64, i64)> {
+    Ok(conn.query_row(
+        "SELECT COALESCE(SUM(price_paid), 0), COUNT(*) FROM owned_items
+         WHERE strftime('%Y-%m', COALESCE(purchased_at, created_at)) = ?1",
+        params![month],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?)
+}
+
+/// 0/1 knapsack maximizing the summed priority of items whose total price fits `budget`;
+/// among equally good sets the cheapest wins. Priorities are small, so the table is indexed
+/// by priority sum (minimum cost to reach it) rather than by yen. Returns chosen indices.
+fn choose_items(items: &[(i64, i64)], budget: i64) -> Vec<usize> {
+    let total: usize = items.iter().map(|(_, p)| *p as usize).sum();
+    let mut min_cost = vec![i64::MAX; total + 1];
+    min_cost[0] = 0;
+    let mut took = vec![vec![false; total + 1]; items.len()];
+    for (i, &(price, priority)) in items.iter().enumerate() {
+        let priority = priority as usize;
+        for sum in (priority..=total).rev() {
+            let base = min_cost[sum - priority];
+            if base != i64::MAX && base + price < min_cost[sum] {
+                min_cost[sum] = base + price;
+                took[i][sum] = true;
+            }
+        }
+    }
+    let Some(mut sum) = (0..=total).rev().find(|&s| min_cost[s] <= budget) else {
+        return Vec::new();
+    };
+    let mut chosen = Vec::new();
+    for i in (0..items.len()).rev() {
+        if took[i][sum] {
+            chosen.push(i);
+            sum -= items[i].1 as usize;
+        }
+    }
+    chosen.reverse();
+    chosen
+}
+
+// ── Commands ───────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_budget_history(db: State<'_, AppDatabase>) -> AppResult<Vec<MonthlyBudget>> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT effective_month, amount FROM monthly_budgets ORDER BY effective_month ASC",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(MonthlyBudget {
+                effective_month: row.get(0)?,
+                amount: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Budget in effect for `month` (default: the current month).
+#[tauri::command]
+pub fn get_monthly_budget(
+    db: State<'_, AppDatabase>,
+    month: Option<String>,
+) -> AppResult<Option<i64>> {
+    let conn = db.conn()?;
+    let month = match month {
+        Some(m) => validate_month(&m)?,
+        None => current_month(&conn)?,
+    };
+    budget_for(&conn, &month)
+}
+
+/// Set the monthly budget from `from_month` (default: the current month) onwards. Earlier
+/// months keep the budget they had, so past progress is not rewritten.
+#[tauri::command]
+pub fn set_monthly_budget(
+    db: State<'_, AppDatabase>,
+    amount: i64,
+    from_month: Option<String>,
+) -> AppResult<()> {
+    if amount < 0 {
+        return Err(AppError::ParseError(
+            "Budget cannot be negative".to_string(),
+        ));
+    }
+    let conn = db.conn()?;
+    let month = match from_month {
+        Some(m) => validate_month(&m)?,
+        None => current_month(&conn)?,
+    };
+    conn.execute(
+        "INSERT INTO monthly_budgets (effective_month, amount) VALUES (?1, ?2)
+         ON CONFLICT(effective_month) DO UPDATE SET
+            amount = excluded.amount, created_at = datetime('now')",
+        params![month, amount],
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_monthly_budget(db: State<'_, AppDatabase>, effective_month: String) -> AppResult<()> {
+    let conn = db.conn()?;
+    conn.execute(
+        "DELETE FROM monthly_budgets WHERE effective_month = ?1",
+        params![effective_month],
+    )?;
+    Ok(())
+}
+
+/// Put a favorite on the purchase plan with a priority of 1 (low) to 5 (high), or take it
+/// off with `None`.
+#[tauri::command]
+pub fn set_purchase_priority(
+    db: State<'_, AppDatabase>,
+    item_id: i64,
+    priority: Option<i64>,
+) -> AppResult<()> {
+    if let Some(p) = priority {
+        if !(MIN_PRIORITY..=MAX_PRIORITY).contains(&p) {
+            return Err(AppError::ParseError(format!(
+                "Priority must be between {} and {}",
+                MIN_PRIORITY, MAX_PRIORITY
+            )));
+        }
+    }
+    let conn = db.conn()?;
+    let updated = conn.execute(
+        "UPDATE favorites SET purchase_priority = ?1 WHERE item_id = ?2 AND deleted_at IS NULL",
+        params![priority, item_id],
+    )?;
+    if updated == 0 {
+        return Err(AppError::NotFound(item_id.to_string()));
+    }
+    Ok(())
+}
+
+/// Budget against spending for the last `months` months (default 12), oldest first.
+#[tauri::command]
+pub fn get_budget_progress(
+    db: State<'_, AppDatabase>,
+    months: Option<i64>,
+) -> AppResult<Vec<BudgetMonth>> {
+    let months = months.unwrap_or(12).clamp(1, 120);
+    let conn = db.conn()?;
+    let month_keys: Vec<String> = {
+        let mut stmt = conn.prepare(
+            "WITH RECURSIVE m(n) AS (SELECT 0 UNION ALL SELECT n + 1 FROM m WHERE n + 1 < ?1)
+             SELECT strftime('%Y-%m', 'now', 'start of month', printf('-%d months', n))
+             FROM m ORDER BY n DESC",
+        )?;
+        let rows = stmt
+            .query_map(params![months], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+
+    let mut cumulative_budget = 0;
+    let mut cumulative_spent = 0;
+    let mut progress = Vec::with_capacity(month_keys.len());
+    for month in month_keys {
+        let budget = budget_for(&conn, &month)?;
+        let (spent, purchases) = spending_in(&conn, &month)?;
+        cumulative_budget += budget.unwrap_or(0);
+        cumulative_spent += spent;
+        progress.push(BudgetMonth {
+            month,
+            budget,
+            spent,
+            purchases,
+            remaining: budget.map(|b| b - spent),
+            cumulative_budget,
+            cumulative_spent,
+        });
+    }
+    Ok(progress)
+}
+
+/// Pick the prioritized favorites to buy within `budget` (default: what is left of this
+/// month's budget). Uses current favorite prices; owned and unavailable items are skipped
+/// and free items are always included.
+#[tauri::command]
+pub fn plan_purchases(db: State<'_, AppDatabase>, budget: Option<i64>) -> AppResult<PurchasePlan> {
+    let conn = db.conn()?;
+    let budget = match budget {
+        Some(b) if b < 0 => {
+            return Err(AppError::ParseError(
+                "Budget cannot be negative".to_string(),
+            ))
+        }
+        Some(b) => b,
+        None => {
+            let month = current_month(&conn)?;
+            let monthly = budget_for(&conn, &month)?.ok_or_else(|| {
+                AppError::ParseError("No monthly budget set; set one or pass a budget".to_string())
+            })?;
+            (monthly - spending_in(&conn, &month)?.0).max(0)
+        }
+    };
+
+    let (already_owned, unavailable): (i64, i64) = conn.query_row(
+        "SELECT
+            COALESCE(SUM(CASE WHEN item_id IN (SELECT item_id FROM owned_items) THEN 1 ELSE 0 END), 0),
+            COALESCE(SUM(CASE WHEN item_id NOT IN (SELECT item_id FROM owned_items)
+                               AND availability IN ('deleted', 'private') THEN 1 ELSE 0 END), 0)
+         FROM favorites WHERE deleted_at IS NULL AND purchase_priority IS NOT NULL",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let candidates: Vec<PlannedItem> = {
+        let mut stmt = conn.prepare(
+            "SELECT item_id, name, price, purchase_priority, thumbnail_url FROM favorites
+             WHERE deleted_at IS NULL AND purchase_priority IS NOT NULL
+               AND availability NOT IN ('deleted', 'private')
+               AND item_id NOT IN (SELECT item_id FROM owned_items)
+             ORDER BY purchase_priority DESC, price ASC, item_id ASC",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(PlannedItem {
+                    item_id: row.get(0)?,
+                    name: row.get(1)?,
+                    price: row.get(2)?,
+                    priority: row.get(3)?,
+                    thumbnail_url: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+
+    let weights: Vec<(i64, i64)> = candidates
+        .iter()
+        .map(|c| (c.price.max(0), c.priority.clamp(MIN_PRIORITY, MAX_PRIORITY)))
+        .collect();
+    let chosen = choose_items(&weights, budget);
+    let (selected, deferred): (Vec<_>, Vec<_>) = candidates
+        .into_iter()
+        .enumerate()
+        .partition(|(i, _)| chosen.contains(i));
+    let selected: Vec<PlannedItem> = selected.into_iter().map(|(_, item)| item).collect();
+    let deferred: Vec<PlannedItem> = deferred.into_iter().map(|(_, item)| item).collect();
+    let total_price = selected.iter().map(|i| i.price).sum();
+    let total_priority = selected.iter().map(|i| i.priority).sum();
+    Ok(PurchasePlan {
+        budget,
+        remaining: budget - total_price,
+        selected,
+        deferred,
+        total_price,
+        total_priority,
+        already_owned,
+        unavailable,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_higher_total_priority_within_budget() {
+        // (price, priority): two mid items beat one expensive high-priority item
+        let items = [(3000, 5), (1500, 3), (1500, 3), (4000, 1)];
+        assert_eq!(choose_items(&items, 3000), vec![1, 2]);
+        assert_eq!(choose_items(&items, 4500), vec![0, 1]);
+    }
+
+    #[test]
+    fn free_items_always_fit() {
+        let items = [(0, 1), (5000, 5)];
+        assert_eq!(choose_items(&items, 0), vec![0]);
+        assert_eq!(choose_items(&items, 5000), vec![0, 1]);
+    }
+
+    #[test]
+    fn cheapest_set_wins_ties() {
+        let items = [(2000, 2), (1000, 2)];
+        assert_eq!(choose_items(&items, 2500), vec![1]);
+    }
+}
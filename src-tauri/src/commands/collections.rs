@@ -4,8 +4,10 @@ use tauri::State;
 
 use crate::database::AppDatabase;
 use crate::error::{AppError, AppResult};
+use crate::sync;
 
-use super::db::FavoriteItem;
+use super::content_policy::{content_policy, police_favorites};
+use super::db::{ensure_favorite, FavoriteItem, FAVORITE_COLUMNS};
 
 // ── Validation ────────────────────────────────────────
 
@@ -55,9 +57,11 @@ pub fn get_collections(db: State<'_, AppDatabase>) -> AppResult<Vec<Collection>>
     let conn = db.conn()?;
     let mut stmt = conn.prepare(
         "SELECT c.id, c.name, c.color, c.created_at, c.sort_order,
-                COALESCE(COUNT(ci.item_id), 0) AS item_count
+                COUNT(f.id) AS item_count
          FROM collections c
          LEFT JOIN collection_items ci ON ci.collection_id = c.id
+         LEFT JOIN favorites f ON f.item_id = ci.item_id AND f.deleted_at IS NULL
+         WHERE c.deleted_at IS NULL
          GROUP BY c.id
          ORDER BY c.sort_order ASC, c.id ASC",
     )?;
@@ -86,8 +90,8 @@ pub fn create_collection(
     validate_color(&color)?;
     let conn = db.conn()?;
     conn.execute(
-        "INSERT INTO collections (name, color) VALUES (?1, ?2)",
-        params![name, color],
+        "INSERT INTO collections (name, color, uuid) VALUES (?1, ?2, ?3)",
+        params![name, color, sync::new_uuid()],
     )?;
     Ok(conn.last_insert_rowid())
 }
@@ -128,26 +132,35 @@ pub fn update_collection_color(
     Ok(())
 }
 
+/// Moves the collection to the trash; its memberships are kept until it is purged.
 #[tauri::command]
 pub fn delete_collection(db: State<'_, AppDatabase>, id: i64) -> AppResult<()> {
     let conn = db.conn()?;
-    conn.execute("DELETE FROM collections WHERE id = ?1", params![id])?;
+    conn.execute(
+        "UPDATE collections SET deleted_at = datetime('now') WHERE id = ?1 AND deleted_at IS NULL",
+        params![id],
+    )?;
     Ok(())
 }
 
 // ── Collection membership ──────────────────────────────
 
+/// Only favorites can be collected: a cached item is favorited first and a trashed favorite
+/// is restored (see [`ensure_favorite`]).
 #[tauri::command]
 pub fn add_to_collection(
     db: State<'_, AppDatabase>,
     collection_id: i64,
     item_id: i64,
 ) -> AppResult<()> {
-    let conn = db.conn()?;
-    conn.execute(
+    let mut conn = db.conn_mut()?;
+    let tx = conn.transaction()?;
+    ensure_favorite(&tx, item_id)?;
+    tx.execute(
         "INSERT OR IGNORE INTO collection_items (collection_id, item_id) VALUES (?1, ?2)",
         params![collection_id, item_id],
     )?;
+    tx.commit()?;
     Ok(())
 }
 
@@ -171,30 +184,19 @@ pub fn get_collection_items(
     collection_id: i64,
 ) -> AppResult<Vec<FavoriteItem>> {
     let conn = db.conn()?;
-    let mut stmt = conn.prepare(
-        "SELECT f.id, f.item_id, f.name, f.price, f.thumbnail_url,
-                f.category_name, f.shop_name, f.added_at, f.note
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}
          FROM favorites f
          INNER JOIN collection_items ci ON ci.item_id = f.item_id
-         WHERE ci.collection_id = ?1
+         INNER JOIN collections c ON c.id = ci.collection_id
+         WHERE ci.collection_id = ?1 AND f.deleted_at IS NULL AND c.deleted_at IS NULL
          ORDER BY ci.added_at DESC",
-    )?;
+        FAVORITE_COLUMNS
+    ))?;
     let rows = stmt
-        .query_map(params![collection_id], |row| {
-            Ok(FavoriteItem {
-                id: row.get(0)?,
-                item_id: row.get(1)?,
-                name: row.get(2)?,
-                price: row.get(3)?,
-                thumbnail_url: row.get(4)?,
-                category_name: row.get(5)?,
-                shop_name: row.get(6)?,
-                added_at: row.get(7)?,
-                note: row.get(8)?,
-            })
-        })?
+        .query_map(params![collection_id], FavoriteItem::from_row)?
         .collect::<Result<Vec<_>, _>>()?;
-    Ok(rows)
+    Ok(police_favorites(content_policy(&conn)?, rows))
 }
 
 /// Get all collection IDs that a given item belongs to
@@ -205,7 +207,9 @@ pub fn get_item_collections(
 ) -> AppResult<Vec<i64>> {
     let conn = db.conn()?;
     let mut stmt = conn.prepare(
-        "SELECT collection_id FROM collection_items WHERE item_id = ?1",
+        "SELECT ci.collection_id FROM collection_items ci
+         INNER JOIN collections c ON c.id = ci.collection_id
+         WHERE ci.item_id = ?1 AND c.deleted_at IS NULL",
     )?;
     let rows = stmt
         .query_map(params![item_id], |row| row.get(0))?
@@ -215,23 +219,29 @@ pub fn get_item_collections(
 
 // ── Item tags ──────────────────────────────────────────
 
+/// Replace the tags of a favorite. Like collections, tagging a cached item favorites it and
+/// tagging a trashed favorite restores it; clearing the tags does neither.
 #[tauri::command]
 pub fn set_item_tags(
     db: State<'_, AppDatabase>,
     item_id: i64,
     tags: Vec<String>,
 ) -> AppResult<()> {
+    let tags: Vec<&str> = tags
+        .iter()
+        .map(|tag| tag.trim())
+        .filter(|tag| !tag.is_empty() && tag.len() <= 100)
+        .collect();
     let mut conn = db.conn_mut()?;
     let tx = conn.transaction()?;
     tx.execute("DELETE FROM item_tags WHERE item_id = ?1", params![item_id])?;
-    for tag in &tags {
-        let trimmed = tag.trim();
-        if trimmed.is_empty() || trimmed.len() > 100 {
-            continue;
-        }
+    if !tags.is_empty() {
+        ensure_favorite(&tx, item_id)?;
+    }
+    for tag in tags {
         tx.execute(
             "INSERT OR IGNORE INTO item_tags (item_id, tag) VALUES (?1, ?2)",
-            params![item_id, trimmed],
+            params![item_id, tag],
         )?;
     }
     tx.commit()?;
@@ -245,7 +255,10 @@ pub fn get_item_tags(
 ) -> AppResult<Vec<String>> {
     let conn = db.conn()?;
     let mut stmt = conn.prepare(
-        "SELECT tag FROM item_tags WHERE item_id = ?1 ORDER BY tag",
+        "SELECT tag FROM item_tags
+         WHERE item_id = ?1
+           AND item_id NOT IN (SELECT item_id FROM favorites WHERE deleted_at IS NOT NULL)
+         ORDER BY tag",
     )?;
     let rows = stmt
         .query_map(params![item_id], |row| row.get(0))?
@@ -263,6 +276,7 @@ pub fn get_all_item_tags_batch(
     let mut stmt = conn.prepare(
         "SELECT it.item_id, it.tag FROM item_tags it
          INNER JOIN favorites f ON f.item_id = it.item_id
+         WHERE f.deleted_at IS NULL
          ORDER BY it.item_id, it.tag",
     )?;
     let mut map: std::collections::HashMap<i64, Vec<String>> = std::collections::HashMap::new();
@@ -285,6 +299,8 @@ pub fn get_all_item_collections_batch(
     let mut stmt = conn.prepare(
         "SELECT ci.item_id, ci.collection_id FROM collection_items ci
          INNER JOIN favorites f ON f.item_id = ci.item_id
+         INNER JOIN collections c ON c.id = ci.collection_id
+         WHERE f.deleted_at IS NULL AND c.deleted_at IS NULL
          ORDER BY ci.item_id",
     )?;
     let mut map: std::collections::HashMap<i64, Vec<i64>> = std::collections::HashMap::new();
@@ -301,7 +317,9 @@ pub fn get_all_item_collections_batch(
 pub fn get_all_user_tags(db: State<'_, AppDatabase>) -> AppResult<Vec<String>> {
     let conn = db.conn()?;
     let mut stmt = conn.prepare(
-        "SELECT DISTINCT tag FROM item_tags ORDER BY tag",
+        "SELECT DISTINCT tag FROM item_tags
+         WHERE item_id NOT IN (SELECT item_id FROM favorites WHERE deleted_at IS NOT NULL)
+         ORDER BY tag",
     )?;
     let rows = stmt
         .query_map([], |row| row.get(0))?
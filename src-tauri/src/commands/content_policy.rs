@@ -0,0 +1,135 @@
+use rusqlite::{params, Connection};
+use tauri::{AppHandle, State};
+
+use crate::booth::models::{has_adult_marker, BoothItem};
+use crate::database::{self, AppDatabase};
+use crate::error::AppResult;
+use crate::settings::{ContentPolicy, SettingsPatch, CONTENT_POLICY_KEY};
+
+use super::db::FavoriteItem;
+use super::settings::change_settings;
+use super::shops::ShopItem;
+use super::trash::TrashEntry;
+
+// ── Helpers ────────────────────────────────────────────
+
+pub(crate) fn content_policy(conn: &Connection) -> AppResult<ContentPolicy> {
+    Ok(database::get_setting(conn, CONTENT_POLICY_KEY)?
+        .map(|v| ContentPolicy::from_db(&v))
+        .unwrap_or_default())
+}
+
+/// Drop or blur R-18 items per the policy; `is_adult` is set from markers as well.
+pub(crate) fn police_items(policy: ContentPolicy, items: Vec<BoothItem>) -> Vec<BoothItem> {
+    items
+        .into_iter()
+        .filter_map(|mut item| {
+            item.is_adult = item.looks_adult();
+            item.blurred = policy.apply(item.is_adult)?;
+            Some(item)
+        })
+        .collect()
+}
+
+pub(crate) fn police_favorites(
+    policy: ContentPolicy,
+    favorites: Vec<FavoriteItem>,
+) -> Vec<FavoriteItem> {
+    favorites
+        .into_iter()
+        .filter_map(|mut favorite| {
+            favorite.blurred = policy.apply(favorite.is_adult)?;
+            Some(favorite)
+        })
+        .collect()
+}
+
+pub(crate) fn police_shop_items(policy: ContentPolicy, items: Vec<ShopItem>) -> Vec<ShopItem> {
+    items
+        .into_iter()
+        .filter_map(|mut item| {
+            item.is_adult = item.is_adult || item.name.as_deref().is_some_and(has_adult_marker);
+            item.blurred = policy.apply(item.is_adult)?;
+            Some(item)
+        })
+        .collect()
+}
+
+pub(crate) fn police_trash(policy: ContentPolicy, entries: Vec<TrashEntry>) -> Vec<TrashEntry> {
+    entries
+        .into_iter()
+        .filter_map(|mut entry| {
+            entry.blurred = policy.apply(entry.is_adult)?;
+            Some(entry)
+        })
+        .collect()
+}
+
+/// Avatar item counts depend on the policy; make the next check refresh them all.
+pub(crate) fn mark_avatars_stale(conn: &Connection) -> AppResult<()> {
+    conn.execute(
+        "UPDATE popular_avatars SET updated_at = ?1",
+        params!["1970-01-01 00:00:00"],
+    )?;
+    Ok(())
+}
+
+// ── Commands ───────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_content_policy(db: State<'_, AppDatabase>) -> AppResult<ContentPolicy> {
+    let conn = db.conn()?;
+    content_policy(&conn)
+}
+
+/// Change the policy. Avatar item counts depend on it, so they are marked for an update.
+/// Emits `settings-changed` like `update_settings`.
+#[tauri::command]
+pub fn set_content_policy(
+    app: AppHandle,
+    db: State<'_, AppDatabase>,
+    policy: ContentPolicy,
+) -> AppResult<()> {
+    let patch = SettingsPatch {
+        content_policy: Some(policy),
+        ..Default::default()
+    };
+    change_settings(&app, &db, patch).map(|_| ())
+}
+
+/// Apply the policy to items fetched by the frontend (search results, shop listings).
+#[tauri::command]
+pub fn apply_content_policy(
+    db: State<'_, AppDatabase>,
+    items: Vec<BoothItem>,
+) -> AppResult<Vec<BoothItem>> {
+    let conn = db.conn()?;
+    Ok(police_items(content_policy(&conn)?, items))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shop_items_are_flagged_by_name_markers() {
+        let item = |item_id: i64, name: &str| ShopItem {
+            shop_id: 1,
+            item_id,
+            name: Some(name.to_string()),
+            price: None,
+            thumbnail_url: None,
+            first_seen_at: String::new(),
+            last_seen_at: String::new(),
+            is_new: true,
+            is_favorite: false,
+            is_adult: false,
+            blurred: false,
+        };
+        let items = || vec![item(1, "Knit"), item(2, "【R-18】Knit")];
+        let shown = police_shop_items(ContentPolicy::Hide, items());
+        assert_eq!(shown.iter().map(|i| i.item_id).collect::<Vec<_>>(), vec![1]);
+        let blurred = police_shop_items(ContentPolicy::Blur, items());
+        assert!(blurred[1].is_adult && blurred[1].blurred && !blurred[0].blurred);
+    }
+}
@@ -0,0 +1,333 @@
+use std::time::Duration;
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use tauri_plugin_http::reqwest;
+
+use crate::currency::{
+    self, convert, normalize_currency, parse_rates_csv, rate_on, require_rate, save_rate,
+    ExchangeRate, BASE_CURRENCY,
+};
+use crate::database::AppDatabase;
+use crate::dates::validate_date;
+use crate::error::{AppError, AppResult};
+
+/// Free ECB reference rates; no API key needed.
+const RATES_API: &str = "https://api.frankfurter.app/latest";
+const MAX_IMPORT_FILE_BYTES: u64 = 5 * 1024 * 1024;
+
+// ── Types ──────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct RateImportReport {
+    pub imported: usize,
+    /// 1-based line numbers that could not be parsed
+    pub bad_lines: Vec<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConvertedPrices {
+    pub currency: String,
+    pub rate: ExchangeRate,
+    /// Same order as the requested JPY amounts
+    pub amounts: Vec<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConvertedMonthlySpend {
+    pub month: String,
+    pub count: i64,
+    pub amount: f64,
+}
+
+/// Money figures from the statistics, converted. Favorite prices use the latest rate;
+/// purchases use the rate on their purchase date.
+#[derive(Debug, Serialize)]
+pub struct CurrencyStatistics {
+    pub currency: String,
+    /// Latest rate, used for current prices
+    pub rate: ExchangeRate,
+    pub total_value: f64,
+    pub total_value_max: f64,
+    pub avg_price: f64,
+    pub wishlist_value: f64,
+    pub total_spent: f64,
+    pub monthly_spend: Vec<ConvertedMonthlySpend>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RatesApiResponse {
+    date: String,
+    rates: std::collections::HashMap<String, f64>,
+}
+
+// ── Commands ───────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_exchange_rates(
+    db: State<'_, AppDatabase>,
+    currency: Option<String>,
+) -> AppResult<Vec<ExchangeRate>> {
+    let currency = currency.map(|c| normalize_currency(&c)).transpose()?;
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT currency, rate_date, jpy_per_unit, source FROM exchange_rates
+         WHERE ?1 IS NULL OR currency = ?1
+         ORDER BY currency, rate_date DESC",
+    )?;
+    let rows = stmt
+        .query_map(params![currency], |row| {
+            Ok(ExchangeRate {
+                currency: row.get(0)?,
+                rate_date: row.get(1)?,
+                jpy_per_unit: row.get(2)?,
+                source: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Record a rate by hand: one unit of `currency` is worth `jpy_per_unit` JPY on `rate_date`.
+#[tauri::command]
+pub fn set_exchange_rate(
+    db: State<'_, AppDatabase>,
+    currency: String,
+    rate_date: String,
+    jpy_per_unit: f64,
+) -> AppResult<()> {
+    let currency = normalize_currency(&currency)?;
+    if currency == BASE_CURRENCY {
+        return Err(AppError::ParseError("JPY is the base currency".to_string()));
+    }
+    if !jpy_per_unit.is_finite() || jpy_per_unit <= 0.0 {
+        return Err(AppError::ParseError(
+            "Exchange rate must be positive".to_string(),
+        ));
+    }
+    let rate_date: String = validate_date(&rate_date)?.chars().take(10).collect();
+    let conn = db.conn()?;
+    save_rate(
+        &conn,
+        &ExchangeRate {
+            currency,
+            rate_date,
+            jpy_per_unit,
+            source: "manual".to_string(),
+        },
+    )
+}
+
+#[tauri::command]
+pub fn delete_exchange_rate(
+    db: State<'_, AppDatabase>,
+    currency: String,
+    rate_date: String,
+) -> AppResult<()> {
+    let currency = normalize_currency(&currency)?;
+    let conn = db.conn()?;
+    conn.execute(
+        "DELETE FROM exchange_rates WHERE currency = ?1 AND rate_date = ?2",
+        params![currency, rate_date],
+    )?;
+    Ok(())
+}
+
+/// Import a CSV of `date,currency,jpy_per_unit` rows. Existing rates for the same
+/// currency and date are replaced.
+#[tauri::command]
+pub fn import_exchange_rates(
+    db: State<'_, AppDatabase>,
+    path: String,
+) -> AppResult<RateImportReport> {
+    let meta =
+        std::fs::metadata(&path).map_err(|e| AppError::NotFound(format!("{}: {}", path, e)))?;
+    if meta.len() > MAX_IMPORT_FILE_BYTES {
+        return Err(AppError::ParseError(format!(
+            "{} is too large for a rate table",
+            path
+        )));
+    }
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| AppError::ParseError(format!("{}: {}", path, e)))?;
+    let (rates, bad_lines) = parse_rates_csv(&text);
+    let rates: Vec<ExchangeRate> = rates
+        .into_iter()
+        .filter(|r| r.currency != BASE_CURRENCY)
+        .collect();
+
+    let mut conn = db.conn_mut()?;
+    let tx = conn.transaction()?;
+    for rate in &rates {
+        save_rate(&tx, rate)?;
+    }
+    tx.commit()?;
+    Ok(RateImportReport {
+        imported: rates.len(),
+        bad_lines,
+    })
+}
+
+/// Fetch today's reference rates for `currencies` and store them. When offline the
+/// previously stored rates stay in use.
+#[tauri::command]
+pub async fn fetch_exchange_rates(
+    db: State<'_, AppDatabase>,
+    currencies: Vec<String>,
+) -> AppResult<Vec<ExchangeRate>> {
+    let currencies = currencies
+        .iter()
+        .map(|c| normalize_currency(c))
+        .collect::<AppResult<Vec<_>>>()?
+        .into_iter()
+        .filter(|c| c != BASE_CURRENCY)
+        .collect::<Vec<_>>();
+    if currencies.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let http = reqwest::Client::builder()
+        .timeout(Duration::from_secs(20))
+        .build()
+        .map_err(|e| AppError::Network(format!("Failed to create HTTP client: {}", e)))?;
+    let url = format!(
+        "{}?from={}&to={}",
+        RATES_API,
+        BASE_CURRENCY,
+        currencies.join(",")
+    );
+    let resp = http
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| AppError::Network(e.to_string()))?;
+    if !resp.status().is_success() {
+        return Err(AppError::Network(format!(
+            "Rate provider returned {}",
+            resp.status()
+        )));
+    }
+    let body = resp
+        .text()
+        .await
+        .map_err(|e| AppError::Network(e.to_string()))?;
+    let data: RatesApiResponse = serde_json::from_str(&body)
+        .map_err(|e| AppError::ParseError(format!("Invalid rate response: {}", e)))?;
+
+    // The API quotes units of each currency per JPY; we store the inverse
+    let rates: Vec<ExchangeRate> = data
+        .rates
+        .into_iter()
+        .filter(|(_, per_jpy)| per_jpy.is_finite() && *per_jpy > 0.0)
+        .map(|(currency, per_jpy)| ExchangeRate {
+            currency,
+            rate_date: data.date.clone(),
+            jpy_per_unit: 1.0 / per_jpy,
+            source: "frankfurter".to_string(),
+        })
+        .collect();
+
+    let mut conn = db.conn_mut()?;
+    let tx = conn.transaction()?;
+    for rate in &rates {
+        save_rate(&tx, rate)?;
+    }
+    tx.commit()?;
+    Ok(rates)
+}
+
+/// Convert JPY prices at the rate on `date` (latest known rate when omitted).
+#[tauri::command]
+pub fn convert_prices(
+    db: State<'_, AppDatabase>,
+    prices: Vec<i64>,
+    currency: String,
+    date: Option<String>,
+) -> AppResult<ConvertedPrices> {
+    let currency = normalize_currency(&currency)?;
+    let conn = db.conn()?;
+    let rate = require_rate(&conn, &currency, date.as_deref())?;
+    Ok(ConvertedPrices {
+        amounts: prices
+            .iter()
+            .map(|p| convert(*p, rate.jpy_per_unit, &currency))
+            .collect(),
+        currency,
+        rate,
+    })
+}
+
+#[tauri::command]
+pub fn get_statistics_in_currency(
+    db: State<'_, AppDatabase>,
+    currency: String,
+) -> AppResult<CurrencyStatistics> {
+    let currency = normalize_currency(&currency)?;
+    let conn = db.conn()?;
+    let latest = require_rate(&conn, &currency, None)?;
+
+    let (total_value, total_value_max, avg_price): (i64, i64, f64) = conn.query_row(
+        "SELECT COALESCE(SUM(price), 0), COALESCE(SUM(COALESCE(price_max, price)), 0),
+                COALESCE(AVG(price), 0)
+         FROM favorites WHERE deleted_at IS NULL",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    let wishlist_value: i64 = conn.query_row(
+        "SELECT COALESCE(SUM(price), 0) FROM favorites
+         WHERE deleted_at IS NULL AND item_id NOT IN (SELECT item_id FROM owned_items)",
+        [],
+        |row| row.get(0),
+    )?;
+
+    // Each purchase at the rate of its own date
+    let purchases: Vec<(String, Option<String>, Option<i64>)> = {
+        let mut stmt = conn.prepare(
+            "SELECT strftime('%Y-%m', COALESCE(purchased_at, created_at)),
+                    date(COALESCE(purchased_at, created_at)), price_paid
+             FROM owned_items ORDER BY 1 ASC",
+        )?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+    let mut total_spent = 0.0;
+    let mut monthly_spend: Vec<ConvertedMonthlySpend> = Vec::new();
+    for (month, day, price) in purchases {
+        let rate = rate_on(&conn, &currency, day.as_deref())?.unwrap_or_else(|| latest.clone());
+        // A purchase of unknown price still counts, but adds nothing
+        let amount = price.map_or(0.0, |p| convert(p, rate.jpy_per_unit, &currency));
+        total_spent += amount;
+        match monthly_spend.last_mut() {
+            Some(last) if last.month == month => {
+                last.count += 1;
+                last.amount += amount;
+            }
+            _ => monthly_spend.push(ConvertedMonthlySpend {
+                month,
+                count: 1,
+                amount,
+            }),
+        }
+    }
+
+    let round = |v: f64| {
+        let factor = 10f64.powi(currency::minor_units(&currency));
+        (v * factor).round() / factor
+    };
+    for month in &mut monthly_spend {
+        month.amount = round(month.amount);
+    }
+    Ok(CurrencyStatistics {
+        total_value: convert(total_value, latest.jpy_per_unit, &currency),
+        total_value_max: convert(total_value_max, latest.jpy_per_unit, &currency),
+        avg_price: round(avg_price / latest.jpy_per_unit),
+        wishlist_value: convert(wishlist_value, latest.jpy_per_unit, &currency),
+        total_spent: round(total_spent),
+        monthly_spend,
+        currency: currency.clone(),
+        rate: latest,
+    })
+}
@@ -1,10 +1,21 @@
-use rusqlite::params;
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
-use crate::booth::models::BoothItem;
+use crate::booth::models::{BoothItem, ItemAvailability};
+use crate::booth::shop_parser::shop_url;
 use crate::database::AppDatabase;
+use crate::entities::{
+    ensure_category, ensure_shop, favorite_cached, get_cached, insert_favorite, item_shop_subdomain,
+};
 use crate::error::AppResult;
+use crate::settings::Settings;
+
+use super::content_policy::{content_policy, police_favorites, police_items};
+use super::search_analytics::{record_conversion, session_id};
+use super::search_history::is_paused;
+
+pub use crate::entities::AddFavoriteParams;
 
 // ── Types ──────────────────────────────────────────────
 
@@ -13,22 +24,68 @@ pub struct FavoriteItem {
     pub id: i64,
     pub item_id: i64,
     pub name: String,
+    /// Cheapest variation's price
     pub price: i64,
+    /// Most expensive variation's price, when the item has several prices
+    pub price_max: Option<i64>,
     pub thumbnail_url: Option<String>,
     pub category_name: Option<String>,
     pub shop_name: Option<String>,
     pub added_at: String,
     pub note: Option<String>,
+    pub availability: ItemAvailability,
+    pub last_checked_at: Option<String>,
+    /// 1 (low) to 5 (high) when the item is on the purchase plan
+    pub purchase_priority: Option<i64>,
+    pub shop_id: Option<i64>,
+    /// Shop page, once the shop's subdomain is known
+    pub shop_url: Option<String>,
+    pub is_adult: bool,
+    /// When Booth first published the item
+    pub published_at: Option<String>,
+    /// Set by the content policy on R-18 favorites the UI should blur
+    pub blurred: bool,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct AddFavoriteParams {
-    pub item_id: i64,
-    pub name: String,
-    pub price: i64,
-    pub thumbnail_url: Option<String>,
-    pub category_name: Option<String>,
-    pub shop_name: Option<String>,
+/// Columns for [`FavoriteItem::from_row`], selected from `favorites f`.
+pub(crate) const FAVORITE_COLUMNS: &str = "f.id, f.item_id, f.name, f.price, f.thumbnail_url,
+     f.category_name, f.shop_name, f.added_at, f.note, f.availability, f.last_checked_at,
+     f.purchase_priority, f.shop_id, (SELECT subdomain FROM shops WHERE id = f.shop_id),
+     f.price_max, f.is_adult, f.published_at";
+
+impl FavoriteItem {
+    /// Maps a row selected as [`FAVORITE_COLUMNS`].
+    pub(crate) fn from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Self> {
+        Ok(FavoriteItem {
+            id: row.get(0)?,
+            item_id: row.get(1)?,
+            name: row.get(2)?,
+            price: row.get(3)?,
+            thumbnail_url: row.get(4)?,
+            category_name: row.get(5)?,
+            shop_name: row.get(6)?,
+            added_at: row.get(7)?,
+            note: row.get(8)?,
+            availability: ItemAvailability::from_db(&row.get::<_, String>(9)?),
+            last_checked_at: row.get(10)?,
+            purchase_priority: row.get(11)?,
+            shop_id: row.get(12)?,
+            shop_url: row.get::<_, Option<String>>(13)?.map(|s| shop_url(&s)),
+            price_max: row.get(14)?,
+            is_adult: row.get(15)?,
+            published_at: row.get(16)?,
+            blurred: false,
+        })
+    }
+}
+
+/// Order of [`get_favorites`]; items with an unknown release date go last.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FavoriteSort {
+    #[default]
+    Added,
+    Published,
 }
 
 #[derive(Debug, Serialize)]
@@ -45,51 +102,129 @@ pub struct PopularAvatar {
 
 // ── Cache / History ────────────────────────────────────
 
+/// Insert or update a cached item. Anything we just received from Booth is by definition available.
+/// Search results list no variations and may lack the dates, event and sale flags, so what they
+/// leave out keeps the values cached from the item page. The adult flag never clears.
+pub(crate) fn upsert_cached_item(conn: &Connection, item: &BoothItem) -> AppResult<()> {
+    let images_json = serde_json::to_string(&item.images).unwrap_or_else(|e| {
+        log::warn!("Failed to serialize images for item {}: {}", item.id, e);
+        "[]".to_string()
+    });
+    let tags_json = serde_json::to_string(&item.tags).unwrap_or_else(|e| {
+        log::warn!("Failed to serialize tags for item {}: {}", item.id, e);
+        "[]".to_string()
+    });
+    let variations_json = serde_json::to_string(&item.variations).unwrap_or_else(|e| {
+        log::warn!("Failed to serialize variations for item {}: {}", item.id, e);
+        "[]".to_string()
+    });
+    let shop_id = ensure_shop(
+        conn,
+        item.shop_name.as_deref(),
+        item_shop_subdomain(item).as_deref(),
+    )?;
+    if let (Some(shop_id), Some(thumbnail)) = (shop_id, &item.shop_thumbnail_url) {
+        conn.execute(
+            "UPDATE shops SET thumbnail_url = ?1 WHERE id = ?2",
+            params![thumbnail, shop_id],
+        )?;
+    }
+    let category_id = ensure_category(conn, item.category_name.as_deref())?;
+    conn.execute(
+        "INSERT INTO cached_items
+         (id, name, description, price, category_name, shop_name, url, images_json, tags_json, wish_count,
+          availability, checked_at, cached_at, shop_id, category_id, variations_json,
+          is_adult, published_at, updated_at, event_name, is_sold_out, is_end_of_sale)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 'available', datetime('now'), datetime('now'),
+                 ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)
+         ON CONFLICT(id) DO UPDATE SET
+             name = excluded.name, description = excluded.description, price = excluded.price,
+             category_name = excluded.category_name, shop_name = excluded.shop_name,
+             url = excluded.url, images_json = excluded.images_json,
+             tags_json = excluded.tags_json, wish_count = excluded.wish_count,
+             availability = excluded.availability, checked_at = excluded.checked_at,
+             cached_at = excluded.cached_at, shop_id = excluded.shop_id,
+             category_id = excluded.category_id,
+             variations_json = CASE WHEN excluded.variations_json = '[]'
+                 THEN cached_items.variations_json ELSE excluded.variations_json END,
+             is_adult = MAX(excluded.is_adult, cached_items.is_adult),
+             published_at = COALESCE(excluded.published_at, cached_items.published_at),
+             updated_at = COALESCE(excluded.updated_at, cached_items.updated_at),
+             event_name = COALESCE(excluded.event_name, cached_items.event_name),
+             is_sold_out = CASE WHEN excluded.variations_json = '[]'
+                 THEN MAX(excluded.is_sold_out, cached_items.is_sold_out)
+                 ELSE excluded.is_sold_out END,
+             is_end_of_sale = CASE WHEN excluded.variations_json = '[]'
+                 THEN MAX(excluded.is_end_of_sale, cached_items.is_end_of_sale)
+                 ELSE excluded.is_end_of_sale END",
+        params![
+            item.id,
+            item.name,
+            item.description,
+            item.price,
+            item.category_name,
+            item.shop_name,
+            item.url,
+            images_json,
+            tags_json,
+            item.wish_lists_count,
+            shop_id,
+            category_id,
+            variations_json,
+            item.looks_adult(),
+            item.published_at,
+            item.updated_at,
+            item.event_name,
+            item.is_sold_out,
+            item.is_end_of_sale,
+        ],
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_cached_item(db: State<'_, AppDatabase>, item_id: i64) -> AppResult<Option<BoothItem>> {
+    let conn = db.conn()?;
+    let cached = get_cached(&conn, item_id)?.into_iter().collect();
+    Ok(police_items(content_policy(&conn)?, cached).pop())
+}
+
 #[tauri::command]
 pub fn cache_items(db: State<'_, AppDatabase>, items: Vec<BoothItem>) -> AppResult<()> {
     let mut conn = db.conn_mut()?;
     let tx = conn.transaction()?;
     for item in &items {
-        let images_json = serde_json::to_string(&item.images).unwrap_or_else(|e| {
-            log::warn!("Failed to serialize images for item {}: {}", item.id, e);
-            "[]".to_string()
-        });
-        let tags_json = serde_json::to_string(&item.tags).unwrap_or_else(|e| {
-            log::warn!("Failed to serialize tags for item {}: {}", item.id, e);
-            "[]".to_string()
-        });
-        tx.execute(
-            "INSERT OR REPLACE INTO cached_items
-             (id, name, description, price, category_name, shop_name, url, images_json, tags_json, wish_count, cached_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, datetime('now'))",
-            params![
-                item.id,
-                item.name,
-                item.description,
-                item.price,
-                item.category_name,
-                item.shop_name,
-                item.url,
-                images_json,
-                tags_json,
-                item.wish_lists_count,
-            ],
-        )?;
+        upsert_cached_item(&tx, item)?;
     }
     tx.commit()?;
     Ok(())
 }
 
+/// Record a search. `result_count`, `filters` (category, sort, price range, ...) and the
+/// IDs of the items shown (`item_ids`) feed the search analytics.
 #[tauri::command]
-pub fn save_search_history(db: State<'_, AppDatabase>, keyword: String) -> AppResult<()> {
+pub fn save_search_history(
+    db: State<'_, AppDatabase>,
+    keyword: String,
+    result_count: Option<i64>,
+    filters: Option<serde_json::Value>,
+    item_ids: Option<Vec<i64>>,
+) -> AppResult<()> {
     let keyword = keyword.trim().to_string();
     if keyword.is_empty() {
         return Ok(());
     }
+    let filters_json = filters.filter(|f| !f.is_null()).map(|f| f.to_string());
+    let item_ids = item_ids.map(|ids| serde_json::Value::from(ids).to_string());
     let conn = db.conn()?;
+    if is_paused(&conn)? {
+        return Ok(());
+    }
     conn.execute(
-        "INSERT INTO search_history (keyword, searched_at) VALUES (?1, datetime('now'))",
-        params![keyword],
+        "INSERT INTO search_history
+         (keyword, searched_at, result_count, filters_json, session_id, result_item_ids)
+         VALUES (?1, datetime('now'), ?2, ?3, ?4, ?5)",
+        params![keyword, result_count, filters_json, session_id(), item_ids],
     )?;
     // Prune old entries to prevent unbounded growth
     conn.execute(
@@ -102,60 +237,72 @@ pub fn save_search_history(db: State<'_, AppDatabase>, keyword: String) -> AppRe
 // ── Favorites ──────────────────────────────────────────
 
 #[tauri::command]
-pub fn get_favorites(db: State<'_, AppDatabase>) -> AppResult<Vec<FavoriteItem>> {
+pub fn get_favorites(
+    db: State<'_, AppDatabase>,
+    sort: Option<FavoriteSort>,
+) -> AppResult<Vec<FavoriteItem>> {
+    let order = match sort.unwrap_or_default() {
+        FavoriteSort::Added => "f.added_at DESC",
+        FavoriteSort::Published => "f.published_at IS NULL, f.published_at DESC, f.added_at DESC",
+    };
     let conn = db.conn()?;
-    let mut stmt = conn.prepare(
-        "SELECT id, item_id, name, price, thumbnail_url, category_name, shop_name, added_at, note
-         FROM favorites ORDER BY added_at DESC",
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM favorites f WHERE f.deleted_at IS NULL ORDER BY {}",
+        FAVORITE_COLUMNS, order
+    ))?;
     let rows = stmt
-        .query_map([], |row| {
-            Ok(FavoriteItem {
-                id: row.get(0)?,
-                item_id: row.get(1)?,
-                name: row.get(2)?,
-                price: row.get(3)?,
-                thumbnail_url: row.get(4)?,
-                category_name: row.get(5)?,
-                shop_name: row.get(6)?,
-                added_at: row.get(7)?,
-                note: row.get(8)?,
-            })
-        })?
+        .query_map([], FavoriteItem::from_row)?
         .collect::<Result<Vec<_>, _>>()?;
-    Ok(rows)
+    Ok(police_favorites(content_policy(&conn)?, rows))
 }
 
 #[tauri::command]
 pub fn add_favorite(db: State<'_, AppDatabase>, params: AddFavoriteParams) -> AppResult<()> {
     let conn = db.conn()?;
-    conn.execute(
-        "INSERT OR IGNORE INTO favorites
-         (item_id, name, price, thumbnail_url, category_name, shop_name, added_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now'))",
-        params![
-            params.item_id,
-            params.name,
-            params.price,
-            params.thumbnail_url,
-            params.category_name,
-            params.shop_name,
-        ],
-    )?;
+    if insert_favorite(&conn, &params)? {
+        record_conversion(&conn, params.item_id)?;
+    }
     Ok(())
 }
 
+/// Tags and collection memberships belong to favorites. Trashed favorites are restored and
+/// items known only from the cache are favorited first; other items are rejected, since
+/// there is nothing to show their tags or memberships on.
+pub(crate) fn ensure_favorite(conn: &Connection, item_id: i64) -> AppResult<()> {
+    let active: Option<bool> = conn
+        .query_row(
+            "SELECT deleted_at IS NULL FROM favorites WHERE item_id = ?1",
+            params![item_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    match active {
+        Some(true) => Ok(()),
+        Some(false) => {
+            conn.execute(
+                "UPDATE favorites SET deleted_at = NULL WHERE item_id = ?1",
+                params![item_id],
+            )?;
+            Ok(())
+        }
+        None => {
+            if favorite_cached(conn, item_id)? {
+                record_conversion(conn, item_id)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Moves the favorite to the trash. Tags, collection memberships and the note are kept
+/// until the trash is emptied or the retention period expires.
 #[tauri::command]
 pub fn remove_favorite(db: State<'_, AppDatabase>, item_id: i64) -> AppResult<()> {
-    let mut conn = db.conn_mut()?;
-    let tx = conn.transaction()?;
-    tx.execute(
-        "DELETE FROM collection_items WHERE item_id = ?1",
+    let conn = db.conn()?;
+    conn.execute(
+        "UPDATE favorites SET deleted_at = datetime('now') WHERE item_id = ?1 AND deleted_at IS NULL",
         params![item_id],
     )?;
-    tx.execute("DELETE FROM item_tags WHERE item_id = ?1", params![item_id])?;
-    tx.execute("DELETE FROM favorites WHERE item_id = ?1", params![item_id])?;
-    tx.commit()?;
     Ok(())
 }
 
@@ -196,9 +343,10 @@ pub fn check_avatars_need_update(db: State<'_, AppDatabase>) -> AppResult<bool>
     match oldest {
         None => Ok(true),
         Some(ts) => {
+            let max_age = format!("-{} days", Settings::load(&conn)?.avatar_refresh_days);
             let needs: bool = conn.query_row(
-                "SELECT CASE WHEN datetime(?1) IS NULL THEN 1 ELSE datetime(?1) < datetime('now', '-7 days') END",
-                params![ts],
+                "SELECT CASE WHEN datetime(?1) IS NULL THEN 1 ELSE datetime(?1) < datetime('now', ?2) END",
+                params![ts, max_age],
                 |row| row.get(0),
             )?;
             Ok(needs)
@@ -0,0 +1,128 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::database::AppDatabase;
+use crate::encryption;
+use crate::error::{AppError, AppResult};
+use crate::profiles::ProfileManager;
+
+use super::profiles::ensure_idle;
+use super::refresh::RefreshJob;
+use super::shops::ShopCheckJob;
+
+// ── Types ──────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct DatabaseStatus {
+    pub profile_id: String,
+    pub encrypted: bool,
+    /// Encrypted and waiting for `unlock_database`
+    pub locked: bool,
+    /// The passphrase is kept in the OS keyring
+    pub passphrase_saved: bool,
+}
+
+// ── Helpers ────────────────────────────────────────────
+
+fn status(profiles: &ProfileManager, db: &AppDatabase) -> AppResult<DatabaseStatus> {
+    let profile = profiles.active()?;
+    Ok(DatabaseStatus {
+        encrypted: encryption::is_encrypted(&profiles.db_path(&profile.id)),
+        locked: db.is_locked(),
+        passphrase_saved: encryption::stored_passphrase(&profile.id).is_some(),
+        profile_id: profile.id,
+    })
+}
+
+fn remember_passphrase(profile_id: &str, passphrase: &str, remember: bool) -> AppResult<()> {
+    if remember {
+        encryption::store_passphrase(profile_id, passphrase)
+    } else {
+        encryption::forget_passphrase(profile_id)
+    }
+}
+
+// ── Commands ───────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_database_status(
+    profiles: State<'_, ProfileManager>,
+    db: State<'_, AppDatabase>,
+) -> AppResult<DatabaseStatus> {
+    status(&profiles, &db)
+}
+
+/// Open the active profile's encrypted database. With `remember` the passphrase is saved in
+/// the OS keyring so later starts skip the prompt; without it a saved one is removed.
+/// Emits `profile-changed` on success.
+#[tauri::command]
+pub fn unlock_database(
+    app: AppHandle,
+    profiles: State<'_, ProfileManager>,
+    db: State<'_, AppDatabase>,
+    passphrase: String,
+    remember: bool,
+) -> AppResult<DatabaseStatus> {
+    let profile = profiles.active()?;
+    let db_path = profiles.db_path(&profile.id);
+    db.switch_to(&db_path, Some(&passphrase))
+        .map_err(|e| match e {
+            AppError::Locked => AppError::ParseError("Wrong passphrase".to_string()),
+            e => e,
+        })?;
+    remember_passphrase(&profile.id, &passphrase, remember)?;
+    let _ = app.emit("profile-changed", profile);
+    status(&profiles, &db)
+}
+
+/// Convert the active profile's plain database to an encrypted one.
+#[tauri::command]
+pub fn encrypt_database(
+    profiles: State<'_, ProfileManager>,
+    db: State<'_, AppDatabase>,
+    refresh_job: State<'_, RefreshJob>,
+    shop_job: State<'_, ShopCheckJob>,
+    passphrase: String,
+    remember: bool,
+) -> AppResult<DatabaseStatus> {
+    ensure_idle(&refresh_job, &shop_job)?;
+    encryption::check_passphrase(&passphrase)?;
+    let profile = profiles.active()?;
+    let db_path = profiles.db_path(&profile.id);
+    db.rewrite(&db_path, None, Some(&passphrase), || {
+        encryption::encrypt(&db_path, &passphrase)
+    })?;
+    remember_passphrase(&profile.id, &passphrase, remember)?;
+    status(&profiles, &db)
+}
+
+/// Convert the active profile's encrypted database back to plain SQLite. The passphrase is
+/// asked for again even when the database is open, and removed from the keyring.
+#[tauri::command]
+pub fn decrypt_database(
+    profiles: State<'_, ProfileManager>,
+    db: State<'_, AppDatabase>,
+    refresh_job: State<'_, RefreshJob>,
+    shop_job: State<'_, ShopCheckJob>,
+    passphrase: String,
+) -> AppResult<DatabaseStatus> {
+    ensure_idle(&refresh_job, &shop_job)?;
+    let profile = profiles.active()?;
+    let db_path = profiles.db_path(&profile.id);
+    encryption::verify_passphrase(&db_path, &passphrase)?;
+    db.rewrite(&db_path, Some(&passphrase), None, || {
+        encryption::decrypt(&db_path, &passphrase)
+    })?;
+    encryption::forget_passphrase(&profile.id)?;
+    status(&profiles, &db)
+}
+
+/// Remove the active profile's passphrase from the keyring; the next start will ask for it.
+#[tauri::command]
+pub fn forget_database_passphrase(
+    profiles: State<'_, ProfileManager>,
+    db: State<'_, AppDatabase>,
+) -> AppResult<DatabaseStatus> {
+    encryption::forget_passphrase(&profiles.active()?.id)?;
+    status(&profiles, &db)
+}
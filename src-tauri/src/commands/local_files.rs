@@ -0,0 +1,503 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::State;
+
+use crate::assets::matcher::{best_match, MatchCandidate};
+use crate::assets::scanner::scan_directory;
+use crate::assets::unitypackage::{analyze_paths, read_package_file};
+use crate::database::{self, AppDatabase};
+use crate::error::{AppError, AppResult};
+
+/// `app_settings` key holding the scanned directories as a JSON array.
+const SCAN_DIRS_KEY: &str = "local_scan_dirs";
+
+// ── Types ──────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct LocalFile {
+    pub id: i64,
+    pub path: String,
+    pub file_name: String,
+    pub size_bytes: i64,
+    /// Unix seconds
+    pub modified_at: Option<i64>,
+    pub item_id: Option<i64>,
+    pub match_score: Option<f64>,
+    pub match_reason: Option<String>,
+    /// Linked (or unlinked) by hand; rescans leave it alone
+    pub is_manual: bool,
+    pub scanned_at: String,
+}
+
+impl LocalFile {
+    fn from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Self> {
+        Ok(LocalFile {
+            id: row.get(0)?,
+            path: row.get(1)?,
+            file_name: row.get(2)?,
+            size_bytes: row.get(3)?,
+            modified_at: row.get(4)?,
+            item_id: row.get(5)?,
+            match_score: row.get(6)?,
+            match_reason: row.get(7)?,
+            is_manual: row.get(8)?,
+            scanned_at: row.get(9)?,
+        })
+    }
+}
+
+const LOCAL_FILE_COLUMNS: &str = "id, path, file_name, size_bytes, modified_at, item_id,
+     match_score, match_reason, is_manual, scanned_at";
+
+#[derive(Debug, Default, Serialize)]
+pub struct LocalScanSummary {
+    pub directories: usize,
+    pub files: usize,
+    pub matched: usize,
+    /// Previously recorded files that are gone from a scanned directory
+    pub removed: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageInspection {
+    pub local_file_id: i64,
+    pub item_id: Option<i64>,
+    pub asset_count: i64,
+    /// Avatar bases the package has folders/files for
+    pub avatars: Vec<String>,
+    /// Shaders the package bundles or references by folder name
+    pub shaders: Vec<String>,
+    pub has_prefab: bool,
+    /// Set when the file could not be read as a unitypackage
+    pub error: Option<String>,
+    pub inspected_at: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PackageInspectSummary {
+    pub inspected: usize,
+    pub failed: usize,
+    /// Already inspected and unchanged since
+    pub skipped: usize,
+}
+
+// ── Helpers ────────────────────────────────────────────
+
+fn load_scan_dirs(conn: &Connection) -> AppResult<Vec<String>> {
+    Ok(database::get_setting(conn, SCAN_DIRS_KEY)?
+        .and_then(|v| serde_json::from_str(&v).ok())
+        .unwrap_or_default())
+}
+
+/// Favorites and cached search results, deduplicated by item.
+fn load_candidates(conn: &Connection) -> AppResult<Vec<MatchCandidate>> {
+    let mut stmt = conn.prepare(
+        "SELECT item_id, name, shop_name FROM favorites WHERE deleted_at IS NULL
+         UNION
+         SELECT id, name, shop_name FROM cached_items
+         WHERE id NOT IN (SELECT item_id FROM favorites WHERE deleted_at IS NULL)",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(MatchCandidate {
+                item_id: row.get(0)?,
+                name: row.get(1)?,
+                shop_name: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Forget automatically matched files that a complete scan of their root did not find.
+/// Files under roots that were not fully listed (an unplugged drive, the file limit) or
+/// under `unlisted` directories are kept, as are files that still exist; manual links are
+/// never dropped.
+fn prune_missing(
+    conn: &Connection,
+    scanned_roots: &[PathBuf],
+    unlisted: &[PathBuf],
+    seen: &HashSet<String>,
+) -> AppResult<usize> {
+    let known: Vec<(i64, String)> = {
+        let mut stmt = conn.prepare("SELECT id, path FROM local_files WHERE is_manual = 0")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+    let mut removed = 0;
+    for (id, path) in known {
+        let file = Path::new(&path);
+        let listed = scanned_roots.iter().any(|root| file.starts_with(root))
+            && !unlisted.iter().any(|dir| file.starts_with(dir));
+        if listed && !seen.contains(&path) && !file.exists() {
+            conn.execute("DELETE FROM local_files WHERE id = ?1", params![id])?;
+            conn.execute(
+                "DELETE FROM package_inspections WHERE local_file_id = ?1",
+                params![id],
+            )?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+// ── Commands ───────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_local_scan_dirs(db: State<'_, AppDatabase>) -> AppResult<Vec<String>> {
+    let conn = db.conn()?;
+    load_scan_dirs(&conn)
+}
+
+/// Scan directories for downloaded archives and match them to favorited or cached items.
+/// `dirs` replaces the remembered directory list; without it the last list is rescanned.
+/// Manual links survive rescans, and files that disappeared from a readable directory are
+/// forgotten.
+#[tauri::command]
+pub async fn scan_local_files(
+    db: State<'_, AppDatabase>,
+    dirs: Option<Vec<String>>,
+) -> AppResult<LocalScanSummary> {
+    let (dirs, candidates) = {
+        let conn = db.conn()?;
+        let dirs = match dirs {
+            Some(dirs) => {
+                for dir in &dirs {
+                    if !Path::new(dir).is_dir() {
+                        return Err(AppError::NotFound(format!("Directory {}", dir)));
+                    }
+                }
+                let json = serde_json::to_string(&dirs)
+                    .map_err(|e| AppError::ParseError(e.to_string()))?;
+                database::set_setting(&conn, SCAN_DIRS_KEY, &json)?;
+                dirs
+            }
+            None => load_scan_dirs(&conn)?,
+        };
+        (dirs, load_candidates(&conn)?)
+    };
+
+    let scan_dirs = dirs.clone();
+    let (results, scanned_roots, unlisted) = tauri::async_runtime::spawn_blocking(move || {
+        // Only roots that were listed in full tell us which files are gone
+        let mut listed = Vec::new();
+        let mut unlisted = Vec::new();
+        let mut results = Vec::new();
+        for dir in scan_dirs.iter().map(PathBuf::from) {
+            if std::fs::read_dir(&dir).is_err() {
+                continue;
+            }
+            let scan = scan_directory(&dir);
+            if scan.complete {
+                listed.push(dir);
+            }
+            unlisted.extend(scan.skipped);
+            results.extend(scan.files.into_iter().map(|file| {
+                let matched = best_match(&file.file_name, &candidates);
+                (file, matched)
+            }));
+        }
+        (results, listed, unlisted)
+    })
+    .await
+    .map_err(|e| AppError::ParseError(format!("Scan failed: {}", e)))?;
+
+    let mut summary = LocalScanSummary {
+        directories: dirs.len(),
+        files: results.len(),
+        ..Default::default()
+    };
+
+    let mut conn = db.conn_mut()?;
+    let tx = conn.transaction()?;
+    let mut seen = HashSet::new();
+    for (file, matched) in &results {
+        let path = file.path.to_string_lossy().into_owned();
+        tx.execute(
+            "INSERT INTO local_files (path, file_name, size_bytes, modified_at, item_id,
+                                      match_score, match_reason, scanned_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, datetime('now'))
+             ON CONFLICT(path) DO UPDATE SET
+                file_name = excluded.file_name,
+                size_bytes = excluded.size_bytes,
+                modified_at = excluded.modified_at,
+                item_id = CASE WHEN is_manual THEN item_id ELSE excluded.item_id END,
+                match_score = CASE WHEN is_manual THEN match_score ELSE excluded.match_score END,
+                match_reason = CASE WHEN is_manual THEN match_reason ELSE excluded.match_reason END,
+                scanned_at = excluded.scanned_at",
+            params![
+                path,
+                file.file_name,
+                file.size_bytes as i64,
+                file.modified,
+                matched.as_ref().map(|m| m.item_id),
+                matched.as_ref().map(|m| m.score),
+                matched.as_ref().map(|m| m.reason.clone()),
+            ],
+        )?;
+        seen.insert(path);
+    }
+
+    summary.removed = prune_missing(&tx, &scanned_roots, &unlisted, &seen)?;
+    summary.matched = tx.query_row(
+        "SELECT COUNT(*) FROM local_files WHERE item_id IS NOT NULL",
+        [],
+        |row| row.get(0),
+    )?;
+    tx.commit()?;
+    Ok(summary)
+}
+
+/// Recorded files, optionally only those linked to one item.
+#[tauri::command]
+pub fn get_local_files(
+    db: State<'_, AppDatabase>,
+    item_id: Option<i64>,
+) -> AppResult<Vec<LocalFile>> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM local_files
+         WHERE ?1 IS NULL OR item_id = ?1
+         ORDER BY file_name COLLATE NOCASE",
+        LOCAL_FILE_COLUMNS
+    ))?;
+    let rows = stmt
+        .query_map(params![item_id], LocalFile::from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Batch: get downloaded files for all linked items in one query.
+/// Returns a map of item_id -> [file, ...]
+#[tauri::command]
+pub fn get_all_local_files_batch(
+    db: State<'_, AppDatabase>,
+) -> AppResult<HashMap<i64, Vec<LocalFile>>> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM local_files WHERE item_id IS NOT NULL
+         ORDER BY item_id, file_name COLLATE NOCASE",
+        LOCAL_FILE_COLUMNS
+    ))?;
+    let mut map: HashMap<i64, Vec<LocalFile>> = HashMap::new();
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let file = LocalFile::from_row(row)?;
+        if let Some(item_id) = file.item_id {
+            map.entry(item_id).or_default().push(file);
+        }
+    }
+    Ok(map)
+}
+
+/// Link a file to an item by hand, or mark it as unrelated with `None`.
+#[tauri::command]
+pub fn link_local_file(db: State<'_, AppDatabase>, id: i64, item_id: Option<i64>) -> AppResult<()> {
+    let conn = db.conn()?;
+    let affected = conn.execute(
+        "UPDATE local_files
+         SET item_id = ?1, is_manual = 1, match_score = NULL, match_reason = 'manual'
+         WHERE id = ?2",
+        params![item_id, id],
+    )?;
+    if affected == 0 {
+        return Err(AppError::NotFound(format!("Local file {}", id)));
+    }
+    Ok(())
+}
+
+/// Read the asset list of linked `.unitypackage` files and detect target avatars, shader
+/// dependencies and prefabs. Packages inspected before are skipped unless the file changed
+/// or `force` is set.
+#[tauri::command]
+pub async fn inspect_local_packages(
+    db: State<'_, AppDatabase>,
+    force: Option<bool>,
+) -> AppResult<PackageInspectSummary> {
+    let force = force.unwrap_or(false);
+    let (pending, skipped) = {
+        let conn = db.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT l.id, l.path, l.modified_at,
+                    p.local_file_id IS NOT NULL AND p.file_modified IS l.modified_at
+             FROM local_files l
+             LEFT JOIN package_inspections p ON p.local_file_id = l.id
+             WHERE l.item_id IS NOT NULL AND lower(l.file_name) LIKE '%.unitypackage'",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<i64>>(2)?,
+                    row.get::<_, bool>(3)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        let total = rows.len();
+        let pending: Vec<_> = rows
+            .into_iter()
+            .filter(|(_, _, _, up_to_date)| force || !up_to_date)
+            .map(|(id, path, modified, _)| (id, path, modified))
+            .collect();
+        let skipped = total - pending.len();
+        (pending, skipped)
+    };
+
+    // Decompressing large packages takes a while; keep it off the async runtime
+    let results = tauri::async_runtime::spawn_blocking(move || {
+        pending
+            .into_iter()
+            .map(|(id, path, modified)| {
+                let result = read_package_file(Path::new(&path));
+                (id, modified, result)
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| AppError::ParseError(format!("Inspection failed: {}", e)))?;
+
+    let mut summary = PackageInspectSummary {
+        skipped,
+        ..Default::default()
+    };
+    let mut conn = db.conn_mut()?;
+    let tx = conn.transaction()?;
+    for (id, modified, result) in results {
+        let (paths, error) = match result {
+            Ok(paths) => {
+                summary.inspected += 1;
+                (paths, None)
+            }
+            Err(e) => {
+                summary.failed += 1;
+                (Vec::new(), Some(e.to_string()))
+            }
+        };
+        let analysis = analyze_paths(&paths);
+        let to_json =
+            |v: &Vec<String>| serde_json::to_string(v).unwrap_or_else(|_| "[]".to_string());
+        tx.execute(
+            "INSERT OR REPLACE INTO package_inspections
+                (local_file_id, file_modified, asset_count, avatars_json, shaders_json,
+                 has_prefab, paths_json, error, inspected_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, datetime('now'))",
+            params![
+                id,
+                modified,
+                analysis.asset_count as i64,
+                to_json(&analysis.avatars),
+                to_json(&analysis.shaders),
+                analysis.has_prefab,
+                to_json(&paths),
+                error,
+            ],
+        )?;
+    }
+    tx.commit()?;
+    Ok(summary)
+}
+
+/// Stored inspection results, optionally only for files linked to one item.
+#[tauri::command]
+pub fn get_package_inspections(
+    db: State<'_, AppDatabase>,
+    item_id: Option<i64>,
+) -> AppResult<Vec<PackageInspection>> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT p.local_file_id, l.item_id, p.asset_count, p.avatars_json, p.shaders_json,
+                p.has_prefab, p.error, p.inspected_at
+         FROM package_inspections p
+         INNER JOIN local_files l ON l.id = p.local_file_id
+         WHERE ?1 IS NULL OR l.item_id = ?1
+         ORDER BY l.file_name COLLATE NOCASE",
+    )?;
+    let rows = stmt
+        .query_map(params![item_id], |row| {
+            let avatars: String = row.get(3)?;
+            let shaders: String = row.get(4)?;
+            Ok(PackageInspection {
+                local_file_id: row.get(0)?,
+                item_id: row.get(1)?,
+                asset_count: row.get(2)?,
+                avatars: serde_json::from_str(&avatars).unwrap_or_default(),
+                shaders: serde_json::from_str(&shaders).unwrap_or_default(),
+                has_prefab: row.get(5)?,
+                error: row.get(6)?,
+                inspected_at: row.get(7)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Project paths of every asset in an inspected package.
+#[tauri::command]
+pub fn get_package_asset_paths(
+    db: State<'_, AppDatabase>,
+    local_file_id: i64,
+) -> AppResult<Vec<String>> {
+    let conn = db.conn()?;
+    let json: String = conn
+        .query_row(
+            "SELECT paths_json FROM package_inspections WHERE local_file_id = ?1",
+            params![local_file_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => {
+                AppError::NotFound(format!("Inspection for local file {}", local_file_id))
+            }
+            other => other.into(),
+        })?;
+    Ok(serde_json::from_str(&json).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rescan_with_a_missing_root_keeps_its_files_and_manual_links() {
+        let conn = AppDatabase::open_connection(Path::new(":memory:"), None).unwrap();
+        conn.execute_batch(
+            "INSERT INTO local_files (id, path, file_name, is_manual) VALUES
+                 (1, '/a/kept.zip', 'kept.zip', 0), (2, '/a/gone.zip', 'gone.zip', 0),
+                 (3, '/a/manual.zip', 'manual.zip', 1), (4, '/unplugged/coat.zip', 'coat.zip', 0),
+                 (5, '/a/locked/hair.zip', 'hair.zip', 0);
+             INSERT INTO package_inspections (local_file_id, paths_json) VALUES
+                 (2, '[]'), (3, '[]'), (4, '[]'), (5, '[]');",
+        )
+        .unwrap();
+        let seen = HashSet::from(["/a/kept.zip".to_string()]);
+
+        let removed = prune_missing(
+            &conn,
+            &[PathBuf::from("/a")],
+            &[PathBuf::from("/a/locked")],
+            &seen,
+        )
+        .unwrap();
+        assert_eq!(removed, 1);
+        let left: Vec<i64> = conn
+            .prepare("SELECT id FROM local_files ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(left, vec![1, 3, 4, 5]);
+        let inspected: i64 = conn
+            .query_row("SELECT COUNT(*) FROM package_inspections", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(inspected, 3);
+    }
+}
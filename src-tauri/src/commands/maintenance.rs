@@ -0,0 +1,262 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::State;
+
+use crate::database::{self, AppDatabase, Eviction};
+use crate::error::AppResult;
+use crate::settings::Settings;
+
+/// Tags and collection memberships of items that are not favorites. Trashed favorites
+/// keep theirs so a restore is lossless, so they do not count. Foreign keys prevent these,
+/// but rows written with enforcement off still show up here and as violations.
+const ORPHAN_TAGS: &str = "FROM item_tags WHERE item_id NOT IN (SELECT item_id FROM favorites)";
+const ORPHAN_COLLECTION_ITEMS: &str =
+    "FROM collection_items WHERE item_id NOT IN (SELECT item_id FROM favorites)";
+
+// ── Types ──────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct ForeignKeyViolation {
+    pub table: String,
+    pub rowid: Option<i64>,
+    /// Table the missing row should be in
+    pub parent: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct MaintenanceReport {
+    /// Problems found by `PRAGMA integrity_check`; empty when the database is intact.
+    /// Nothing is fixed or vacuumed otherwise, since writing could make things worse.
+    pub integrity_errors: Vec<String>,
+    pub foreign_key_violations: Vec<ForeignKeyViolation>,
+    pub orphan_tags: usize,
+    pub orphan_collection_items: usize,
+    /// Distinct items the orphan rows belong to
+    pub orphan_item_ids: Vec<i64>,
+    /// Whether the orphans, violations and expired rows were removed
+    pub fixed: bool,
+    /// Cached rows and trash past their lifetime; only counted unless `fixed`
+    pub evicted: Eviction,
+    /// WAL frames copied into the database file
+    pub checkpointed_frames: i64,
+    pub vacuumed: bool,
+    pub size_before: i64,
+    pub size_after: i64,
+}
+
+// ── Helpers ────────────────────────────────────────────
+
+fn database_size(conn: &Connection) -> AppResult<i64> {
+    Ok(conn.query_row(
+        "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+        [],
+        |row| row.get(0),
+    )?)
+}
+
+fn integrity_errors(conn: &Connection) -> AppResult<Vec<String>> {
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows.into_iter().filter(|r| r != "ok").collect())
+}
+
+fn foreign_key_violations(conn: &Connection) -> AppResult<Vec<(ForeignKeyViolation, i64)>> {
+    let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                ForeignKeyViolation {
+                    table: row.get(0)?,
+                    rowid: row.get(1)?,
+                    parent: row.get(2)?,
+                },
+                row.get(3)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Resolve a violation the way the foreign key's ON DELETE action would have: clear the
+/// reference for SET NULL, otherwise delete the row.
+fn fix_violation(conn: &Connection, violation: &ForeignKeyViolation, fkid: i64) -> AppResult<()> {
+    let Some(rowid) = violation.rowid else {
+        return Ok(());
+    };
+    let action: Option<(String, String)> = conn
+        .query_row(
+            "SELECT \"from\", on_delete FROM pragma_foreign_key_list(?1) WHERE id = ?2",
+            params![violation.table, fkid],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    match action {
+        Some((column, on_delete)) if on_delete == "SET NULL" => conn.execute(
+            &format!(
+                "UPDATE \"{}\" SET \"{}\" = NULL WHERE rowid = ?1",
+                violation.table, column
+            ),
+            params![rowid],
+        )?,
+        _ => conn.execute(
+            &format!("DELETE FROM \"{}\" WHERE rowid = ?1", violation.table),
+            params![rowid],
+        )?,
+    };
+    Ok(())
+}
+
+fn maintain(conn: &mut Connection, fix: bool, vacuum: bool) -> AppResult<MaintenanceReport> {
+    let mut report = MaintenanceReport {
+        size_before: database_size(conn)?,
+        integrity_errors: integrity_errors(conn)?,
+        ..Default::default()
+    };
+    let intact = report.integrity_errors.is_empty();
+
+    let violations = foreign_key_violations(conn)?;
+    report.orphan_tags =
+        conn.query_row(&format!("SELECT COUNT(*) {}", ORPHAN_TAGS), [], |row| {
+            row.get(0)
+        })?;
+    report.orphan_collection_items = conn.query_row(
+        &format!("SELECT COUNT(*) {}", ORPHAN_COLLECTION_ITEMS),
+        [],
+        |row| row.get(0),
+    )?;
+    report.orphan_item_ids = {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT item_id {0} UNION SELECT item_id {1} ORDER BY 1",
+            ORPHAN_TAGS, ORPHAN_COLLECTION_ITEMS
+        ))?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+
+    if fix && intact {
+        let tx = conn.transaction()?;
+        for (violation, fkid) in &violations {
+            fix_violation(&tx, violation, *fkid)?;
+        }
+        tx.execute(&format!("DELETE {}", ORPHAN_TAGS), [])?;
+        tx.execute(&format!("DELETE {}", ORPHAN_COLLECTION_ITEMS), [])?;
+        tx.commit()?;
+        report.fixed = true;
+    }
+    report.foreign_key_violations = violations.into_iter().map(|(v, _)| v).collect();
+
+    if intact {
+        let settings = Settings::load(conn)?;
+        report.evicted = database::evict_expired(conn, &settings, fix)?;
+    }
+
+    let checkpoint = |conn: &Connection| -> AppResult<i64> {
+        Ok(
+            conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+                row.get::<_, i64>(2)
+            })?,
+        )
+    };
+    report.checkpointed_frames = checkpoint(conn)?;
+    if vacuum && intact {
+        conn.execute_batch("VACUUM")?;
+        // VACUUM goes through the WAL as well; fold it in so the file actually shrinks
+        checkpoint(conn)?;
+        report.vacuumed = true;
+    }
+    report.size_after = database_size(conn)?;
+    Ok(report)
+}
+
+// ── Commands ───────────────────────────────────────────
+
+/// Check integrity and foreign keys, find orphan tags, collection memberships and expired
+/// rows, and checkpoint the WAL. With `fix`, orphans, foreign key violations and expired
+/// rows are removed; with `vacuum`, the file is compacted. Both are skipped on a corrupt
+/// database.
+#[tauri::command]
+pub fn run_maintenance(
+    db: State<'_, AppDatabase>,
+    fix: bool,
+    vacuum: bool,
+) -> AppResult<MaintenanceReport> {
+    let mut conn = db.conn_mut()?;
+    maintain(&mut conn, fix, vacuum)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn conn() -> Connection {
+        AppDatabase::open_connection(Path::new(":memory:"), None).unwrap()
+    }
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    /// Rows only a connection without foreign key enforcement could have written.
+    fn insert_broken_rows(conn: &Connection) {
+        conn.execute_batch(
+            "PRAGMA foreign_keys = OFF;
+             INSERT INTO favorites (item_id, name, price, shop_id) VALUES (1, 'Knit', 500, 999);
+             INSERT INTO item_tags (item_id, tag) VALUES (1, 'coat'), (2, 'gone');
+             INSERT INTO collections (name) VALUES ('Winter');
+             INSERT INTO collection_items (collection_id, item_id) VALUES (1, 2), (1, 3);
+             PRAGMA foreign_keys = ON;",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn reports_orphans_and_violations_without_fixing() {
+        let mut conn = conn();
+        insert_broken_rows(&conn);
+        conn.execute(
+            "INSERT INTO cached_items (id, name, price, url, cached_at)
+             VALUES (5, 'Old', 100, 'https://booth.pm/ja/items/5', datetime('now', '-400 days'))",
+            [],
+        )
+        .unwrap();
+
+        let report = maintain(&mut conn, false, false).unwrap();
+        assert!(report.integrity_errors.is_empty());
+        assert_eq!((report.orphan_tags, report.orphan_collection_items), (1, 2));
+        assert_eq!(report.orphan_item_ids, vec![2, 3]);
+        assert_eq!(report.foreign_key_violations.len(), 4);
+        assert!(!report.fixed);
+        assert_eq!(report.evicted.cached_items, 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM item_tags"), 2);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM cached_items"), 1);
+    }
+
+    #[test]
+    fn fix_resolves_violations_like_their_on_delete_action() {
+        let mut conn = conn();
+        insert_broken_rows(&conn);
+
+        let report = maintain(&mut conn, true, false).unwrap();
+        assert!(report.fixed);
+        // SET NULL for the missing shop, delete for rows of items that are not favorites
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM favorites WHERE shop_id IS NULL"
+            ),
+            1
+        );
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM item_tags"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM collection_items"), 0);
+
+        let again = maintain(&mut conn, false, false).unwrap();
+        assert!(again.foreign_key_violations.is_empty());
+        assert_eq!((again.orphan_tags, again.orphan_collection_items), (0, 0));
+    }
+}
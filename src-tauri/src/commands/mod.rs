@@ -1,5 +1,22 @@
+pub mod alerts;
+pub mod budget;
 pub mod collections;
+pub mod content_policy;
+pub mod currency;
 pub mod db;
+pub mod encryption;
+pub mod local_files;
+pub mod maintenance;
+pub mod owned;
+pub mod profiles;
+pub mod purchase_import;
+pub mod refresh;
+pub mod search_analytics;
+pub mod search_history;
+pub mod settings;
+pub mod shops;
 pub mod stats;
+pub mod sync;
 pub mod translation;
+pub mod trash;
 pub mod updater;
@@ -0,0 +1,192 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::database::AppDatabase;
+use crate::dates::validate_date;
+use crate::error::{AppError, AppResult};
+
+// ── Validation ────────────────────────────────────────
+
+fn validate_text(value: Option<String>, field: &str, max_len: usize) -> AppResult<Option<String>> {
+    let value = value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+    if let Some(v) = &value {
+        if v.chars().count() > max_len {
+            return Err(AppError::ParseError(format!(
+                "{} too long (max {} chars)",
+                field, max_len
+            )));
+        }
+    }
+    Ok(value)
+}
+
+// ── Types ──────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct OwnedItem {
+    pub id: i64,
+    pub item_id: i64,
+    /// Stored name, falling back to the favorite or cached item name
+    pub name: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub shop_name: Option<String>,
+    pub variation: Option<String>,
+    /// `None` when the purchase was imported from a page that does not show prices
+    pub price_paid: Option<i64>,
+    pub purchased_at: Option<String>,
+    pub license: Option<String>,
+    pub note: Option<String>,
+    pub is_favorite: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OwnedItemParams {
+    pub item_id: i64,
+    pub name: Option<String>,
+    pub variation: Option<String>,
+    pub price_paid: Option<i64>,
+    pub purchased_at: Option<String>,
+    pub license: Option<String>,
+    pub note: Option<String>,
+}
+
+impl OwnedItemParams {
+    fn validated(self) -> AppResult<Self> {
+        if self.item_id <= 0 {
+            return Err(AppError::ParseError("Invalid item id".to_string()));
+        }
+        if self.price_paid.is_some_and(|p| p < 0) {
+            return Err(AppError::ParseError(
+                "Price paid cannot be negative".to_string(),
+            ));
+        }
+        Ok(OwnedItemParams {
+            item_id: self.item_id,
+            name: validate_text(self.name, "Name", 200)?,
+            variation: validate_text(self.variation, "Variation", 200)?,
+            price_paid: self.price_paid,
+            purchased_at: self
+                .purchased_at
+                .filter(|d| !d.trim().is_empty())
+                .map(|d| validate_date(&d))
+                .transpose()?,
+            license: validate_text(self.license, "License", 200)?,
+            note: validate_text(self.note, "Note", 2000)?,
+        })
+    }
+}
+
+// ── Helpers ────────────────────────────────────────────
+
+pub(crate) fn insert_owned_item(conn: &Connection, params: OwnedItemParams) -> AppResult<i64> {
+    let p = params.validated()?;
+    conn.execute(
+        "INSERT INTO owned_items (item_id, name, variation, price_paid, purchased_at, license, note)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            p.item_id,
+            p.name,
+            p.variation,
+            p.price_paid,
+            p.purchased_at,
+            p.license,
+            p.note,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+// ── Commands ───────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_owned_items(db: State<'_, AppDatabase>) -> AppResult<Vec<OwnedItem>> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT o.id, o.item_id, COALESCE(o.name, f.name, c.name),
+                COALESCE(f.thumbnail_url, json_extract(c.images_json, '$[0]')),
+                COALESCE(f.shop_name, c.shop_name),
+                o.variation, o.price_paid, o.purchased_at, o.license, o.note,
+                f.id IS NOT NULL, o.created_at
+         FROM owned_items o
+         LEFT JOIN favorites f ON f.item_id = o.item_id AND f.deleted_at IS NULL
+         LEFT JOIN cached_items c ON c.id = o.item_id
+         ORDER BY COALESCE(o.purchased_at, o.created_at) DESC, o.id DESC",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(OwnedItem {
+                id: row.get(0)?,
+                item_id: row.get(1)?,
+                name: row.get(2)?,
+                thumbnail_url: row.get(3)?,
+                shop_name: row.get(4)?,
+                variation: row.get(5)?,
+                price_paid: row.get(6)?,
+                purchased_at: row.get(7)?,
+                license: row.get(8)?,
+                note: row.get(9)?,
+                is_favorite: row.get(10)?,
+                created_at: row.get(11)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Distinct Booth item IDs we own at least one purchase of (for "owned" badges).
+#[tauri::command]
+pub fn get_owned_item_ids(db: State<'_, AppDatabase>) -> AppResult<Vec<i64>> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare("SELECT DISTINCT item_id FROM owned_items ORDER BY item_id")?;
+    let rows = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<i64>, _>>()?;
+    Ok(rows)
+}
+
+#[tauri::command]
+pub fn add_owned_item(db: State<'_, AppDatabase>, params: OwnedItemParams) -> AppResult<i64> {
+    let conn = db.conn()?;
+    insert_owned_item(&conn, params)
+}
+
+#[tauri::command]
+pub fn update_owned_item(
+    db: State<'_, AppDatabase>,
+    id: i64,
+    params: OwnedItemParams,
+) -> AppResult<()> {
+    let p = params.validated()?;
+    let conn = db.conn()?;
+    let affected = conn.execute(
+        "UPDATE owned_items
+         SET item_id = ?1, name = ?2, variation = ?3, price_paid = ?4, purchased_at = ?5,
+             license = ?6, note = ?7
+         WHERE id = ?8",
+        params![
+            p.item_id,
+            p.name,
+            p.variation,
+            p.price_paid,
+            p.purchased_at,
+            p.license,
+            p.note,
+            id,
+        ],
+    )?;
+    if affected == 0 {
+        return Err(AppError::NotFound(format!("Owned item {}", id)));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn remove_owned_item(db: State<'_, AppDatabase>, id: i64) -> AppResult<()> {
+    let conn = db.conn()?;
+    conn.execute("DELETE FROM owned_items WHERE id = ?1", params![id])?;
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use std::path::Path;
+
+use tauri::{AppHandle, Emitter, State};
+
+use crate::database::AppDatabase;
+use crate::encryption::{self, Access};
+use crate::error::{AppError, AppResult};
+use crate::profiles::{self, CopySummary, Profile, ProfileManager, ProfileSummary};
+use crate::settings::Settings;
+
+use super::refresh::RefreshJob;
+use super::settings::emit_settings_changed;
+use super::shops::ShopCheckJob;
+
+// ── Helpers ────────────────────────────────────────────
+
+/// Refuse to replace the open database while a background job writes to it.
+pub(crate) fn ensure_idle(refresh_job: &RefreshJob, shop_job: &ShopCheckJob) -> AppResult<()> {
+    if refresh_job.is_running() {
+        return Err(AppError::Busy("Favorite refresh".to_string()));
+    }
+    if shop_job.is_running() {
+        return Err(AppError::Busy("Shop check".to_string()));
+    }
+    Ok(())
+}
+
+// ── Commands ───────────────────────────────────────────
+
+#[tauri::command]
+pub fn list_profiles(profiles: State<'_, ProfileManager>) -> AppResult<Vec<ProfileSummary>> {
+    profiles.list()
+}
+
+#[tauri::command]
+pub fn get_active_profile(profiles: State<'_, ProfileManager>) -> AppResult<Profile> {
+    profiles.active()
+}
+
+#[tauri::command]
+pub fn create_profile(profiles: State<'_, ProfileManager>, name: String) -> AppResult<Profile> {
+    profiles.create(&name)
+}
+
+#[tauri::command]
+pub fn rename_profile(
+    profiles: State<'_, ProfileManager>,
+    id: String,
+    name: String,
+) -> AppResult<Profile> {
+    profiles.rename(&id, &name)
+}
+
+#[tauri::command]
+pub fn delete_profile(profiles: State<'_, ProfileManager>, id: String) -> AppResult<()> {
+    profiles.delete(&id)
+}
+
+/// Open another profile's database in place of the current one and remember it for the next
+/// start. Emits `profile-changed`, plus `settings-changed` when the profiles' settings differ.
+/// An encrypted profile without a passphrase in the keyring is left locked until
+/// `unlock_database`. Refused while a background job is writing to the current database.
+#[tauri::command]
+pub fn switch_profile(
+    app: AppHandle,
+    profiles: State<'_, ProfileManager>,
+    db: State<'_, AppDatabase>,
+    refresh_job: State<'_, RefreshJob>,
+    shop_job: State<'_, ShopCheckJob>,
+    id: String,
+) -> AppResult<Profile> {
+    ensure_idle(&refresh_job, &shop_job)?;
+    let profile = profiles.get(&id)?;
+    let previous = db.conn().and_then(|conn| Settings::load(&conn)).ok();
+    encryption::open_profile(&db, &profile.id, &profiles.db_path(&profile.id))?;
+    profiles.set_active(&profile.id)?;
+
+    let _ = app.emit("profile-changed", profile.clone());
+    let current = db.conn().and_then(|conn| Settings::load(&conn));
+    if let (Some(previous), Ok(settings)) = (previous, current) {
+        let changed = previous.diff(&settings);
+        if !changed.is_empty() {
+            emit_settings_changed(&app, &settings, &changed);
+        }
+    }
+    Ok(profile)
+}
+
+/// Copy favorites (all, or `item_ids`) with tags and collections from one profile to another.
+#[tauri::command]
+pub fn copy_profile_data(
+    profiles: State<'_, ProfileManager>,
+    db: State<'_, AppDatabase>,
+    source_id: String,
+    target_id: String,
+    item_ids: Option<Vec<i64>>,
+) -> AppResult<CopySummary> {
+    if source_id == target_id {
+        return Err(AppError::ParseError(
+            "Choose two different profiles".to_string(),
+        ));
+    }
+    let source = profiles.get(&source_id)?;
+    let target = profiles.get(&target_id)?;
+    let (source_path, target_path) = (profiles.db_path(&source.id), profiles.db_path(&target.id));
+    let key = |profile: &Profile, path: &Path| match encryption::access(&profile.id, path) {
+        Access::Plain => Ok(None),
+        Access::Passphrase(key) => Ok(Some(key)),
+        Access::NeedsPassphrase => Err(AppError::ParseError(format!(
+            "Profile '{}' is encrypted; save its passphrase to copy its data",
+            profile.name
+        ))),
+    };
+    let source_key = key(&source, &source_path)?;
+    let target_key = key(&target, &target_path)?;
+    // Keep the app from writing to the active database while the copy runs
+    let _conn = db.conn()?;
+    profiles::copy_favorites(
+        &source_path,
+        source_key.as_deref(),
+        &target_path,
+        target_key.as_deref(),
+        item_ids.as_deref(),
+    )
+}
@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::State;
+
+use crate::booth::purchase_parser::{parse_purchase_document, ParsedPurchase};
+use crate::database::AppDatabase;
+use crate::error::{AppError, AppResult};
+
+use super::owned::{insert_owned_item, OwnedItemParams};
+
+/// Saved pages are a few hundred KB; anything far larger is not a Booth export.
+const MAX_IMPORT_FILE_BYTES: u64 = 20 * 1024 * 1024;
+
+// ── Types ──────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct PurchaseImportFile {
+    pub path: String,
+    pub purchases: Vec<ParsedPurchase>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PurchaseImportReport {
+    pub files: usize,
+    pub parsed: usize,
+    pub imported: usize,
+    /// Purchases already in the library (same item, purchase date and price)
+    pub duplicates: usize,
+    /// Recorded purchases that gained their missing date or price
+    pub completed: usize,
+    pub errors: Vec<String>,
+}
+
+// ── Helpers ────────────────────────────────────────────
+
+fn read_document(path: &str) -> AppResult<String> {
+    let path = Path::new(path);
+    let meta = std::fs::metadata(path)
+        .map_err(|e| AppError::NotFound(format!("{}: {}", path.display(), e)))?;
+    if !meta.is_file() {
+        return Err(AppError::ParseError(format!(
+            "{} is not a file",
+            path.display()
+        )));
+    }
+    if meta.len() > MAX_IMPORT_FILE_BYTES {
+        return Err(AppError::ParseError(format!(
+            "{} is too large to be a Booth export",
+            path.display()
+        )));
+    }
+    let bytes = std::fs::read(path)
+        .map_err(|e| AppError::ParseError(format!("{}: {}", path.display(), e)))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn parse_files(paths: &[String]) -> Vec<PurchaseImportFile> {
+    paths
+        .iter()
+        .map(|path| match read_document(path) {
+            Ok(content) => PurchaseImportFile {
+                path: path.clone(),
+                purchases: parse_purchase_document(&content),
+                error: None,
+            },
+            Err(e) => PurchaseImportFile {
+                path: path.clone(),
+                purchases: Vec::new(),
+                error: Some(e.to_string()),
+            },
+        })
+        .collect()
+}
+
+/// Recorded purchases matching item, date and price. Undated or unpriced records (library
+/// pages) only tell us the item is owned at all, so they match any date or price.
+fn recorded_count(conn: &Connection, purchase: &ParsedPurchase) -> AppResult<usize> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM owned_items
+         WHERE item_id = ?1 AND (?2 IS NULL OR date(purchased_at) = ?2)
+           AND (?3 IS NULL OR price_paid = ?3)",
+        params![purchase.item_id, purchase.purchased_at, purchase.price_paid],
+        |row| row.get(0),
+    )?;
+    Ok(count as usize)
+}
+
+/// A recorded purchase of the item that lacks the date or price `purchase` carries and
+/// agrees with it otherwise, such as a library page row met again on an order page.
+fn incomplete_record(conn: &Connection, purchase: &ParsedPurchase) -> AppResult<Option<i64>> {
+    Ok(conn
+        .query_row(
+            "SELECT id FROM owned_items
+             WHERE item_id = ?1
+               AND (purchased_at IS NULL OR ?2 IS NULL OR date(purchased_at) = ?2)
+               AND (price_paid IS NULL OR ?3 IS NULL OR price_paid = ?3)
+               AND ((purchased_at IS NULL AND ?2 IS NOT NULL)
+                    OR (price_paid IS NULL AND ?3 IS NOT NULL))
+             ORDER BY id LIMIT 1",
+            params![purchase.item_id, purchase.purchased_at, purchase.price_paid],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Add the purchases of one file. The same item can be bought twice on a day (two
+/// variations), so the n-th identical purchase in a file is only a duplicate when the
+/// library already holds n of them. A purchase that fills in the date or price of a
+/// recorded one completes that record instead of adding another.
+fn import_purchases(
+    conn: &Connection,
+    path: &str,
+    purchases: Vec<ParsedPurchase>,
+    report: &mut PurchaseImportReport,
+) -> AppResult<()> {
+    let mut seen: HashMap<(i64, Option<String>, Option<i64>), usize> = HashMap::new();
+    for purchase in purchases {
+        let occurrence = seen
+            .entry((
+                purchase.item_id,
+                purchase.purchased_at.clone(),
+                purchase.price_paid,
+            ))
+            .or_default();
+        *occurrence += 1;
+        if recorded_count(conn, &purchase)? >= *occurrence {
+            report.duplicates += 1;
+            continue;
+        }
+        if let Some(id) = incomplete_record(conn, &purchase)? {
+            conn.execute(
+                "UPDATE owned_items
+                 SET purchased_at = COALESCE(purchased_at, ?2),
+                     price_paid = COALESCE(price_paid, ?3),
+                     name = COALESCE(name, ?4)
+                 WHERE id = ?1",
+                params![
+                    id,
+                    purchase.purchased_at,
+                    purchase.price_paid,
+                    purchase.name
+                ],
+            )?;
+            report.completed += 1;
+            continue;
+        }
+        let params = OwnedItemParams {
+            item_id: purchase.item_id,
+            name: purchase.name,
+            variation: None,
+            price_paid: purchase.price_paid,
+            purchased_at: purchase.purchased_at,
+            license: None,
+            note: None,
+        };
+        match insert_owned_item(conn, params) {
+            Ok(_) => report.imported += 1,
+            Err(e) => report
+                .errors
+                .push(format!("{} (item {}): {}", path, purchase.item_id, e)),
+        }
+    }
+    Ok(())
+}
+
+// ── Commands ───────────────────────────────────────────
+
+/// Parse saved Booth library/order pages or emails without writing anything.
+#[tauri::command]
+pub fn preview_purchase_import(paths: Vec<String>) -> Vec<PurchaseImportFile> {
+    parse_files(&paths)
+}
+
+/// Parse saved Booth library/order pages or emails and add the purchases to the owned library.
+/// Purchases that are already recorded are skipped, so re-importing a file is harmless.
+#[tauri::command]
+pub fn import_purchase_history(
+    db: State<'_, AppDatabase>,
+    paths: Vec<String>,
+) -> AppResult<PurchaseImportReport> {
+    let files = parse_files(&paths);
+    let mut report = PurchaseImportReport {
+        files: files.len(),
+        ..Default::default()
+    };
+
+    let mut conn = db.conn_mut()?;
+    let tx = conn.transaction()?;
+    for file in files {
+        if let Some(error) = file.error {
+            report.errors.push(error);
+            continue;
+        }
+        report.parsed += file.purchases.len();
+        import_purchases(&tx, &file.path, file.purchases, &mut report)?;
+    }
+    tx.commit()?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reimport_keeps_both_variations_once() {
+        let dir = std::env::temp_dir().join(format!("bh-purchase-import-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let conn = AppDatabase::open_connection(&dir.join("test.db"), None).unwrap();
+        let purchases = parse_purchase_document(include_str!(
+            "../../tests/fixtures/purchases/order_variations.html"
+        ));
+
+        let mut report = PurchaseImportReport::default();
+        import_purchases(&conn, "first", purchases.clone(), &mut report).unwrap();
+        import_purchases(&conn, "again", purchases, &mut report).unwrap();
+        assert_eq!((report.imported, report.duplicates), (2, 2));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn order_page_completes_a_library_page_record() {
+        let conn = AppDatabase::open_connection(Path::new(":memory:"), None).unwrap();
+        let purchase = |purchased_at: Option<&str>, price_paid| ParsedPurchase {
+            item_id: 10,
+            name: Some("Knit".to_string()),
+            purchased_at: purchased_at.map(str::to_string),
+            price_paid,
+        };
+
+        let mut report = PurchaseImportReport::default();
+        import_purchases(&conn, "library", vec![purchase(None, None)], &mut report).unwrap();
+        let price: Option<i64> = conn
+            .query_row("SELECT price_paid FROM owned_items", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(price, None);
+
+        let order = vec![purchase(Some("2024-03-01"), Some(500))];
+        import_purchases(&conn, "order", order.clone(), &mut report).unwrap();
+        import_purchases(&conn, "order again", order, &mut report).unwrap();
+        import_purchases(
+            &conn,
+            "library again",
+            vec![purchase(None, None)],
+            &mut report,
+        )
+        .unwrap();
+        assert_eq!(
+            (report.imported, report.completed, report.duplicates),
+            (1, 1, 2)
+        );
+        let row: (i64, Option<String>, Option<i64>) = conn
+            .query_row(
+                "SELECT COUNT(*), MAX(purchased_at), MAX(price_paid) FROM owned_items",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(row, (1, Some("2024-03-01".to_string()), Some(500)));
+    }
+}
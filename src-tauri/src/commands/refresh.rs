@@ -0,0 +1,468 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::booth::client::{BoothClient, ItemFetch};
+use crate::booth::models::ItemAvailability;
+use crate::database::AppDatabase;
+use crate::entities::{ensure_category, ensure_shop, item_shop_subdomain};
+use crate::error::{AppError, AppResult};
+use crate::settings::request_interval;
+
+use super::alerts::{deliver_price_alert, record_price_alert};
+use super::db::upsert_cached_item;
+
+// ── Types ──────────────────────────────────────────────
+
+/// Tracks the single background refresh job so it cannot be started twice.
+#[derive(Default)]
+pub struct RefreshJob {
+    running: AtomicBool,
+    cancel_requested: AtomicBool,
+}
+
+impl RefreshJob {
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FavoriteChange {
+    pub id: i64,
+    pub item_id: i64,
+    pub name: Option<String>,
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub changed_at: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshProgress {
+    pub done: usize,
+    pub total: usize,
+    pub item_id: i64,
+    pub availability: Option<ItemAvailability>,
+    pub changes: Vec<FieldChange>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshSummary {
+    pub total: usize,
+    pub checked: usize,
+    pub changed: usize,
+    pub unavailable: usize,
+    pub failed: usize,
+    pub cancelled: bool,
+}
+
+// ── Snapshot update ────────────────────────────────────
+
+/// Mirror a fetch result into `cached_items`: found items are re-cached, unavailable ones
+/// only get their status and check time updated (if they are cached at all).
+pub(crate) fn record_cached_fetch(
+    conn: &Connection,
+    item_id: i64,
+    fetch: &ItemFetch,
+) -> AppResult<()> {
+    match fetch {
+        ItemFetch::Found(item) => upsert_cached_item(conn, item),
+        ItemFetch::Unavailable(availability) => {
+            conn.execute(
+                "UPDATE cached_items SET availability = ?1, checked_at = datetime('now') WHERE id = ?2",
+                params![availability.as_str(), item_id],
+            )?;
+            Ok(())
+        }
+    }
+}
+
+struct Snapshot {
+    name: String,
+    price: i64,
+    price_max: Option<i64>,
+    thumbnail_url: Option<String>,
+    category_name: Option<String>,
+    shop_name: Option<String>,
+    availability: ItemAvailability,
+}
+
+fn diff_field(
+    changes: &mut Vec<FieldChange>,
+    field: &str,
+    old_value: Option<String>,
+    new_value: Option<String>,
+) {
+    if old_value != new_value {
+        changes.push(FieldChange {
+            field: field.to_string(),
+            old_value,
+            new_value,
+        });
+    }
+}
+
+/// Apply a fetch result to a favorite's snapshot, log every changed field and
+/// stamp `last_checked_at`. Unavailable items keep their last known snapshot.
+pub(crate) fn apply_fetch_result(
+    conn: &mut Connection,
+    item_id: i64,
+    fetch: &ItemFetch,
+) -> AppResult<Vec<FieldChange>> {
+    let tx = conn.transaction()?;
+    let old = tx
+        .query_row(
+            "SELECT name, price, thumbnail_url, category_name, shop_name, availability, price_max
+             FROM favorites WHERE item_id = ?1",
+            params![item_id],
+            |row| {
+                Ok(Snapshot {
+                    name: row.get(0)?,
+                    price: row.get(1)?,
+                    thumbnail_url: row.get(2)?,
+                    category_name: row.get(3)?,
+                    shop_name: row.get(4)?,
+                    availability: ItemAvailability::from_db(&row.get::<_, String>(5)?),
+                    price_max: row.get(6)?,
+                })
+            },
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => {
+                AppError::NotFound(format!("Favorite {}", item_id))
+            }
+            other => other.into(),
+        })?;
+
+    // Fields Booth omitted keep their previous value rather than being wiped
+    let new = match fetch {
+        ItemFetch::Found(item) => Snapshot {
+            name: if item.name.is_empty() {
+                old.name.clone()
+            } else {
+                item.name.clone()
+            },
+            price: item.min_price(),
+            price_max: Some(item.max_price()).filter(|max| *max > item.min_price()),
+            thumbnail_url: item
+                .images
+                .first()
+                .cloned()
+                .or_else(|| old.thumbnail_url.clone()),
+            category_name: item
+                .category_name
+                .clone()
+                .or_else(|| old.category_name.clone()),
+            shop_name: item.shop_name.clone().or_else(|| old.shop_name.clone()),
+            availability: ItemAvailability::Available,
+        },
+        ItemFetch::Unavailable(availability) => Snapshot {
+            name: old.name.clone(),
+            price: old.price,
+            price_max: old.price_max,
+            thumbnail_url: old.thumbnail_url.clone(),
+            category_name: old.category_name.clone(),
+            shop_name: old.shop_name.clone(),
+            availability: *availability,
+        },
+    };
+
+    let mut changes = Vec::new();
+    diff_field(&mut changes, "name", Some(old.name), Some(new.name.clone()));
+    diff_field(
+        &mut changes,
+        "price",
+        Some(old.price.to_string()),
+        Some(new.price.to_string()),
+    );
+    diff_field(
+        &mut changes,
+        "price_max",
+        old.price_max.map(|p| p.to_string()),
+        new.price_max.map(|p| p.to_string()),
+    );
+    diff_field(
+        &mut changes,
+        "thumbnail_url",
+        old.thumbnail_url,
+        new.thumbnail_url.clone(),
+    );
+    diff_field(
+        &mut changes,
+        "category_name",
+        old.category_name,
+        new.category_name.clone(),
+    );
+    diff_field(
+        &mut changes,
+        "shop_name",
+        old.shop_name,
+        new.shop_name.clone(),
+    );
+    diff_field(
+        &mut changes,
+        "availability",
+        Some(old.availability.as_str().to_string()),
+        Some(new.availability.as_str().to_string()),
+    );
+
+    // Unavailable items keep their shop and category links
+    let (shop_id, category_id) = match fetch {
+        ItemFetch::Found(item) => (
+            ensure_shop(
+                &tx,
+                new.shop_name.as_deref(),
+                item_shop_subdomain(item).as_deref(),
+            )?,
+            ensure_category(&tx, new.category_name.as_deref())?,
+        ),
+        ItemFetch::Unavailable(_) => (None, None),
+    };
+    tx.execute(
+        "UPDATE favorites
+         SET name = ?1, price = ?2, thumbnail_url = ?3, category_name = ?4, shop_name = ?5,
+             availability = ?6, last_checked_at = datetime('now'),
+             shop_id = COALESCE(?8, shop_id), category_id = COALESCE(?9, category_id),
+             price_max = ?10
+         WHERE item_id = ?7",
+        params![
+            new.name,
+            new.price,
+            new.thumbnail_url,
+            new.category_name,
+            new.shop_name,
+            new.availability.as_str(),
+            item_id,
+            shop_id,
+            category_id,
+            new.price_max,
+        ],
+    )?;
+    if let ItemFetch::Found(item) = fetch {
+        tx.execute(
+            "UPDATE favorites SET is_adult = ?1, published_at = COALESCE(?2, published_at)
+             WHERE item_id = ?3",
+            params![item.looks_adult(), item.published_at, item_id],
+        )?;
+    }
+    for change in &changes {
+        tx.execute(
+            "INSERT INTO favorite_changes (item_id, field, old_value, new_value, changed_at)
+             VALUES (?1, ?2, ?3, ?4, datetime('now'))",
+            params![item_id, change.field, change.old_value, change.new_value],
+        )?;
+    }
+    record_cached_fetch(&tx, item_id, fetch)?;
+    tx.commit()?;
+    Ok(changes)
+}
+
+// ── Background job ─────────────────────────────────────
+
+async fn run_refresh(app: &AppHandle, item_ids: Vec<i64>) -> RefreshSummary {
+    let job = app.state::<RefreshJob>();
+    let db = app.state::<AppDatabase>();
+    let mut summary = RefreshSummary {
+        total: item_ids.len(),
+        ..Default::default()
+    };
+
+    let client = match BoothClient::new() {
+        Ok(c) => c,
+        Err(e) => {
+            log::error!("Favorite refresh aborted: {}", e);
+            summary.failed = item_ids.len();
+            return summary;
+        }
+    };
+
+    for (idx, item_id) in item_ids.iter().copied().enumerate() {
+        if job.cancel_requested.load(Ordering::SeqCst) {
+            summary.cancelled = true;
+            break;
+        }
+        if idx > 0 {
+            tokio::time::sleep(request_interval(&db)).await;
+        }
+
+        let result = match client.fetch_item(item_id).await {
+            Ok(fetch) => db
+                .conn_mut()
+                .and_then(|mut conn| apply_fetch_result(&mut conn, item_id, &fetch))
+                .map(|changes| (fetch, changes)),
+            Err(e) => Err(e),
+        };
+
+        let progress = match result {
+            Ok((fetch, changes)) => {
+                summary.checked += 1;
+                if !changes.is_empty() {
+                    summary.changed += 1;
+                }
+                match db
+                    .conn()
+                    .and_then(|conn| record_price_alert(&conn, item_id, &changes))
+                {
+                    Ok(Some(alert)) => deliver_price_alert(app, alert),
+                    Ok(None) => {}
+                    Err(e) => log::warn!("Failed to check price alerts for {}: {}", item_id, e),
+                }
+                let availability = match fetch {
+                    ItemFetch::Found(_) => ItemAvailability::Available,
+                    ItemFetch::Unavailable(a) => {
+                        summary.unavailable += 1;
+                        a
+                    }
+                };
+                RefreshProgress {
+                    done: idx + 1,
+                    total: summary.total,
+                    item_id,
+                    availability: Some(availability),
+                    changes,
+                    error: None,
+                }
+            }
+            Err(e) => {
+                log::warn!("Failed to refresh favorite {}: {}", item_id, e);
+                summary.failed += 1;
+                RefreshProgress {
+                    done: idx + 1,
+                    total: summary.total,
+                    item_id,
+                    availability: None,
+                    changes: Vec::new(),
+                    error: Some(e.to_string()),
+                }
+            }
+        };
+        let _ = app.emit("favorites-refresh-progress", progress);
+    }
+
+    summary
+}
+
+// ── Commands ───────────────────────────────────────────
+
+/// Start re-fetching favorites from Booth in the background (all of them, or only `item_ids`).
+/// Progress is reported through `favorites-refresh-progress` events and the result through
+/// `favorites-refresh-finished`. Returns the number of items queued.
+#[tauri::command]
+pub fn refresh_favorites(
+    app: AppHandle,
+    db: State<'_, AppDatabase>,
+    job: State<'_, RefreshJob>,
+    item_ids: Option<Vec<i64>>,
+) -> AppResult<usize> {
+    let queued: Vec<i64> = {
+        let conn = db.conn()?;
+        // Least recently checked first so an interrupted run resumes where it stopped
+        let mut stmt = conn.prepare(
+            "SELECT item_id FROM favorites WHERE deleted_at IS NULL
+             ORDER BY last_checked_at ASC NULLS FIRST, added_at DESC",
+        )?;
+        let all = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?;
+        match item_ids {
+            Some(ids) => all.into_iter().filter(|id| ids.contains(id)).collect(),
+            None => all,
+        }
+    };
+
+    if job.running.swap(true, Ordering::SeqCst) {
+        return Err(AppError::Busy("Favorite refresh".to_string()));
+    }
+    job.cancel_requested.store(false, Ordering::SeqCst);
+
+    let total = queued.len();
+    tauri::async_runtime::spawn(async move {
+        let summary = run_refresh(&app, queued).await;
+        app.state::<RefreshJob>()
+            .running
+            .store(false, Ordering::SeqCst);
+        let _ = app.emit("favorites-refresh-finished", summary);
+    });
+    Ok(total)
+}
+
+#[tauri::command]
+pub fn cancel_refresh_favorites(job: State<'_, RefreshJob>) {
+    if job.running.load(Ordering::SeqCst) {
+        job.cancel_requested.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Fetch a single item now and record its availability on the favorite (if any) and in the cache.
+#[tauri::command]
+pub async fn check_item_availability(
+    app: AppHandle,
+    db: State<'_, AppDatabase>,
+    item_id: i64,
+) -> AppResult<ItemAvailability> {
+    let fetch = BoothClient::new()?.fetch_item(item_id).await?;
+    let mut conn = db.conn_mut()?;
+    let is_favorite: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM favorites WHERE item_id = ?1",
+        params![item_id],
+        |row| row.get(0),
+    )?;
+    if is_favorite {
+        let changes = apply_fetch_result(&mut conn, item_id, &fetch)?;
+        if let Some(alert) = record_price_alert(&conn, item_id, &changes)? {
+            deliver_price_alert(&app, alert);
+        }
+    } else {
+        record_cached_fetch(&conn, item_id, &fetch)?;
+    }
+    Ok(match fetch {
+        ItemFetch::Found(_) => ItemAvailability::Available,
+        ItemFetch::Unavailable(a) => a,
+    })
+}
+
+/// Snapshot changes recorded by refreshes, newest first.
+#[tauri::command]
+pub fn get_favorite_changes(
+    db: State<'_, AppDatabase>,
+    item_id: Option<i64>,
+    limit: Option<i64>,
+) -> AppResult<Vec<FavoriteChange>> {
+    let limit = limit.unwrap_or(200).clamp(1, 1000);
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT fc.id, fc.item_id, f.name, fc.field, fc.old_value, fc.new_value, fc.changed_at
+         FROM favorite_changes fc
+         LEFT JOIN favorites f ON f.item_id = fc.item_id
+         WHERE ?1 IS NULL OR fc.item_id = ?1
+         ORDER BY fc.changed_at DESC, fc.id DESC
+         LIMIT ?2",
+    )?;
+    let rows = stmt
+        .query_map(params![item_id, limit], |row| {
+            Ok(FavoriteChange {
+                id: row.get(0)?,
+                item_id: row.get(1)?,
+                name: row.get(2)?,
+                field: row.get(3)?,
+                old_value: row.get(4)?,
+                new_value: row.get(5)?,
+                changed_at: row.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
@@ -0,0 +1,186 @@
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::database::AppDatabase;
+use crate::error::AppResult;
+
+/// A favorite added this long after a search (in the same app session) counts as a conversion.
+const CONVERSION_WINDOW_MINUTES: i64 = 30;
+
+// ── Types ──────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrendGranularity {
+    Day,
+    Week,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchTrend {
+    /// `YYYY-MM-DD` for days, `YYYY-Www` for weeks
+    pub period: String,
+    pub searches: i64,
+    pub distinct_keywords: i64,
+    pub zero_results: i64,
+    pub conversions: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ZeroResultKeyword {
+    pub keyword: String,
+    pub count: i64,
+    pub last_searched_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeywordConversion {
+    pub keyword: String,
+    pub searches: i64,
+    pub conversions: i64,
+    /// `conversions / searches`, 0.0–1.0
+    pub conversion_rate: f64,
+}
+
+// ── Helpers ────────────────────────────────────────────
+
+/// Identifies this run of the app, so conversions are only credited to searches made
+/// in the same session.
+pub(crate) fn session_id() -> &'static str {
+    static ID: OnceLock<String> = OnceLock::new();
+    ID.get_or_init(|| {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        format!("{:x}-{:x}", nanos, std::process::id())
+    })
+}
+
+/// Credit a newly added favorite to the latest unconverted search of this session that
+/// listed the item, if it happened within the conversion window.
+pub(crate) fn record_conversion(conn: &Connection, item_id: i64) -> AppResult<()> {
+    conn.execute(
+        "UPDATE search_history SET converted_item_id = ?1, converted_at = datetime('now')
+         WHERE id = (
+             SELECT id FROM search_history
+             WHERE session_id = ?2 AND converted_item_id IS NULL
+               AND searched_at >= datetime('now', printf('-%d minutes', ?3))
+               AND EXISTS (
+                   SELECT 1 FROM json_each(result_item_ids) WHERE value = ?1
+               )
+             ORDER BY searched_at DESC, id DESC LIMIT 1
+         )",
+        params![item_id, session_id(), CONVERSION_WINDOW_MINUTES],
+    )?;
+    Ok(())
+}
+
+// ── Commands ───────────────────────────────────────────
+
+/// Searches per day or ISO week over the last `days` days (default 90).
+#[tauri::command]
+pub fn get_search_trends(
+    db: State<'_, AppDatabase>,
+    granularity: TrendGranularity,
+    days: Option<i64>,
+) -> AppResult<Vec<SearchTrend>> {
+    let days = days.unwrap_or(90).clamp(1, 3650);
+    let period = match granularity {
+        TrendGranularity::Day => "date(searched_at)",
+        // SQLite has no ISO week; %W (Monday-based) is close enough for a trend line
+        TrendGranularity::Week => "strftime('%Y-W%W', searched_at)",
+    };
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} AS period, COUNT(*), COUNT(DISTINCT keyword),
+                SUM(CASE WHEN result_count = 0 THEN 1 ELSE 0 END),
+                SUM(CASE WHEN converted_item_id IS NOT NULL THEN 1 ELSE 0 END)
+         FROM search_history
+         WHERE searched_at >= datetime('now', printf('-%d days', ?1))
+         GROUP BY period ORDER BY period ASC",
+        period
+    ))?;
+    let rows = stmt
+        .query_map(params![days], |row| {
+            Ok(SearchTrend {
+                period: row.get(0)?,
+                searches: row.get(1)?,
+                distinct_keywords: row.get(2)?,
+                zero_results: row.get(3)?,
+                conversions: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Keywords whose most recent search returned nothing, most frequent first.
+#[tauri::command]
+pub fn get_zero_result_keywords(
+    db: State<'_, AppDatabase>,
+    limit: Option<i64>,
+) -> AppResult<Vec<ZeroResultKeyword>> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT keyword, COUNT(*) AS cnt, MAX(searched_at) FROM search_history h
+         WHERE result_count = 0
+           AND NOT EXISTS (
+               SELECT 1 FROM search_history later
+               WHERE later.keyword = h.keyword AND later.result_count > 0
+                 AND later.searched_at > h.searched_at
+           )
+         GROUP BY keyword ORDER BY cnt DESC, MAX(searched_at) DESC LIMIT ?1",
+    )?;
+    let rows = stmt
+        .query_map(params![limit.unwrap_or(50).clamp(1, 500)], |row| {
+            Ok(ZeroResultKeyword {
+                keyword: row.get(0)?,
+                count: row.get(1)?,
+                last_searched_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Share of searches per keyword that led to a favorite, for keywords searched at least
+/// `min_searches` times (default 2).
+#[tauri::command]
+pub fn get_keyword_conversion(
+    db: State<'_, AppDatabase>,
+    limit: Option<i64>,
+    min_searches: Option<i64>,
+) -> AppResult<Vec<KeywordConversion>> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT keyword, COUNT(*) AS searches,
+                SUM(CASE WHEN converted_item_id IS NOT NULL THEN 1 ELSE 0 END) AS conversions
+         FROM search_history
+         GROUP BY keyword HAVING COUNT(*) >= ?1
+         ORDER BY CAST(conversions AS REAL) / searches DESC, searches DESC LIMIT ?2",
+    )?;
+    let rows = stmt
+        .query_map(
+            params![
+                min_searches.unwrap_or(2).max(1),
+                limit.unwrap_or(50).clamp(1, 500)
+            ],
+            |row| {
+                let searches: i64 = row.get(1)?;
+                let conversions: i64 = row.get(2)?;
+                Ok(KeywordConversion {
+                    keyword: row.get(0)?,
+                    searches,
+                    conversions,
+                    conversion_rate: conversions as f64 / searches as f64,
+                })
+            },
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
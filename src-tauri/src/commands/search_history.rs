@@ -0,0 +1,155 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+use crate::database::{self, AppDatabase};
+use crate::error::{AppError, AppResult};
+use crate::settings::{SettingsPatch, SEARCH_HISTORY_PAUSED_KEY};
+
+use super::settings::change_settings;
+
+// ── Types ──────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct RecentSearch {
+    pub keyword: String,
+    pub count: i64,
+    pub last_searched_at: String,
+}
+
+// ── Helpers ────────────────────────────────────────────
+
+pub(crate) fn is_paused(conn: &Connection) -> AppResult<bool> {
+    Ok(database::get_setting(conn, SEARCH_HISTORY_PAUSED_KEY)?.as_deref() == Some("1"))
+}
+
+/// Turn a user pattern into a `LIKE` pattern (with `\` as escape). `*` and `?` are
+/// wildcards over the whole keyword; without them the pattern matches as a substring.
+fn pattern_to_like(pattern: &str) -> String {
+    let mut like = String::new();
+    let has_wildcards = pattern.contains(['*', '?']);
+    if !has_wildcards {
+        like.push('%');
+    }
+    for c in pattern.chars() {
+        match c {
+            '%' | '_' | '\\' => {
+                like.push('\\');
+                like.push(c);
+            }
+            '*' => like.push('%'),
+            '?' => like.push('_'),
+            _ => like.push(c),
+        }
+    }
+    if !has_wildcards {
+        like.push('%');
+    }
+    like
+}
+
+// ── Commands ───────────────────────────────────────────
+
+/// Distinct keywords, most recently used first.
+#[tauri::command]
+pub fn get_recent_searches(
+    db: State<'_, AppDatabase>,
+    limit: Option<i64>,
+) -> AppResult<Vec<RecentSearch>> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT keyword, COUNT(*), MAX(searched_at) AS last FROM search_history
+         GROUP BY keyword ORDER BY last DESC LIMIT ?1",
+    )?;
+    let rows = stmt
+        .query_map(params![limit.unwrap_or(50).clamp(1, 1000)], |row| {
+            Ok(RecentSearch {
+                keyword: row.get(0)?,
+                count: row.get(1)?,
+                last_searched_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Forget every search for `keyword`. Returns the number of deleted rows.
+#[tauri::command]
+pub fn delete_search_keyword(db: State<'_, AppDatabase>, keyword: String) -> AppResult<usize> {
+    let conn = db.conn()?;
+    let deleted = conn.execute(
+        "DELETE FROM search_history WHERE keyword = ?1",
+        params![keyword.trim()],
+    )?;
+    Ok(deleted)
+}
+
+/// Forget searches whose keyword matches `pattern` (case-insensitive for ASCII; `*` and `?`
+/// are wildcards, otherwise a substring match). Returns the number of deleted rows.
+#[tauri::command]
+pub fn delete_search_history_matching(
+    db: State<'_, AppDatabase>,
+    pattern: String,
+) -> AppResult<usize> {
+    let pattern = pattern.trim();
+    if pattern.is_empty() || pattern.chars().all(|c| c == '*') {
+        return Err(AppError::ParseError(
+            "Pattern would match everything; clear the history instead".to_string(),
+        ));
+    }
+    let conn = db.conn()?;
+    let deleted = conn.execute(
+        "DELETE FROM search_history WHERE keyword LIKE ?1 ESCAPE '\\'",
+        params![pattern_to_like(pattern)],
+    )?;
+    Ok(deleted)
+}
+
+#[tauri::command]
+pub fn clear_search_history(db: State<'_, AppDatabase>) -> AppResult<usize> {
+    let conn = db.conn()?;
+    let deleted = conn.execute("DELETE FROM search_history", [])?;
+    Ok(deleted)
+}
+
+#[tauri::command]
+pub fn get_search_history_paused(db: State<'_, AppDatabase>) -> AppResult<bool> {
+    let conn = db.conn()?;
+    is_paused(&conn)
+}
+
+/// Stop (or resume) recording searches, e.g. while sharing the screen. Emits
+/// `settings-changed` like `update_settings`.
+#[tauri::command]
+pub fn set_search_history_paused(
+    app: AppHandle,
+    db: State<'_, AppDatabase>,
+    paused: bool,
+) -> AppResult<()> {
+    let patch = SettingsPatch {
+        search_history_paused: Some(paused),
+        ..Default::default()
+    };
+    change_settings(&app, &db, patch).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_patterns_match_substrings() {
+        assert_eq!(pattern_to_like("coat"), "%coat%");
+    }
+
+    #[test]
+    fn wildcards_match_whole_keyword() {
+        assert_eq!(pattern_to_like("kip*"), "kip%");
+        assert_eq!(pattern_to_like("?oat"), "_oat");
+    }
+
+    #[test]
+    fn like_metacharacters_are_escaped() {
+        assert_eq!(pattern_to_like("50%_off"), "%50\\%\\_off%");
+    }
+}
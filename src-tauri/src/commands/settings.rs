@@ -0,0 +1,82 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::database::{self, AppDatabase};
+use crate::error::AppResult;
+use crate::settings::{Settings, SettingsPatch};
+
+use super::content_policy::mark_avatars_stale;
+
+// ── Types ──────────────────────────────────────────────
+
+/// Payload of `settings-changed`; `changed` lists the snake_case field names.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsChanged {
+    pub settings: Settings,
+    pub changed: Vec<String>,
+}
+
+// ── Helpers ────────────────────────────────────────────
+
+/// Validate, store and announce a partial update. Nothing is written if any field is
+/// invalid. The dedicated setters of older settings go through here as well.
+pub(crate) fn change_settings(
+    app: &AppHandle,
+    db: &AppDatabase,
+    patch: SettingsPatch,
+) -> AppResult<Settings> {
+    let mut conn = db.conn_mut()?;
+    let mut settings = Settings::load(&conn)?;
+    let changed = settings.apply(patch)?;
+    if changed.is_empty() {
+        return Ok(settings);
+    }
+
+    let tx = conn.transaction()?;
+    settings.save(&tx)?;
+    if changed.contains(&"content_policy") {
+        mark_avatars_stale(&tx)?;
+    }
+    // Cached translations are in the previous language pair
+    if changed.contains(&"translation_source") || changed.contains(&"translation_target") {
+        tx.execute("DELETE FROM translations", [])?;
+    }
+    tx.commit()?;
+    if changed.contains(&"trash_retention_days") {
+        database::purge_trash(&mut conn, Some(settings.trash_retention_days))?;
+    }
+    drop(conn);
+
+    emit_settings_changed(app, &settings, &changed);
+    Ok(settings)
+}
+
+pub(crate) fn emit_settings_changed(app: &AppHandle, settings: &Settings, changed: &[&str]) {
+    let _ = app.emit(
+        "settings-changed",
+        SettingsChanged {
+            settings: settings.clone(),
+            changed: changed.iter().map(|f| f.to_string()).collect(),
+        },
+    );
+}
+
+// ── Commands ───────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_settings(db: State<'_, AppDatabase>) -> AppResult<Settings> {
+    let conn = db.conn()?;
+    Settings::load(&conn)
+}
+
+/// Validate and store a partial update. Nothing is written if any field is invalid.
+/// Background jobs read settings per request, so changes apply to running jobs as well.
+#[tauri::command]
+pub fn update_settings(
+    app: AppHandle,
+    db: State<'_, AppDatabase>,
+    patch: SettingsPatch,
+) -> AppResult<Settings> {
+    change_settings(&app, &db, patch)
+}
@@ -0,0 +1,436 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::booth::client::BoothClient;
+use crate::booth::shop_parser::{
+    normalize_shop, parse_shop_items, parse_shop_name, shop_url, ShopListing,
+};
+use crate::database::AppDatabase;
+use crate::entities::ensure_shop;
+use crate::error::{AppError, AppResult};
+use crate::settings::{request_interval, ContentPolicy};
+
+use super::content_policy::{content_policy, police_favorites, police_shop_items};
+use super::db::{FavoriteItem, FAVORITE_COLUMNS};
+
+/// Listing pages fetched per shop and check; later pages are only read while every item
+/// on the previous page was new.
+const MAX_SHOP_PAGES: u32 = 5;
+
+/// Favorites belong to a shop when they are linked to it or appear on its listing.
+const SHOP_FAVORITE_MATCH: &str =
+    "(f.shop_id = s.id OR f.item_id IN (SELECT item_id FROM shop_items WHERE shop_id = s.id))";
+
+// ── Types ──────────────────────────────────────────────
+
+/// Tracks the background shop check so it cannot be started twice.
+#[derive(Default)]
+pub struct ShopCheckJob {
+    running: AtomicBool,
+}
+
+impl ShopCheckJob {
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Shop {
+    pub id: i64,
+    /// Unknown for shops only seen by name on favorites
+    pub subdomain: Option<String>,
+    pub name: Option<String>,
+    pub url: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub followed: bool,
+    pub item_count: i64,
+    /// Items first seen since the shop was last visited
+    pub new_item_count: i64,
+    pub favorite_count: i64,
+    pub last_fetched_at: Option<String>,
+    pub last_visited_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShopItem {
+    pub shop_id: i64,
+    pub item_id: i64,
+    pub name: Option<String>,
+    pub price: Option<i64>,
+    pub thumbnail_url: Option<String>,
+    pub first_seen_at: String,
+    pub last_seen_at: String,
+    pub is_new: bool,
+    pub is_favorite: bool,
+    /// Known from the cached copy or favorite, or from markers in the name
+    pub is_adult: bool,
+    /// Set by the content policy on R-18 items the UI should blur
+    pub blurred: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShopCheckProgress {
+    pub done: usize,
+    pub total: usize,
+    pub shop_id: i64,
+    pub subdomain: String,
+    pub new_items: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShopCheckSummary {
+    pub total: usize,
+    pub checked: usize,
+    pub new_items: usize,
+    pub failed: usize,
+}
+
+// ── Helpers ────────────────────────────────────────────
+
+const SHOP_ITEM_COLUMNS: &str = "si.shop_id, si.item_id, si.name, si.price, si.thumbnail_url,
+     si.first_seen_at, si.last_seen_at,
+     si.first_seen_at > COALESCE(s.last_visited_at, '9999-12-31'),
+     EXISTS (SELECT 1 FROM favorites f WHERE f.item_id = si.item_id AND f.deleted_at IS NULL),
+     EXISTS (SELECT 1 FROM cached_items c WHERE c.id = si.item_id AND c.is_adult = 1)
+         OR EXISTS (SELECT 1 FROM favorites f WHERE f.item_id = si.item_id AND f.is_adult = 1)";
+
+fn shop_item_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ShopItem> {
+    Ok(ShopItem {
+        shop_id: row.get(0)?,
+        item_id: row.get(1)?,
+        name: row.get(2)?,
+        price: row.get(3)?,
+        thumbnail_url: row.get(4)?,
+        first_seen_at: row.get(5)?,
+        last_seen_at: row.get(6)?,
+        is_new: row.get(7)?,
+        is_favorite: row.get(8)?,
+        is_adult: row.get(9)?,
+        blurred: false,
+    })
+}
+
+/// Record the items on one listing page. Returns how many were not known before.
+fn record_listing(conn: &mut Connection, shop_id: i64, items: &[ShopListing]) -> AppResult<usize> {
+    let tx = conn.transaction()?;
+    let mut new_items = 0;
+    for item in items {
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO shop_items (shop_id, item_id, name, price, thumbnail_url)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                shop_id,
+                item.item_id,
+                item.name,
+                item.price,
+                item.thumbnail_url
+            ],
+        )?;
+        if inserted > 0 {
+            new_items += 1;
+        } else {
+            tx.execute(
+                "UPDATE shop_items SET
+                    name = COALESCE(?3, name),
+                    price = COALESCE(?4, price),
+                    thumbnail_url = COALESCE(?5, thumbnail_url),
+                    last_seen_at = datetime('now')
+                 WHERE shop_id = ?1 AND item_id = ?2",
+                params![
+                    shop_id,
+                    item.item_id,
+                    item.name,
+                    item.price,
+                    item.thumbnail_url
+                ],
+            )?;
+        }
+    }
+    tx.commit()?;
+    Ok(new_items)
+}
+
+/// Fetch a shop's listing and record it. On the first check everything found becomes the
+/// baseline, so only later releases count as new.
+async fn check_shop(
+    client: &BoothClient,
+    db: &AppDatabase,
+    shop_id: i64,
+    subdomain: &str,
+) -> AppResult<usize> {
+    let first_check: bool = db.conn()?.query_row(
+        "SELECT last_fetched_at IS NULL FROM shops WHERE id = ?1",
+        params![shop_id],
+        |row| row.get(0),
+    )?;
+
+    let mut new_items = 0;
+    for page in 1..=MAX_SHOP_PAGES {
+        if page > 1 {
+            tokio::time::sleep(request_interval(db)).await;
+        }
+        let html = client.fetch_shop_page(subdomain, page).await?;
+        let items = parse_shop_items(&html);
+        let mut conn = db.conn_mut()?;
+        if page == 1 {
+            // Names the shop and moves items whose URL shows it off name-only rows
+            if let Some(name) = parse_shop_name(&html) {
+                ensure_shop(&conn, Some(&name), Some(subdomain))?;
+            }
+        }
+        let page_new = record_listing(&mut conn, shop_id, &items)?;
+        new_items += page_new;
+        if items.is_empty() || page_new < items.len() {
+            break;
+        }
+    }
+
+    let conn = db.conn()?;
+    conn.execute(
+        "UPDATE shops SET last_fetched_at = datetime('now'),
+             last_visited_at = CASE WHEN ?2 THEN datetime('now') ELSE last_visited_at END
+         WHERE id = ?1",
+        params![shop_id, first_check],
+    )?;
+    Ok(if first_check { 0 } else { new_items })
+}
+
+async fn run_shop_check(app: &AppHandle, shops: Vec<(i64, String)>) -> ShopCheckSummary {
+    let db = app.state::<AppDatabase>();
+    let mut summary = ShopCheckSummary {
+        total: shops.len(),
+        ..Default::default()
+    };
+    let client = match BoothClient::new() {
+        Ok(c) => c,
+        Err(e) => {
+            log::error!("Shop check aborted: {}", e);
+            summary.failed = shops.len();
+            return summary;
+        }
+    };
+
+    for (idx, (shop_id, subdomain)) in shops.into_iter().enumerate() {
+        if idx > 0 {
+            tokio::time::sleep(request_interval(&db)).await;
+        }
+        let (new_items, error) = match check_shop(&client, &db, shop_id, &subdomain).await {
+            Ok(n) => {
+                summary.checked += 1;
+                summary.new_items += n;
+                (n, None)
+            }
+            Err(e) => {
+                log::warn!("Failed to check shop {}: {}", subdomain, e);
+                summary.failed += 1;
+                (0, Some(e.to_string()))
+            }
+        };
+        let _ = app.emit(
+            "shops-check-progress",
+            ShopCheckProgress {
+                done: idx + 1,
+                total: summary.total,
+                shop_id,
+                subdomain,
+                new_items,
+                error,
+            },
+        );
+    }
+    summary
+}
+
+// ── Commands ───────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_shops(db: State<'_, AppDatabase>, followed_only: Option<bool>) -> AppResult<Vec<Shop>> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT s.id, s.subdomain, s.name, s.followed, s.last_fetched_at, s.last_visited_at,
+                s.thumbnail_url,
+                (SELECT COUNT(*) FROM shop_items si WHERE si.shop_id = s.id),
+                (SELECT COUNT(*) FROM shop_items si WHERE si.shop_id = s.id
+                   AND si.first_seen_at > COALESCE(s.last_visited_at, '9999-12-31')),
+                (SELECT COUNT(*) FROM favorites f WHERE f.deleted_at IS NULL AND {})
+         FROM shops s
+         WHERE ?1 = 0 OR s.followed = 1
+         ORDER BY s.followed DESC, COALESCE(s.name, s.subdomain) COLLATE NOCASE ASC",
+        SHOP_FAVORITE_MATCH
+    ))?;
+    let rows = stmt
+        .query_map(params![followed_only.unwrap_or(false)], |row| {
+            let subdomain: Option<String> = row.get(1)?;
+            Ok(Shop {
+                id: row.get(0)?,
+                url: subdomain.as_deref().map(shop_url),
+                subdomain,
+                name: row.get(2)?,
+                followed: row.get(3)?,
+                last_fetched_at: row.get(4)?,
+                last_visited_at: row.get(5)?,
+                thumbnail_url: row.get(6)?,
+                item_count: row.get(7)?,
+                new_item_count: row.get(8)?,
+                favorite_count: row.get(9)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Follow a shop given its subdomain or any URL on it. Returns the shop id.
+#[tauri::command]
+pub fn follow_shop(db: State<'_, AppDatabase>, shop: String) -> AppResult<i64> {
+    let subdomain = normalize_shop(&shop)?;
+    let conn = db.conn()?;
+    let id = ensure_shop(&conn, None, Some(&subdomain))?
+        .ok_or_else(|| AppError::ParseError(format!("Not a Booth shop: '{}'", shop)))?;
+    conn.execute("UPDATE shops SET followed = 1 WHERE id = ?1", params![id])?;
+    Ok(id)
+}
+
+/// Stop following a shop; what was seen on its listing is kept.
+#[tauri::command]
+pub fn unfollow_shop(db: State<'_, AppDatabase>, id: i64) -> AppResult<()> {
+    let conn = db.conn()?;
+    conn.execute("UPDATE shops SET followed = 0 WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+/// Start fetching the listings of followed shops (all of them, or only `shop_ids`) in the
+/// background. Progress is reported through `shops-check-progress` events and the result
+/// through `shops-check-finished`. Returns the number of shops queued.
+#[tauri::command]
+pub fn check_followed_shops(
+    app: AppHandle,
+    db: State<'_, AppDatabase>,
+    job: State<'_, ShopCheckJob>,
+    shop_ids: Option<Vec<i64>>,
+) -> AppResult<usize> {
+    let queued: Vec<(i64, String)> = {
+        let conn = db.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, subdomain FROM shops WHERE followed = 1 AND subdomain IS NOT NULL
+             ORDER BY last_fetched_at ASC NULLS FIRST",
+        )?;
+        let all = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(i64, String)>, _>>()?;
+        match shop_ids {
+            Some(ids) => all.into_iter().filter(|(id, _)| ids.contains(id)).collect(),
+            None => all,
+        }
+    };
+
+    if job.running.swap(true, Ordering::SeqCst) {
+        return Err(AppError::Busy("Shop check".to_string()));
+    }
+    let total = queued.len();
+    tauri::async_runtime::spawn(async move {
+        let summary = run_shop_check(&app, queued).await;
+        app.state::<ShopCheckJob>()
+            .running
+            .store(false, Ordering::SeqCst);
+        let _ = app.emit("shops-check-finished", summary);
+    });
+    Ok(total)
+}
+
+/// Everything seen on a shop's listing, newest first.
+#[tauri::command]
+pub fn get_shop_items(db: State<'_, AppDatabase>, shop_id: i64) -> AppResult<Vec<ShopItem>> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM shop_items si JOIN shops s ON s.id = si.shop_id
+         WHERE si.shop_id = ?1
+         ORDER BY si.first_seen_at DESC, si.item_id DESC",
+        SHOP_ITEM_COLUMNS
+    ))?;
+    let rows = stmt
+        .query_map(params![shop_id], shop_item_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(police_shop_items(content_policy(&conn)?, rows))
+}
+
+/// Items released since the last visit, for one shop or all followed shops.
+#[tauri::command]
+pub fn get_new_shop_items(
+    db: State<'_, AppDatabase>,
+    shop_id: Option<i64>,
+) -> AppResult<Vec<ShopItem>> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM shop_items si JOIN shops s ON s.id = si.shop_id
+         WHERE si.first_seen_at > COALESCE(s.last_visited_at, '9999-12-31')
+           AND (?1 IS NULL AND s.followed = 1 OR si.shop_id = ?1)
+         ORDER BY si.first_seen_at DESC, si.item_id DESC",
+        SHOP_ITEM_COLUMNS
+    ))?;
+    let rows = stmt
+        .query_map(params![shop_id], shop_item_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(police_shop_items(content_policy(&conn)?, rows))
+}
+
+/// Mark a shop's current items as seen.
+#[tauri::command]
+pub fn mark_shop_visited(db: State<'_, AppDatabase>, shop_id: i64) -> AppResult<()> {
+    let conn = db.conn()?;
+    let updated = conn.execute(
+        "UPDATE shops SET last_visited_at = datetime('now') WHERE id = ?1",
+        params![shop_id],
+    )?;
+    if updated == 0 {
+        return Err(AppError::NotFound(format!("Shop {}", shop_id)));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_shop_favorites(
+    db: State<'_, AppDatabase>,
+    shop_id: i64,
+) -> AppResult<Vec<FavoriteItem>> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM favorites f, shops s
+         WHERE s.id = ?1 AND f.deleted_at IS NULL AND {}
+         ORDER BY f.added_at DESC",
+        FAVORITE_COLUMNS, SHOP_FAVORITE_MATCH
+    ))?;
+    let rows = stmt
+        .query_map(params![shop_id], FavoriteItem::from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(police_favorites(content_policy(&conn)?, rows))
+}
+
+/// Batch: favorite item ids for every known shop, without those the content policy hides.
+#[tauri::command]
+pub fn get_shop_favorites_batch(db: State<'_, AppDatabase>) -> AppResult<HashMap<i64, Vec<i64>>> {
+    let conn = db.conn()?;
+    let hide_adult = content_policy(&conn)? == ContentPolicy::Hide;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT s.id, f.item_id FROM shops s, favorites f
+         WHERE f.deleted_at IS NULL AND (?1 = 0 OR f.is_adult = 0) AND {}
+         ORDER BY s.id, f.added_at DESC",
+        SHOP_FAVORITE_MATCH
+    ))?;
+    let mut map: HashMap<i64, Vec<i64>> = HashMap::new();
+    let rows = stmt.query_map(params![hide_adult], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
+    })?;
+    for row in rows {
+        let (shop_id, item_id) = row?;
+        map.entry(shop_id).or_default().push(item_id);
+    }
+    Ok(map)
+}
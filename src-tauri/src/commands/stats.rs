@@ -1,8 +1,66 @@
-use serde::Serialize;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
 use tauri::State;
 
+use crate::booth::models::ItemAvailability;
+use crate::booth::shop_parser::shop_url;
 use crate::database::AppDatabase;
-use crate::error::AppResult;
+use crate::dates::validate_date;
+use crate::error::{AppError, AppResult};
+
+/// Upper bounds (JPY) of the default price buckets.
+pub const DEFAULT_PRICE_BOUNDS: &[i64] = &[0, 500, 1000, 3000, 5000, 10000];
+const MAX_PRICE_BUCKETS: usize = 20;
+const MAX_TOP_N: usize = 100;
+
+/// Language of the human-readable labels in the statistics output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsLocale {
+    Ja,
+    #[default]
+    Ko,
+    En,
+}
+
+impl StatsLocale {
+    fn free(self) -> &'static str {
+        match self {
+            StatsLocale::Ja => "無料",
+            StatsLocale::Ko => "무료",
+            StatsLocale::En => "Free",
+        }
+    }
+
+    fn uncategorized(self) -> &'static str {
+        match self {
+            StatsLocale::Ja => "未分類",
+            StatsLocale::Ko => "미분류",
+            StatsLocale::En => "Uncategorized",
+        }
+    }
+}
+
+/// Favorites matching the statistics filters, as `fav`. Binds `?1` from, `?2` to,
+/// `?3` collection id and `?4` tag (each ignored when NULL).
+const FAV_CTE: &str = "WITH fav AS (
+    SELECT * FROM favorites
+    WHERE deleted_at IS NULL
+      AND (?1 IS NULL OR date(added_at) >= ?1)
+      AND (?2 IS NULL OR date(added_at) <= ?2)
+      AND (?3 IS NULL OR item_id IN (SELECT item_id FROM collection_items WHERE collection_id = ?3))
+      AND (?4 IS NULL OR item_id IN (SELECT item_id FROM item_tags WHERE tag = ?4))
+)";
+
+/// Purchases matching the statistics filters, as `owned`; same parameters as [`FAV_CTE`],
+/// with the date range applied to the purchase date.
+const OWNED_CTE: &str = "WITH owned AS (
+    SELECT * FROM owned_items
+    WHERE (?1 IS NULL OR date(COALESCE(purchased_at, created_at)) >= ?1)
+      AND (?2 IS NULL OR date(COALESCE(purchased_at, created_at)) <= ?2)
+      AND (?3 IS NULL OR item_id IN (SELECT item_id FROM collection_items WHERE collection_id = ?3))
+      AND (?4 IS NULL OR item_id IN (SELECT item_id FROM item_tags WHERE tag = ?4))
+)";
 
 // ── Types ──────────────────────────────────────────────
 
@@ -12,22 +70,50 @@ pub struct DashboardStats {
     pub collections_count: i64,
     pub tags_count: i64,
     pub searches_count: i64,
+    /// Sum of favorites' cheapest prices
     pub total_value: i64,
+    /// Sum of favorites' most expensive variations
+    pub total_value_max: i64,
     pub avg_price: i64,
+    /// Favorites whose last check found them deleted, private or age-gated
+    pub unavailable_count: i64,
+    /// Favorites never checked or not checked in the last 30 days
+    pub stale_check_count: i64,
+    /// Distinct items in the owned library (purchases of several variations count once)
+    pub owned_count: i64,
+    /// Sum of `price_paid` over all purchases
+    pub total_spent: i64,
+    /// `total_value` minus favorites that are already owned
+    pub wishlist_value: i64,
 }
 
 #[derive(Debug, Serialize)]
 pub struct CategoryStat {
+    pub category_id: Option<i64>,
+    /// Category name, or the localized "uncategorized" label
     pub category: String,
+    /// Set for favorites without a category, so the UI need not match on the label
+    pub uncategorized: bool,
     pub count: i64,
 }
 
 #[derive(Debug, Serialize)]
 pub struct PriceBucket {
+    /// Locale-independent id: `free` or the JPY range (`~500`, `501~1000`, `10000~`)
+    pub key: String,
+    /// Inclusive JPY bounds; `max` is `None` for the open-ended top bucket
+    pub min: i64,
+    pub max: Option<i64>,
     pub label: String,
     pub count: i64,
 }
 
+#[derive(Debug, Serialize)]
+pub struct AvailabilityStat {
+    pub availability: ItemAvailability,
+    pub count: i64,
+}
+
 #[derive(Debug, Serialize)]
 pub struct TagStat {
     pub tag: String,
@@ -46,50 +132,281 @@ pub struct MonthlyCount {
     pub count: i64,
 }
 
+#[derive(Debug, Serialize)]
+pub struct MonthlySpend {
+    pub month: String,
+    pub count: i64,
+    pub amount: i64,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ShopStat {
+    pub shop_id: i64,
     pub shop: String,
+    /// Shop page, once the shop's subdomain is known
+    pub url: Option<String>,
     pub count: i64,
 }
 
+/// Totals for one date range, used to compare a period with the one before it.
+#[derive(Debug, Serialize)]
+pub struct PeriodSummary {
+    pub from: String,
+    pub to: String,
+    pub favorites_added: i64,
+    pub value_added: i64,
+    pub purchases: i64,
+    pub spent: i64,
+    pub searches: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PeriodComparison {
+    pub current: PeriodSummary,
+    /// The equally long period ending the day before `current.from`
+    pub previous: PeriodSummary,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StatisticsParams {
+    /// Inclusive `YYYY-MM-DD` bounds on when favorites were added, purchases made and
+    /// searches run
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub collection_id: Option<i64>,
+    pub tag: Option<String>,
+    /// Ascending upper bounds (JPY) of the price buckets; a leading 0 is the "free" bucket
+    pub price_bounds: Option<Vec<i64>>,
+    pub top_categories: Option<usize>,
+    pub top_tags: Option<usize>,
+    pub top_searches: Option<usize>,
+    pub top_shops: Option<usize>,
+    /// Summarize `from`..`to` against the period right before it (needs `from`)
+    #[serde(default)]
+    pub compare_previous: bool,
+    /// Language of `label`/`category` texts (keys and bounds are always locale-independent)
+    #[serde(default)]
+    pub locale: StatsLocale,
+}
+
 /// All statistics bundled into a single response to avoid 7 separate IPC round-trips.
 #[derive(Debug, Serialize)]
 pub struct AllStatistics {
     pub stats: DashboardStats,
     pub categories: Vec<CategoryStat>,
     pub prices: Vec<PriceBucket>,
+    pub availability: Vec<AvailabilityStat>,
     pub tags: Vec<TagStat>,
     pub searches: Vec<SearchFrequency>,
     pub monthly: Vec<MonthlyCount>,
+    pub monthly_spend: Vec<MonthlySpend>,
     pub shops: Vec<ShopStat>,
+    pub comparison: Option<PeriodComparison>,
+}
+
+// ── Helpers ────────────────────────────────────────────
+
+fn validate_bounds(bounds: Option<Vec<i64>>) -> AppResult<Vec<i64>> {
+    let bounds = bounds.unwrap_or_else(|| DEFAULT_PRICE_BOUNDS.to_vec());
+    if bounds.is_empty() || bounds.len() > MAX_PRICE_BUCKETS {
+        return Err(AppError::ParseError(format!(
+            "Price buckets need 1 to {} bounds",
+            MAX_PRICE_BUCKETS
+        )));
+    }
+    if bounds[0] < 0 || bounds.windows(2).any(|w| w[0] >= w[1]) {
+        return Err(AppError::ParseError(
+            "Price bounds must be non-negative and strictly ascending".to_string(),
+        ));
+    }
+    Ok(bounds)
+}
+
+/// Days are `YYYY-MM-DD`, so they compare as strings. A range without `to` runs to
+/// `today`, which `from` may then not be past.
+fn validate_range(from: Option<&str>, to: Option<&str>, today: &str) -> AppResult<()> {
+    let Some(from) = from else {
+        return Ok(());
+    };
+    let end = to.unwrap_or(today);
+    if from > end {
+        return Err(AppError::ParseError(format!(
+            "Date range starts on {} after it ends on {}",
+            from, end
+        )));
+    }
+    Ok(())
+}
+
+fn top_n(value: Option<usize>, default: usize) -> usize {
+    value.unwrap_or(default).clamp(1, MAX_TOP_N)
+}
+
+fn bucket_key(bounds: &[i64], index: usize) -> String {
+    let lower = index.checked_sub(1).map(|i| bounds[i]);
+    match (lower, bounds.get(index)) {
+        (None, Some(0)) => "free".to_string(),
+        (None, Some(upper)) | (Some(0), Some(upper)) => format!("~{}", upper),
+        (Some(lower), Some(upper)) => format!("{}~{}", lower + 1, upper),
+        (Some(lower), None) => format!("{}~", lower),
+        (None, None) => String::new(),
+    }
+}
+
+/// Count prices into buckets `(prev, bound]` for each bound plus one open bucket above
+/// the last bound.
+fn bucket_prices(prices: &[i64], bounds: &[i64], locale: StatsLocale) -> Vec<PriceBucket> {
+    let mut counts = vec![0i64; bounds.len() + 1];
+    for price in prices {
+        let index = bounds.partition_point(|bound| bound < price);
+        counts[index] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| {
+            let key = bucket_key(bounds, i);
+            PriceBucket {
+                label: if key == "free" {
+                    locale.free().to_string()
+                } else {
+                    key.clone()
+                },
+                key,
+                min: i.checked_sub(1).map(|prev| bounds[prev] + 1).unwrap_or(0),
+                max: bounds.get(i).copied(),
+                count,
+            }
+        })
+        .collect()
+}
+
+fn period_summary(
+    conn: &Connection,
+    from: &str,
+    to: &str,
+    collection_id: Option<i64>,
+    tag: Option<&str>,
+) -> AppResult<PeriodSummary> {
+    let filter = params![from, to, collection_id, tag];
+    let (favorites_added, value_added): (i64, i64) = conn.query_row(
+        &format!("{} SELECT COUNT(*), COALESCE(SUM(price), 0) FROM fav", FAV_CTE),
+        filter,
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let (purchases, spent): (i64, i64) = conn.query_row(
+        &format!("{} SELECT COUNT(*), COALESCE(SUM(price_paid), 0) FROM owned", OWNED_CTE),
+        filter,
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let searches: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM search_history WHERE date(searched_at) BETWEEN ?1 AND ?2",
+        params![from, to],
+        |row| row.get(0),
+    )?;
+    Ok(PeriodSummary {
+        from: from.to_string(),
+        to: to.to_string(),
+        favorites_added,
+        value_added,
+        purchases,
+        spent,
+        searches,
+    })
 }
 
 // ── Commands ───────────────────────────────────────────
 
+/// Aggregate statistics over the library. Without `params` the whole library is used with
+/// the default bucket bounds and list sizes.
 #[tauri::command]
-pub fn get_all_statistics(db: State<'_, AppDatabase>) -> AppResult<AllStatistics> {
+pub fn get_all_statistics(
+    db: State<'_, AppDatabase>,
+    params: Option<StatisticsParams>,
+) -> AppResult<AllStatistics> {
+    let p = params.unwrap_or_default();
+    let day = |d: &str| validate_date(d).map(|d| d.chars().take(10).collect::<String>());
+    let from = p.from.as_deref().map(day).transpose()?;
+    let to = p.to.as_deref().map(day).transpose()?;
+    let tag = p.tag.as_deref().map(str::trim).filter(|t| !t.is_empty());
+    let bounds = validate_bounds(p.price_bounds)?;
+    let filtered = from.is_some() || to.is_some() || p.collection_id.is_some() || tag.is_some();
+    let filter = params![from, to, p.collection_id, tag];
+    let dates = params![from, to];
+
     let conn = db.conn()?;
+    let today: String = conn.query_row("SELECT date('now')", [], |row| row.get(0))?;
+    validate_range(from.as_deref(), to.as_deref(), &today)?;
 
     // Dashboard stats
-    let favorites_count: i64 =
-        conn.query_row("SELECT COUNT(*) FROM favorites", [], |row| row.get(0))?;
-    let collections_count: i64 =
-        conn.query_row("SELECT COUNT(*) FROM collections", [], |row| row.get(0))?;
-    let tags_count: i64 = conn.query_row(
-        "SELECT COUNT(DISTINCT tag) FROM item_tags",
-        [],
+    let favorites_count: i64 = conn.query_row(
+        &format!("{} SELECT COUNT(*) FROM fav", FAV_CTE),
+        filter,
         |row| row.get(0),
     )?;
-    let searches_count: i64 =
-        conn.query_row("SELECT COUNT(*) FROM search_history", [], |row| row.get(0))?;
-    let total_value: i64 = conn.query_row(
-        "SELECT COALESCE(SUM(price), 0) FROM favorites",
+    let collections_count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM collections WHERE deleted_at IS NULL",
         [],
         |row| row.get(0),
     )?;
-    let avg_price: i64 = conn.query_row(
-        "SELECT CAST(COALESCE(AVG(price), 0) AS INTEGER) FROM favorites",
-        [],
+    // Unfiltered, tags on any non-trashed item count; filtered, only tags on matching favorites
+    let tags_count: i64 = conn.query_row(
+        &format!(
+            "{} SELECT COUNT(DISTINCT tag) FROM item_tags
+             WHERE item_id NOT IN (SELECT item_id FROM favorites WHERE deleted_at IS NOT NULL)
+               AND (?5 = 0 OR item_id IN (SELECT item_id FROM fav))",
+            FAV_CTE
+        ),
+        params![from, to, p.collection_id, tag, filtered],
+        |row| row.get(0),
+    )?;
+    let searches_count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM search_history
+         WHERE (?1 IS NULL OR date(searched_at) >= ?1) AND (?2 IS NULL OR date(searched_at) <= ?2)",
+        dates,
+        |row| row.get(0),
+    )?;
+    let (total_value, total_value_max, avg_price): (i64, i64, i64) = conn.query_row(
+        &format!(
+            "{} SELECT COALESCE(SUM(price), 0), COALESCE(SUM(COALESCE(price_max, price)), 0),
+                       CAST(COALESCE(AVG(price), 0) AS INTEGER)
+             FROM fav",
+            FAV_CTE
+        ),
+        filter,
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+
+    let unavailable_count: i64 = conn.query_row(
+        &format!("{} SELECT COUNT(*) FROM fav WHERE availability != 'available'", FAV_CTE),
+        filter,
+        |row| row.get(0),
+    )?;
+    let stale_check_count: i64 = conn.query_row(
+        &format!(
+            "{} SELECT COUNT(*) FROM fav
+             WHERE last_checked_at IS NULL OR last_checked_at < datetime('now', '-30 days')",
+            FAV_CTE
+        ),
+        filter,
+        |row| row.get(0),
+    )?;
+
+    let (owned_count, total_spent): (i64, i64) = conn.query_row(
+        &format!(
+            "{} SELECT COUNT(DISTINCT item_id), COALESCE(SUM(price_paid), 0) FROM owned",
+            OWNED_CTE
+        ),
+        filter,
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let wishlist_value: i64 = conn.query_row(
+        &format!(
+            "{} SELECT COALESCE(SUM(price), 0) FROM fav
+             WHERE item_id NOT IN (SELECT item_id FROM owned_items)",
+            FAV_CTE
+        ),
+        filter,
         |row| row.get(0),
     )?;
 
@@ -99,90 +416,273 @@ pub fn get_all_statistics(db: State<'_, AppDatabase>) -> AppResult<AllStatistics
         tags_count,
         searches_count,
         total_value,
+        total_value_max,
         avg_price,
+        unavailable_count,
+        stale_check_count,
+        owned_count,
+        total_spent,
+        wishlist_value,
     };
 
     // Category distribution
     let categories = {
-        let mut stmt = conn.prepare(
-            "SELECT COALESCE(category_name, '미분류') AS cat, COUNT(*) AS cnt
-             FROM favorites GROUP BY cat ORDER BY cnt DESC LIMIT 10",
-        )?;
-        let rows = stmt.query_map([], |row| {
-            Ok(CategoryStat { category: row.get(0)?, count: row.get(1)? })
-        })?.collect::<Result<Vec<_>, _>>()?;
+        let mut stmt = conn.prepare(&format!(
+            "{} SELECT fav.category_id, c.name, COUNT(*) AS cnt
+             FROM fav LEFT JOIN categories c ON c.id = fav.category_id
+             GROUP BY fav.category_id ORDER BY cnt DESC LIMIT ?5",
+            FAV_CTE
+        ))?;
+        let limit = top_n(p.top_categories, 10) as i64;
+        let rows = stmt
+            .query_map(params![from, to, p.collection_id, tag, limit], |row| {
+                let category: Option<String> = row.get(1)?;
+                Ok(CategoryStat {
+                    category_id: row.get(0)?,
+                    uncategorized: category.is_none(),
+                    category: category
+                        .unwrap_or_else(|| p.locale.uncategorized().to_string()),
+                    count: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
         rows
     };
 
-    // Price distribution
+    // Price distribution, by each favorite's cheapest variation
     let prices = {
-        let mut stmt = conn.prepare(
-            "SELECT
-                SUM(CASE WHEN price = 0 THEN 1 ELSE 0 END),
-                SUM(CASE WHEN price > 0 AND price <= 500 THEN 1 ELSE 0 END),
-                SUM(CASE WHEN price > 500 AND price <= 1000 THEN 1 ELSE 0 END),
-                SUM(CASE WHEN price > 1000 AND price <= 3000 THEN 1 ELSE 0 END),
-                SUM(CASE WHEN price > 3000 AND price <= 5000 THEN 1 ELSE 0 END),
-                SUM(CASE WHEN price > 5000 AND price <= 10000 THEN 1 ELSE 0 END),
-                SUM(CASE WHEN price > 10000 THEN 1 ELSE 0 END)
-             FROM favorites",
-        )?;
-        let labels = ["무료", "~500", "501~1000", "1001~3000", "3001~5000", "5001~10000", "10000~"];
-        let counts = stmt.query_row([], |row| {
-            let mut v = Vec::new();
-            for i in 0..7 { v.push(row.get::<_, i64>(i).unwrap_or(0)); }
-            Ok(v)
-        })?;
-        let result: Vec<PriceBucket> = labels.iter().zip(counts.iter()).map(|(l, c)| PriceBucket { label: l.to_string(), count: *c }).collect();
-        result
+        let mut stmt = conn.prepare(&format!("{} SELECT price FROM fav", FAV_CTE))?;
+        let prices = stmt
+            .query_map(filter, |row| row.get::<_, i64>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        bucket_prices(&prices, &bounds, p.locale)
+    };
+
+    // Availability distribution
+    let availability = {
+        let mut stmt = conn.prepare(&format!(
+            "{} SELECT availability, COUNT(*) AS cnt
+             FROM fav GROUP BY availability ORDER BY cnt DESC",
+            FAV_CTE
+        ))?;
+        let rows = stmt
+            .query_map(filter, |row| {
+                Ok(AvailabilityStat {
+                    availability: ItemAvailability::from_db(&row.get::<_, String>(0)?),
+                    count: row.get(1)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
     };
 
     // Top tags
     let tags = {
-        let mut stmt = conn.prepare(
-            "SELECT tag, COUNT(*) AS cnt FROM item_tags GROUP BY tag ORDER BY cnt DESC LIMIT 15",
-        )?;
-        let rows = stmt.query_map([], |row| {
-            Ok(TagStat { tag: row.get(0)?, count: row.get(1)? })
-        })?.collect::<Result<Vec<_>, _>>()?;
+        let mut stmt = conn.prepare(&format!(
+            "{} SELECT tag, COUNT(*) AS cnt FROM item_tags
+             WHERE item_id NOT IN (SELECT item_id FROM favorites WHERE deleted_at IS NOT NULL)
+               AND (?5 = 0 OR item_id IN (SELECT item_id FROM fav))
+             GROUP BY tag ORDER BY cnt DESC LIMIT ?6",
+            FAV_CTE
+        ))?;
+        let limit = top_n(p.top_tags, 15) as i64;
+        let rows = stmt
+            .query_map(
+                params![from, to, p.collection_id, tag, filtered, limit],
+                |row| {
+                    Ok(TagStat {
+                        tag: row.get(0)?,
+                        count: row.get(1)?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
         rows
     };
 
     // Search history stats
     let searches = {
         let mut stmt = conn.prepare(
-            "SELECT keyword, COUNT(*) AS cnt FROM search_history GROUP BY keyword ORDER BY cnt DESC LIMIT 10",
+            "SELECT keyword, COUNT(*) AS cnt FROM search_history
+             WHERE (?1 IS NULL OR date(searched_at) >= ?1) AND (?2 IS NULL OR date(searched_at) <= ?2)
+             GROUP BY keyword ORDER BY cnt DESC LIMIT ?3",
         )?;
-        let rows = stmt.query_map([], |row| {
-            Ok(SearchFrequency { keyword: row.get(0)?, count: row.get(1)? })
-        })?.collect::<Result<Vec<_>, _>>()?;
+        let limit = top_n(p.top_searches, 10) as i64;
+        let rows = stmt
+            .query_map(params![from, to, limit], |row| {
+                Ok(SearchFrequency {
+                    keyword: row.get(0)?,
+                    count: row.get(1)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
         rows
     };
 
     // Monthly favorites
     let monthly = {
-        let mut stmt = conn.prepare(
-            "SELECT strftime('%Y-%m', added_at) AS month, COUNT(*) AS cnt
-             FROM favorites WHERE added_at IS NOT NULL GROUP BY month ORDER BY month ASC",
-        )?;
-        let rows = stmt.query_map([], |row| {
-            Ok(MonthlyCount { month: row.get(0)?, count: row.get(1)? })
-        })?.collect::<Result<Vec<_>, _>>()?;
+        let mut stmt = conn.prepare(&format!(
+            "{} SELECT strftime('%Y-%m', added_at) AS month, COUNT(*) AS cnt
+             FROM fav WHERE added_at IS NOT NULL
+             GROUP BY month ORDER BY month ASC",
+            FAV_CTE
+        ))?;
+        let rows = stmt
+            .query_map(filter, |row| {
+                Ok(MonthlyCount {
+                    month: row.get(0)?,
+                    count: row.get(1)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+
+    // Monthly spend (purchases without a date fall back to when they were recorded)
+    let monthly_spend = {
+        let mut stmt = conn.prepare(&format!(
+            "{} SELECT strftime('%Y-%m', COALESCE(purchased_at, created_at)) AS month,
+                    COUNT(*) AS cnt, COALESCE(SUM(price_paid), 0) AS amount
+             FROM owned GROUP BY month ORDER BY month ASC",
+            OWNED_CTE
+        ))?;
+        let rows = stmt
+            .query_map(filter, |row| {
+                Ok(MonthlySpend {
+                    month: row.get(0)?,
+                    count: row.get(1)?,
+                    amount: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
         rows
     };
 
     // Top shops
     let shops = {
-        let mut stmt = conn.prepare(
-            "SELECT shop_name AS shop, COUNT(*) AS cnt
-             FROM favorites WHERE shop_name IS NOT NULL AND shop_name != ''
-             GROUP BY shop ORDER BY cnt DESC LIMIT 10",
-        )?;
-        let rows = stmt.query_map([], |row| {
-            Ok(ShopStat { shop: row.get(0)?, count: row.get(1)? })
-        })?.collect::<Result<Vec<_>, _>>()?;
+        let mut stmt = conn.prepare(&format!(
+            "{} SELECT s.id, COALESCE(s.name, MAX(fav.shop_name), s.subdomain), s.subdomain,
+                    COUNT(*) AS cnt
+             FROM fav INNER JOIN shops s ON s.id = fav.shop_id
+             GROUP BY s.id ORDER BY cnt DESC LIMIT ?5",
+            FAV_CTE
+        ))?;
+        let limit = top_n(p.top_shops, 10) as i64;
+        let rows = stmt
+            .query_map(params![from, to, p.collection_id, tag, limit], |row| {
+                Ok(ShopStat {
+                    shop_id: row.get(0)?,
+                    shop: row.get(1)?,
+                    url: row.get::<_, Option<String>>(2)?.map(|s| shop_url(&s)),
+                    count: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
         rows
     };
 
-    Ok(AllStatistics { stats, categories, prices, tags, searches, monthly, shops })
+    // This period against the equally long one right before it
+    let comparison = match (&from, p.compare_previous) {
+        (Some(from), true) => {
+            let (to, prev_from, prev_to): (String, String, String) = conn.query_row(
+                "SELECT COALESCE(?2, date('now')),
+                        date(?1, printf('-%d days',
+                            julianday(COALESCE(?2, date('now'))) - julianday(?1) + 1)),
+                        date(?1, '-1 day')",
+                params![from, to],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?;
+            Some(PeriodComparison {
+                current: period_summary(&conn, from, &to, p.collection_id, tag)?,
+                previous: period_summary(&conn, &prev_from, &prev_to, p.collection_id, tag)?,
+            })
+        }
+        _ => None,
+    };
+
+    Ok(AllStatistics {
+        stats,
+        categories,
+        prices,
+        availability,
+        tags,
+        searches,
+        monthly,
+        monthly_spend,
+        shops,
+        comparison,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys_and_counts(buckets: &[PriceBucket]) -> Vec<(&str, i64)> {
+        buckets.iter().map(|b| (b.key.as_str(), b.count)).collect()
+    }
+
+    #[test]
+    fn default_buckets_match_fixed_ranges() {
+        let buckets = bucket_prices(
+            &[0, 500, 501, 3000, 10000, 10001],
+            DEFAULT_PRICE_BOUNDS,
+            StatsLocale::Ko,
+        );
+        assert_eq!(
+            keys_and_counts(&buckets),
+            vec![
+                ("free", 1),
+                ("~500", 1),
+                ("501~1000", 1),
+                ("1001~3000", 1),
+                ("3001~5000", 0),
+                ("5001~10000", 1),
+                ("10000~", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn ranges_must_run_forward() {
+        let today = "2024-03-10";
+        assert!(validate_range(Some("2024-03-01"), Some("2024-03-01"), today).is_ok());
+        assert!(validate_range(Some("2024-03-01"), None, today).is_ok());
+        assert!(validate_range(None, Some("2024-01-01"), today).is_ok());
+        assert!(validate_range(Some("2024-03-10"), Some("2024-03-02"), today).is_err());
+        assert!(validate_range(Some("2024-03-11"), None, today).is_err());
+    }
+
+    #[test]
+    fn custom_buckets_without_free_bucket() {
+        let buckets = bucket_prices(&[0, 800, 2500], &[1000, 2000], StatsLocale::En);
+        assert_eq!(
+            keys_and_counts(&buckets),
+            vec![("~1000", 2), ("1001~2000", 0), ("2000~", 1)]
+        );
+        assert_eq!(
+            buckets.iter().map(|b| (b.min, b.max)).collect::<Vec<_>>(),
+            vec![(0, Some(1000)), (1001, Some(2000)), (2001, None)]
+        );
+    }
+
+    #[test]
+    fn free_bucket_label_follows_locale() {
+        for (locale, label) in [
+            (StatsLocale::Ja, "無料"),
+            (StatsLocale::Ko, "무료"),
+            (StatsLocale::En, "Free"),
+        ] {
+            let buckets = bucket_prices(&[0], DEFAULT_PRICE_BOUNDS, locale);
+            assert_eq!((buckets[0].key.as_str(), buckets[0].label.as_str()), ("free", label));
+        }
+    }
+
+    #[test]
+    fn rejects_unsorted_or_negative_bounds() {
+        assert!(validate_bounds(Some(vec![500, 100])).is_err());
+        assert!(validate_bounds(Some(vec![-1, 100])).is_err());
+        assert!(validate_bounds(Some(vec![])).is_err());
+        assert_eq!(validate_bounds(None).unwrap(), DEFAULT_PRICE_BOUNDS);
+    }
 }
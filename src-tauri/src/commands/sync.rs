@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use rusqlite::params;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::database::{self, AppDatabase};
+use crate::error::{AppError, AppResult};
+use crate::sync::{self, SyncReport, SYNC_FOLDER_KEY};
+
+// ── Types ──────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct SyncDevice {
+    pub device: String,
+    /// Lines of its log merged so far
+    pub applied: i64,
+    pub last_seen_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncStatus {
+    /// `None` while sync is off
+    pub folder: Option<String>,
+    pub device_id: String,
+    pub devices: Vec<SyncDevice>,
+}
+
+// ── Commands ───────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_sync_status(db: State<'_, AppDatabase>) -> AppResult<SyncStatus> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT device, applied, last_seen_at FROM sync_devices ORDER BY last_seen_at DESC",
+    )?;
+    let devices = stmt
+        .query_map([], |row| {
+            Ok(SyncDevice {
+                device: row.get(0)?,
+                applied: row.get(1)?,
+                last_seen_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(SyncStatus {
+        folder: sync::sync_folder(&conn)?.map(|f| f.to_string_lossy().into_owned()),
+        device_id: sync::device_id(&conn)?,
+        devices,
+    })
+}
+
+/// Set the shared folder, or turn sync off with `None`. Progress through the logs of
+/// another folder does not carry over; the merged library does.
+#[tauri::command]
+pub fn set_sync_folder(db: State<'_, AppDatabase>, folder: Option<String>) -> AppResult<()> {
+    let folder = folder.map(|f| f.trim().to_string()).unwrap_or_default();
+    if !folder.is_empty() && !Path::new(&folder).is_dir() {
+        return Err(AppError::ParseError(format!("Not a folder: '{}'", folder)));
+    }
+    let conn = db.conn()?;
+    if database::get_setting(&conn, SYNC_FOLDER_KEY)?.unwrap_or_default() == folder {
+        return Ok(());
+    }
+    conn.execute("DELETE FROM sync_devices", params![])?;
+    database::set_setting(&conn, SYNC_FOLDER_KEY, &folder)
+}
+
+/// Publish local changes and merge those of other devices. Emits `library-synced` when
+/// the library changed.
+#[tauri::command]
+pub fn sync_library(app: AppHandle, db: State<'_, AppDatabase>) -> AppResult<SyncReport> {
+    let report = {
+        let mut conn = db.conn_mut()?;
+        let folder = sync::sync_folder(&conn)?
+            .ok_or_else(|| AppError::ParseError("No sync folder is set".to_string()))?;
+        sync::sync(&mut conn, &folder)?
+    };
+    if report.imported > 0 {
+        let _ = app.emit("library-synced", report.clone());
+    }
+    Ok(report)
+}
@@ -0,0 +1,115 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::database::{self, AppDatabase};
+use crate::error::{AppError, AppResult};
+use crate::settings::{Settings, SettingsPatch};
+
+use super::content_policy::{content_policy, police_trash};
+use super::settings::change_settings;
+
+// ── Types ──────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrashKind {
+    Favorite,
+    Collection,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrashEntry {
+    pub kind: TrashKind,
+    /// `item_id` for favorites, collection `id` for collections
+    pub id: i64,
+    pub name: String,
+    pub thumbnail_url: Option<String>,
+    pub deleted_at: String,
+    /// Always false for collections
+    pub is_adult: bool,
+    /// Set by the content policy on R-18 favorites the UI should blur
+    pub blurred: bool,
+}
+
+// ── Commands ───────────────────────────────────────────
+
+/// List trashed favorites and collections, most recently deleted first.
+#[tauri::command]
+pub fn get_trash(db: State<'_, AppDatabase>) -> AppResult<Vec<TrashEntry>> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT 'favorite', item_id, name, thumbnail_url, deleted_at, is_adult
+         FROM favorites WHERE deleted_at IS NOT NULL
+         UNION ALL
+         SELECT 'collection', id, name, NULL, deleted_at, 0
+         FROM collections WHERE deleted_at IS NOT NULL
+         ORDER BY 5 DESC",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            let kind: String = row.get(0)?;
+            Ok(TrashEntry {
+                kind: if kind == "collection" {
+                    TrashKind::Collection
+                } else {
+                    TrashKind::Favorite
+                },
+                id: row.get(1)?,
+                name: row.get(2)?,
+                thumbnail_url: row.get(3)?,
+                deleted_at: row.get(4)?,
+                is_adult: row.get(5)?,
+                blurred: false,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(police_trash(content_policy(&conn)?, rows))
+}
+
+#[tauri::command]
+pub fn restore_from_trash(db: State<'_, AppDatabase>, kind: TrashKind, id: i64) -> AppResult<()> {
+    let conn = db.conn()?;
+    let affected = match kind {
+        TrashKind::Favorite => conn.execute(
+            "UPDATE favorites SET deleted_at = NULL WHERE item_id = ?1 AND deleted_at IS NOT NULL",
+            params![id],
+        )?,
+        TrashKind::Collection => conn.execute(
+            "UPDATE collections SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![id],
+        )?,
+    };
+    if affected == 0 {
+        return Err(AppError::NotFound(format!("Trashed {:?} {}", kind, id)));
+    }
+    Ok(())
+}
+
+/// Permanently delete everything in the trash. Returns the number of purged rows.
+#[tauri::command]
+pub fn empty_trash(db: State<'_, AppDatabase>) -> AppResult<usize> {
+    let mut conn = db.conn_mut()?;
+    database::purge_trash(&mut conn, None)
+}
+
+#[tauri::command]
+pub fn get_trash_retention_days(db: State<'_, AppDatabase>) -> AppResult<i64> {
+    let conn = db.conn()?;
+    Ok(Settings::load(&conn)?.trash_retention_days)
+}
+
+/// Set how long trashed rows are kept. Expired rows are purged immediately and on every startup.
+/// Emits `settings-changed` like `update_settings`.
+#[tauri::command]
+pub fn set_trash_retention_days(
+    app: AppHandle,
+    db: State<'_, AppDatabase>,
+    days: i64,
+) -> AppResult<()> {
+    let patch = SettingsPatch {
+        trash_retention_days: Some(days),
+        ..Default::default()
+    };
+    change_settings(&app, &db, patch).map(|_| ())
+}
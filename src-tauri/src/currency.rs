@@ -0,0 +1,190 @@
+//! Exchange rates for showing JPY prices in other currencies.
+//!
+//! Booth prices are always JPY integers, so every rate is stored as "how many JPY one unit
+//! of the currency is worth" (`jpy_per_unit`, e.g. 150.0 for USD, 0.11 for KRW) together
+//! with the date it applies to. Conversions pick the latest rate on or before the date in
+//! question, so the last fetched or imported rates keep working offline.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::dates::validate_date;
+use crate::error::{AppError, AppResult};
+
+pub const BASE_CURRENCY: &str = "JPY";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExchangeRate {
+    pub currency: String,
+    /// `YYYY-MM-DD`
+    pub rate_date: String,
+    /// JPY per one unit of `currency`
+    pub jpy_per_unit: f64,
+    /// `manual`, `import` or the name of the rate provider
+    pub source: String,
+}
+
+/// Upper-cased ISO 4217 code, or an error for anything that does not look like one.
+pub fn normalize_currency(code: &str) -> AppResult<String> {
+    let code = code.trim().to_ascii_uppercase();
+    if code.len() != 3 || !code.bytes().all(|b| b.is_ascii_uppercase()) {
+        return Err(AppError::ParseError(format!(
+            "Invalid currency code '{}'",
+            code
+        )));
+    }
+    Ok(code)
+}
+
+/// Digits after the decimal point used when displaying amounts.
+pub fn minor_units(currency: &str) -> i32 {
+    match currency {
+        "JPY" | "KRW" | "VND" | "CLP" | "ISK" | "TWD" => 0,
+        _ => 2,
+    }
+}
+
+/// Convert a JPY amount, rounded to the currency's minor units.
+pub fn convert(amount_jpy: i64, jpy_per_unit: f64, currency: &str) -> f64 {
+    let factor = 10f64.powi(minor_units(currency));
+    (amount_jpy as f64 / jpy_per_unit * factor).round() / factor
+}
+
+/// Parse `date,currency,jpy_per_unit` lines (a header line and blank lines are skipped).
+/// Returns the rates together with the line numbers that could not be parsed.
+pub fn parse_rates_csv(text: &str) -> (Vec<ExchangeRate>, Vec<usize>) {
+    let mut rates = Vec::new();
+    let mut bad_lines = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || (index == 0 && line.to_ascii_lowercase().starts_with("date")) {
+            continue;
+        }
+        let fields: Vec<&str> = line.split([',', '\t', ';']).map(str::trim).collect();
+        let parsed = match fields.as_slice() {
+            [date, currency, rate] => {
+                let date_ok = validate_date(date).ok();
+                let currency = normalize_currency(currency).ok();
+                let rate = rate
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|r| r.is_finite() && *r > 0.0);
+                match (date_ok, currency, rate) {
+                    (Some(date), Some(currency), Some(rate)) => Some(ExchangeRate {
+                        currency,
+                        rate_date: date.chars().take(10).collect(),
+                        jpy_per_unit: rate,
+                        source: "import".to_string(),
+                    }),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        match parsed {
+            Some(rate) => rates.push(rate),
+            None => bad_lines.push(index + 1),
+        }
+    }
+    (rates, bad_lines)
+}
+
+pub fn save_rate(conn: &Connection, rate: &ExchangeRate) -> AppResult<()> {
+    conn.execute(
+        "INSERT INTO exchange_rates (currency, rate_date, jpy_per_unit, source)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(currency, rate_date) DO UPDATE SET
+            jpy_per_unit = excluded.jpy_per_unit,
+            source = excluded.source,
+            created_at = datetime('now')",
+        params![
+            rate.currency,
+            rate.rate_date,
+            rate.jpy_per_unit,
+            rate.source
+        ],
+    )?;
+    Ok(())
+}
+
+/// Rate in effect on `date` (`YYYY-MM-DD`), or the latest known rate without a date.
+/// Dates before the first recorded rate use the earliest one.
+pub fn rate_on(
+    conn: &Connection,
+    currency: &str,
+    date: Option<&str>,
+) -> AppResult<Option<ExchangeRate>> {
+    if currency == BASE_CURRENCY {
+        return Ok(Some(ExchangeRate {
+            currency: BASE_CURRENCY.to_string(),
+            rate_date: date.unwrap_or("").to_string(),
+            jpy_per_unit: 1.0,
+            source: "base".to_string(),
+        }));
+    }
+    let day = date.map(|d| d.chars().take(10).collect::<String>());
+    let rate = conn
+        .query_row(
+            "SELECT currency, rate_date, jpy_per_unit, source FROM exchange_rates
+             WHERE currency = ?1
+             ORDER BY rate_date > COALESCE(?2, '9999-12-31'),
+                      CASE WHEN rate_date <= COALESCE(?2, '9999-12-31') THEN rate_date END DESC,
+                      rate_date ASC
+             LIMIT 1",
+            params![currency, day],
+            |row| {
+                Ok(ExchangeRate {
+                    currency: row.get(0)?,
+                    rate_date: row.get(1)?,
+                    jpy_per_unit: row.get(2)?,
+                    source: row.get(3)?,
+                })
+            },
+        )
+        .optional()?;
+    Ok(rate)
+}
+
+/// Like [`rate_on`], but a missing rate is an error the UI can show.
+pub fn require_rate(
+    conn: &Connection,
+    currency: &str,
+    date: Option<&str>,
+) -> AppResult<ExchangeRate> {
+    rate_on(conn, currency, date)?
+        .ok_or_else(|| AppError::NotFound(format!("Exchange rate for {}", currency)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_with_currency_precision() {
+        assert_eq!(convert(1500, 150.0, "USD"), 10.0);
+        assert_eq!(convert(1000, 150.0, "USD"), 6.67);
+        assert_eq!(convert(1000, 0.11, "KRW"), 9091.0);
+    }
+
+    #[test]
+    fn parses_rate_csv_and_reports_bad_lines() {
+        let (rates, bad) = parse_rates_csv(
+            "date,currency,jpy_per_unit\n2024-01-05,usd,144.5\n\n2024-01-05;KRW;0.11\nnope\n2024-01-06,USD,-1",
+        );
+        assert_eq!(
+            rates
+                .iter()
+                .map(|r| (r.rate_date.as_str(), r.currency.as_str(), r.jpy_per_unit))
+                .collect::<Vec<_>>(),
+            vec![("2024-01-05", "USD", 144.5), ("2024-01-05", "KRW", 0.11)]
+        );
+        assert_eq!(bad, vec![5, 6]);
+    }
+
+    #[test]
+    fn rejects_invalid_currency_codes() {
+        assert_eq!(normalize_currency(" krw ").unwrap(), "KRW");
+        assert!(normalize_currency("WON!").is_err());
+        assert!(normalize_currency("US").is_err());
+    }
+}
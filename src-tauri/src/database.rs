@@ -1,21 +1,101 @@
-use std::path::PathBuf;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, ErrorCode, OptionalExtension};
+use serde::Serialize;
 
+use crate::booth::models::ADULT_MARKERS;
 use crate::error::{AppError, AppResult};
+use crate::settings::Settings;
+
+/// `app_settings` key holding the number of days trashed rows are kept before purging.
+pub const TRASH_RETENTION_KEY: &str = "trash_retention_days";
+/// Set once migration v21 has flagged R-18 items by their markers.
+const ADULT_MARKERS_SCANNED_KEY: &str = "adult_markers_scanned";
+pub const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+
+/// Database file of the default profile, in the app data dir.
+pub const DEFAULT_DB_FILE: &str = "boothhunter.db";
 
 pub struct AppDatabase {
     conn: Mutex<Connection>,
+    /// Set while an encrypted database waits for its passphrase; `conn` is then a
+    /// placeholder in memory and every command gets [`AppError::Locked`].
+    locked: AtomicBool,
 }
 
 impl AppDatabase {
-    pub fn initialize(app_data_dir: PathBuf) -> Result<Self, AppError> {
-        std::fs::create_dir_all(&app_data_dir)
-            .map_err(|e| AppError::Database(format!("Failed to create data dir: {}", e)))?;
+    /// Start without a database until [`AppDatabase::switch_to`] gets the passphrase.
+    pub fn locked() -> AppResult<Self> {
+        Ok(Self {
+            conn: Mutex::new(Connection::open_in_memory()?),
+            locked: AtomicBool::new(true),
+        })
+    }
 
-        let db_path = app_data_dir.join("boothhunter.db");
-        let conn = Connection::open(&db_path)?;
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::SeqCst)
+    }
+
+    /// Replace the connection with one to another database file, e.g. on a profile switch.
+    /// The new file is opened and migrated first, so on error the current one stays in use.
+    pub fn switch_to(&self, db_path: &Path, key: Option<&str>) -> AppResult<()> {
+        let conn = Self::open_connection(db_path, key)?;
+        *self.lock_conn()? = conn;
+        self.locked.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Close the current database and wait for a passphrase.
+    pub fn lock(&self) -> AppResult<()> {
+        *self.lock_conn()? = Connection::open_in_memory()?;
+        self.locked.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Close the database file while `rewrite` replaces it, then reopen it with `key`,
+    /// unlocked. Commands wait on the mutex meanwhile. If `rewrite` fails the file is
+    /// reopened with `previous_key`. If it cannot be reopened the database stays locked,
+    /// so commands get [`AppError::Locked`] rather than the placeholder.
+    pub fn rewrite<T>(
+        &self,
+        db_path: &Path,
+        previous_key: Option<&str>,
+        key: Option<&str>,
+        rewrite: impl FnOnce() -> AppResult<T>,
+    ) -> AppResult<T> {
+        let mut conn = self.lock_conn()?;
+        self.locked.store(true, Ordering::SeqCst);
+        *conn = Connection::open_in_memory()?;
+        let result = rewrite();
+        let reopen_key = if result.is_ok() { key } else { previous_key };
+        *conn = Self::open_connection(db_path, reopen_key)?;
+        self.locked.store(false, Ordering::SeqCst);
+        result
+    }
+
+    /// Open a database file, creating and migrating it as needed.
+    pub fn open_connection(db_path: &Path, key: Option<&str>) -> AppResult<Connection> {
+        if let Some(dir) = db_path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| AppError::Database(format!("Failed to create data dir: {}", e)))?;
+        }
+
+        let mut conn = Connection::open(db_path)?;
+        if let Some(key) = key {
+            conn.pragma_update(None, "key", key)?;
+        }
+        // The first read fails with "file is not a database" on a wrong or missing key
+        conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| {
+            row.get::<_, i64>(0)
+        })
+        .map_err(|e| match e {
+            rusqlite::Error::SqliteFailure(f, _) if f.code == ErrorCode::NotADatabase => {
+                AppError::Locked
+            }
+            e => e.into(),
+        })?;
 
         conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA foreign_keys = ON;")?;
 
@@ -140,24 +220,443 @@ impl AppDatabase {
             );",
         )?;
 
-        // Evict cached items older than 30 days to prevent unbounded growth
+        // Migration v7: soft-delete trash for favorites & collections, key-value settings
+        if !has_column(&conn, "favorites", "deleted_at") {
+            conn.execute_batch("ALTER TABLE favorites ADD COLUMN deleted_at TEXT;")?;
+        }
+        if !has_column(&conn, "collections", "deleted_at") {
+            conn.execute_batch("ALTER TABLE collections ADD COLUMN deleted_at TEXT;")?;
+        }
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_favorites_deleted_at ON favorites(deleted_at);
+             CREATE INDEX IF NOT EXISTS idx_collections_deleted_at ON collections(deleted_at);
+
+             CREATE TABLE IF NOT EXISTS app_settings (
+                key         TEXT PRIMARY KEY,
+                value       TEXT NOT NULL,
+                updated_at  TEXT DEFAULT (datetime('now'))
+             );",
+        )?;
+
+        // Migration v8: favorite snapshot refresh (availability + change log)
+        if !has_column(&conn, "favorites", "availability") {
+            conn.execute_batch(
+                "ALTER TABLE favorites ADD COLUMN availability TEXT NOT NULL DEFAULT 'available';
+                 ALTER TABLE favorites ADD COLUMN last_checked_at TEXT;",
+            )?;
+        }
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS favorite_changes (
+                id          INTEGER PRIMARY KEY AUTOINCREMENT,
+                item_id     INTEGER NOT NULL,
+                field       TEXT NOT NULL,
+                old_value   TEXT,
+                new_value   TEXT,
+                changed_at  TEXT DEFAULT (datetime('now'))
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_favorite_changes_item ON favorite_changes(item_id);
+            CREATE INDEX IF NOT EXISTS idx_favorite_changes_changed_at ON favorite_changes(changed_at);",
+        )?;
+
+        // Migration v9: availability status on cached items
+        if !has_column(&conn, "cached_items", "availability") {
+            conn.execute_batch(
+                "ALTER TABLE cached_items ADD COLUMN availability TEXT NOT NULL DEFAULT 'available';
+                 ALTER TABLE cached_items ADD COLUMN checked_at TEXT;
+                 UPDATE cached_items SET checked_at = cached_at;",
+            )?;
+        }
+
+        // Migration v10: purchased/owned library
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS owned_items (
+                id            INTEGER PRIMARY KEY AUTOINCREMENT,
+                item_id       INTEGER NOT NULL,
+                name          TEXT,
+                variation     TEXT,
+                price_paid    INTEGER NOT NULL DEFAULT 0,
+                purchased_at  TEXT,
+                license       TEXT,
+                note          TEXT,
+                created_at    TEXT DEFAULT (datetime('now'))
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_owned_items_item ON owned_items(item_id);
+            CREATE INDEX IF NOT EXISTS idx_owned_items_purchased_at ON owned_items(purchased_at);",
+        )?;
+
+        // Migration v11: downloaded archives found on disk, matched to items
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS local_files (
+                id            INTEGER PRIMARY KEY AUTOINCREMENT,
+                path          TEXT NOT NULL UNIQUE,
+                file_name     TEXT NOT NULL,
+                size_bytes    INTEGER NOT NULL DEFAULT 0,
+                modified_at   INTEGER,
+                item_id       INTEGER,
+                match_score   REAL,
+                match_reason  TEXT,
+                is_manual     INTEGER NOT NULL DEFAULT 0,
+                scanned_at    TEXT DEFAULT (datetime('now'))
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_local_files_item ON local_files(item_id);",
+        )?;
+
+        // Migration v12: unitypackage contents per local file
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS package_inspections (
+                local_file_id  INTEGER PRIMARY KEY,
+                file_modified  INTEGER,
+                asset_count    INTEGER NOT NULL DEFAULT 0,
+                avatars_json   TEXT NOT NULL DEFAULT '[]',
+                shaders_json   TEXT NOT NULL DEFAULT '[]',
+                has_prefab     INTEGER NOT NULL DEFAULT 0,
+                paths_json     TEXT NOT NULL DEFAULT '[]',
+                error          TEXT,
+                inspected_at   TEXT DEFAULT (datetime('now'))
+            );",
+        )?;
+
+        // Migration v13: exchange rates (JPY per unit of each currency, by date)
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS exchange_rates (
+                currency      TEXT NOT NULL,
+                rate_date     TEXT NOT NULL,
+                jpy_per_unit  REAL NOT NULL,
+                source        TEXT NOT NULL DEFAULT 'manual',
+                created_at    TEXT DEFAULT (datetime('now')),
+                PRIMARY KEY (currency, rate_date)
+            );",
+        )?;
+
+        // Migration v14: search analytics (result counts, filters, conversion to favorites)
+        if !has_column(&conn, "search_history", "result_count") {
+            conn.execute_batch(
+                "ALTER TABLE search_history ADD COLUMN result_count INTEGER;
+                 ALTER TABLE search_history ADD COLUMN filters_json TEXT;
+                 ALTER TABLE search_history ADD COLUMN session_id TEXT;
+                 ALTER TABLE search_history ADD COLUMN converted_item_id INTEGER;
+                 ALTER TABLE search_history ADD COLUMN converted_at TEXT;",
+            )?;
+        }
         conn.execute(
-            "DELETE FROM cached_items WHERE cached_at < datetime('now', '-30 days')",
+            "CREATE INDEX IF NOT EXISTS idx_search_history_keyword ON search_history(keyword)",
             [],
         )?;
 
-        // Evict translations older than 90 days
-        conn.execute(
-            "DELETE FROM translations WHERE created_at < datetime('now', '-90 days')",
+        // Migration v15: monthly budget (effective from a month on) and purchase priorities
+        if !has_column(&conn, "favorites", "purchase_priority") {
+            conn.execute_batch("ALTER TABLE favorites ADD COLUMN purchase_priority INTEGER;")?;
+        }
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS monthly_budgets (
+                effective_month  TEXT PRIMARY KEY,
+                amount           INTEGER NOT NULL,
+                created_at       TEXT DEFAULT (datetime('now'))
+            );",
+        )?;
+
+        // Migration v16: price alert rules, alert log and per-favorite mute
+        if !has_column(&conn, "favorites", "alerts_muted") {
+            conn.execute_batch(
+                "ALTER TABLE favorites ADD COLUMN alerts_muted INTEGER NOT NULL DEFAULT 0;",
+            )?;
+        }
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS price_alert_rules (
+                id          INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind        TEXT NOT NULL,
+                threshold   INTEGER,
+                item_id     INTEGER,
+                enabled     INTEGER NOT NULL DEFAULT 1,
+                created_at  TEXT DEFAULT (datetime('now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS price_alerts (
+                id            INTEGER PRIMARY KEY AUTOINCREMENT,
+                rule_id       INTEGER,
+                item_id       INTEGER NOT NULL,
+                kind          TEXT NOT NULL,
+                threshold     INTEGER,
+                old_price     INTEGER NOT NULL,
+                new_price     INTEGER NOT NULL,
+                triggered_at  TEXT DEFAULT (datetime('now')),
+                read_at       TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_price_alerts_triggered ON price_alerts(triggered_at);",
+        )?;
+
+        // Migration v17: followed shops and the items seen on their listings
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS shops (
+                id               INTEGER PRIMARY KEY AUTOINCREMENT,
+                subdomain        TEXT NOT NULL UNIQUE,
+                name             TEXT,
+                followed         INTEGER NOT NULL DEFAULT 0,
+                last_fetched_at  TEXT,
+                last_visited_at  TEXT,
+                created_at       TEXT DEFAULT (datetime('now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS shop_items (
+                shop_id        INTEGER NOT NULL,
+                item_id        INTEGER NOT NULL,
+                name           TEXT,
+                price          INTEGER,
+                thumbnail_url  TEXT,
+                first_seen_at  TEXT DEFAULT (datetime('now')),
+                last_seen_at   TEXT DEFAULT (datetime('now')),
+                PRIMARY KEY (shop_id, item_id)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_shop_items_item ON shop_items(item_id);",
+        )?;
+
+        // Migration v18: shop and category entities referenced by favorites and cached items.
+        // Shops known only by name have no subdomain, so the column becomes nullable.
+        let subdomain_required: bool = conn.query_row(
+            "SELECT COALESCE(MAX(\"notnull\"), 0) FROM pragma_table_info('shops')
+             WHERE name = 'subdomain'",
             [],
+            |row| row.get(0),
         )?;
+        if subdomain_required {
+            conn.execute_batch(
+                "BEGIN;
+                 CREATE TABLE shops_new (
+                     id               INTEGER PRIMARY KEY AUTOINCREMENT,
+                     subdomain        TEXT UNIQUE,
+                     name             TEXT,
+                     followed         INTEGER NOT NULL DEFAULT 0,
+                     last_fetched_at  TEXT,
+                     last_visited_at  TEXT,
+                     created_at       TEXT DEFAULT (datetime('now'))
+                 );
+                 INSERT INTO shops_new SELECT id, subdomain, name, followed, last_fetched_at,
+                     last_visited_at, created_at FROM shops;
+                 DROP TABLE shops;
+                 ALTER TABLE shops_new RENAME TO shops;
+                 COMMIT;",
+            )?;
+        }
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_shops_name ON shops(name);
 
-        Ok(Self {
-            conn: Mutex::new(conn),
-        })
+            CREATE TABLE IF NOT EXISTS categories (
+                id          INTEGER PRIMARY KEY AUTOINCREMENT,
+                name        TEXT NOT NULL UNIQUE,
+                created_at  TEXT DEFAULT (datetime('now'))
+            );",
+        )?;
+        if !has_column(&conn, "favorites", "shop_id") {
+            conn.execute_batch(
+                "ALTER TABLE favorites ADD COLUMN shop_id INTEGER REFERENCES shops(id) ON DELETE SET NULL;
+                 ALTER TABLE favorites ADD COLUMN category_id INTEGER REFERENCES categories(id) ON DELETE SET NULL;
+                 ALTER TABLE cached_items ADD COLUMN shop_id INTEGER REFERENCES shops(id) ON DELETE SET NULL;
+                 ALTER TABLE cached_items ADD COLUMN category_id INTEGER REFERENCES categories(id) ON DELETE SET NULL;",
+            )?;
+        }
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_favorites_shop ON favorites(shop_id);
+             CREATE INDEX IF NOT EXISTS idx_cached_items_shop ON cached_items(shop_id);",
+        )?;
+        crate::entities::backfill(&conn)?;
+
+        // Migration v19: item variations; favorites keep the cheapest price in `price`
+        if !has_column(&conn, "cached_items", "variations_json") {
+            conn.execute_batch(
+                "ALTER TABLE cached_items ADD COLUMN variations_json TEXT NOT NULL DEFAULT '[]';
+                 ALTER TABLE favorites ADD COLUMN price_max INTEGER;",
+            )?;
+        }
+
+        // Migration v20: adult flag, release dates, sale markers and shop thumbnails
+        if !has_column(&conn, "cached_items", "is_adult") {
+            conn.execute_batch(
+                "ALTER TABLE cached_items ADD COLUMN is_adult INTEGER NOT NULL DEFAULT 0;
+                 ALTER TABLE cached_items ADD COLUMN published_at TEXT;
+                 ALTER TABLE cached_items ADD COLUMN updated_at TEXT;
+                 ALTER TABLE cached_items ADD COLUMN event_name TEXT;
+                 ALTER TABLE cached_items ADD COLUMN is_sold_out INTEGER NOT NULL DEFAULT 0;
+                 ALTER TABLE cached_items ADD COLUMN is_end_of_sale INTEGER NOT NULL DEFAULT 0;
+                 ALTER TABLE favorites ADD COLUMN is_adult INTEGER NOT NULL DEFAULT 0;
+                 ALTER TABLE favorites ADD COLUMN published_at TEXT;
+                 ALTER TABLE shops ADD COLUMN thumbnail_url TEXT;",
+            )?;
+        }
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_cached_items_published ON cached_items(published_at);",
+        )?;
+
+        // Migration v21: flag R-18 items saved before the adult flag existed by their markers
+        let marked = |columns: &[&str]| {
+            columns
+                .iter()
+                .flat_map(|c| {
+                    ADULT_MARKERS
+                        .iter()
+                        .map(move |m| format!("{} LIKE '%{}%'", c, m))
+                })
+                .collect::<Vec<_>>()
+                .join(" OR ")
+        };
+        if get_setting(&conn, ADULT_MARKERS_SCANNED_KEY)?.is_none() {
+            conn.execute_batch(&format!(
+                "UPDATE favorites SET is_adult = 1 WHERE is_adult = 0 AND ({});
+                 UPDATE cached_items SET is_adult = 1 WHERE is_adult = 0 AND ({});",
+                marked(&["name", "category_name"]),
+                marked(&["name", "category_name", "tags_json"]),
+            ))?;
+            set_setting(&conn, ADULT_MARKERS_SCANNED_KEY, "1")?;
+        }
+
+        // Migration v22: typed settings; stamp the layout version the values use
+        crate::settings::migrate(&conn)?;
+        let settings = Settings::load(&conn)?;
+
+        // Migration v23: tags and collection memberships reference their favorite, so purging
+        // a favorite takes them along. Cached items that were tagged or collected are favorited
+        // first, as tagging them does now; only rows of items nothing is known about are
+        // dropped.
+        let tags_reference_favorites: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM pragma_foreign_key_list('item_tags')
+             WHERE \"table\" = 'favorites')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !tags_reference_favorites {
+            let tx = conn.transaction()?;
+            let unfavorited: Vec<i64> = {
+                let mut stmt = tx.prepare(
+                    "SELECT id FROM cached_items
+                     WHERE id NOT IN (SELECT item_id FROM favorites)
+                       AND (id IN (SELECT item_id FROM item_tags)
+                            OR id IN (SELECT item_id FROM collection_items))",
+                )?;
+                let rows = stmt
+                    .query_map([], |row| row.get(0))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                rows
+            };
+            for item_id in unfavorited {
+                crate::entities::favorite_cached(&tx, item_id)?;
+            }
+            tx.execute_batch(
+                "CREATE TABLE collection_items_new (
+                     collection_id  INTEGER NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+                     item_id        INTEGER NOT NULL REFERENCES favorites(item_id) ON DELETE CASCADE,
+                     added_at       TEXT DEFAULT (datetime('now')),
+                     PRIMARY KEY (collection_id, item_id)
+                 );
+                 INSERT INTO collection_items_new
+                     SELECT collection_id, item_id, added_at FROM collection_items
+                     WHERE item_id IN (SELECT item_id FROM favorites)
+                       AND collection_id IN (SELECT id FROM collections);
+                 DROP TABLE collection_items;
+                 ALTER TABLE collection_items_new RENAME TO collection_items;
+
+                 CREATE TABLE item_tags_new (
+                     id       INTEGER PRIMARY KEY AUTOINCREMENT,
+                     item_id  INTEGER NOT NULL REFERENCES favorites(item_id) ON DELETE CASCADE,
+                     tag      TEXT NOT NULL,
+                     UNIQUE(item_id, tag)
+                 );
+                 INSERT INTO item_tags_new
+                     SELECT id, item_id, tag FROM item_tags
+                     WHERE item_id IN (SELECT item_id FROM favorites);
+                 DROP TABLE item_tags;
+                 ALTER TABLE item_tags_new RENAME TO item_tags;
+
+                 CREATE INDEX idx_collection_items_item ON collection_items(item_id);
+                 CREATE INDEX idx_collection_items_collection ON collection_items(collection_id);
+                 CREATE INDEX idx_item_tags_item ON item_tags(item_id);
+                 CREATE INDEX idx_item_tags_tag ON item_tags(tag);",
+            )?;
+            tx.commit()?;
+        }
+
+        // Migration v24: folder sync; collections get IDs shared between devices
+        if !has_column(&conn, "collections", "uuid") {
+            conn.execute_batch("ALTER TABLE collections ADD COLUMN uuid TEXT;")?;
+        }
+        conn.execute_batch(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_collections_uuid ON collections(uuid);
+
+            CREATE TABLE IF NOT EXISTS sync_fields (
+                entity  TEXT NOT NULL,
+                key     TEXT NOT NULL,
+                field   TEXT NOT NULL,
+                value   TEXT NOT NULL,
+                at      INTEGER NOT NULL,
+                device  TEXT NOT NULL,
+                PRIMARY KEY (entity, key, field)
+            );
+
+            CREATE TABLE IF NOT EXISTS sync_devices (
+                device        TEXT PRIMARY KEY,
+                applied       INTEGER NOT NULL DEFAULT 0,
+                last_seen_at  TEXT
+            );",
+        )?;
+        crate::sync::assign_uuids(&conn)?;
+
+        // Migration v25: the items a search showed, so a favorite only converts searches that
+        // listed it
+        if !has_column(&conn, "search_history", "result_item_ids") {
+            conn.execute_batch("ALTER TABLE search_history ADD COLUMN result_item_ids TEXT;")?;
+        }
+
+        // Migration v26: when each synced field was last written, so sync stamps a change
+        // with its edit time
+        crate::sync::track_edits(&conn)?;
+
+        // Migration v27: purchases imported without a price keep it unknown (NULL) rather
+        // than counting as free
+        let price_required: bool = conn.query_row(
+            "SELECT \"notnull\" FROM pragma_table_info('owned_items') WHERE name = 'price_paid'",
+            [],
+            |row| row.get(0),
+        )?;
+        if price_required {
+            conn.execute_batch(
+                "BEGIN;
+                 CREATE TABLE owned_items_new (
+                     id            INTEGER PRIMARY KEY AUTOINCREMENT,
+                     item_id       INTEGER NOT NULL,
+                     name          TEXT,
+                     variation     TEXT,
+                     price_paid    INTEGER,
+                     purchased_at  TEXT,
+                     license       TEXT,
+                     note          TEXT,
+                     created_at    TEXT DEFAULT (datetime('now'))
+                 );
+                 INSERT INTO owned_items_new SELECT * FROM owned_items;
+                 DROP TABLE owned_items;
+                 ALTER TABLE owned_items_new RENAME TO owned_items;
+                 CREATE INDEX idx_owned_items_item ON owned_items(item_id);
+                 CREATE INDEX idx_owned_items_purchased_at ON owned_items(purchased_at);
+                 COMMIT;",
+            )?;
+        }
+
+        // Evict expired cache rows and trash to prevent unbounded growth
+        evict_expired(&mut conn, &settings, true)?;
+
+        Ok(conn)
     }
 
     pub fn conn(&self) -> AppResult<std::sync::MutexGuard<'_, Connection>> {
+        if self.is_locked() {
+            return Err(AppError::Locked);
+        }
+        self.lock_conn()
+    }
+
+    fn lock_conn(&self) -> AppResult<std::sync::MutexGuard<'_, Connection>> {
         self.conn.lock().map_err(|e| {
             log::error!("Database mutex poisoned: {}", e);
             AppError::Database(format!("Lock poisoned: {}", e))
@@ -207,3 +706,153 @@ impl AppDatabase {
         Ok(())
     }
 }
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> bool {
+    conn.prepare(&format!("SELECT {} FROM {} LIMIT 0", column, table))
+        .is_ok()
+}
+
+// ── Settings ───────────────────────────────────────────
+
+pub fn get_setting(conn: &Connection, key: &str) -> AppResult<Option<String>> {
+    let value = conn
+        .query_row(
+            "SELECT value FROM app_settings WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(value)
+}
+
+pub fn set_setting(conn: &Connection, key: &str, value: &str) -> AppResult<()> {
+    conn.execute(
+        "INSERT INTO app_settings (key, value, updated_at) VALUES (?1, ?2, datetime('now'))
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+// ── Trash ──────────────────────────────────────────────
+
+/// Rows removed by [`evict_expired`].
+#[derive(Debug, Default, Serialize)]
+pub struct Eviction {
+    pub cached_items: usize,
+    pub translations: usize,
+    pub trash: usize,
+}
+
+/// Drop cached items and translations past their lifetime, and trash past the retention
+/// period. Runs on startup and from maintenance. Without `remove` the rows are only
+/// counted.
+pub fn evict_expired(
+    conn: &mut Connection,
+    settings: &Settings,
+    remove: bool,
+) -> AppResult<Eviction> {
+    let tx = conn.transaction()?;
+    let cached_items = tx.execute(
+        "DELETE FROM cached_items WHERE cached_at < datetime('now', ?1)",
+        params![format!("-{} days", settings.cache_ttl_days)],
+    )?;
+    let translations = tx.execute(
+        "DELETE FROM translations WHERE created_at < datetime('now', ?1)",
+        params![format!("-{} days", settings.translation_ttl_days)],
+    )?;
+    let trash = delete_trashed(&tx, Some(settings.trash_retention_days))?;
+    if remove {
+        tx.commit()?;
+    }
+    Ok(Eviction {
+        cached_items,
+        translations,
+        trash,
+    })
+}
+
+/// Permanently delete trashed favorites and collections.
+/// With `older_than_days`, only rows trashed before that cutoff are purged; otherwise the whole trash is emptied.
+/// Returns the number of purged favorites + collections.
+pub fn purge_trash(conn: &mut Connection, older_than_days: Option<i64>) -> AppResult<usize> {
+    let tx = conn.transaction()?;
+    let purged = delete_trashed(&tx, older_than_days)?;
+    tx.commit()?;
+    Ok(purged)
+}
+
+fn delete_trashed(tx: &Connection, older_than_days: Option<i64>) -> AppResult<usize> {
+    let modifier = older_than_days.map(|d| format!("-{} days", d));
+    // Tags and memberships survive trashing so restores are lossless; on purge they go
+    // with the favorite through ON DELETE CASCADE
+    tx.execute(
+        "DELETE FROM favorite_changes WHERE item_id IN (
+            SELECT item_id FROM favorites
+            WHERE deleted_at IS NOT NULL AND (?1 IS NULL OR deleted_at < datetime('now', ?1)))",
+        params![modifier],
+    )?;
+    let favorites = tx.execute(
+        "DELETE FROM favorites
+         WHERE deleted_at IS NOT NULL AND (?1 IS NULL OR deleted_at < datetime('now', ?1))",
+        params![modifier],
+    )?;
+    // collection_items rows go with ON DELETE CASCADE
+    let collections = tx.execute(
+        "DELETE FROM collections
+         WHERE deleted_at IS NOT NULL AND (?1 IS NULL OR deleted_at < datetime('now', ?1))",
+        params![modifier],
+    )?;
+    Ok(favorites + collections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_of_cached_items_survive_the_foreign_key_rebuild() {
+        let dir = std::env::temp_dir().join(format!("bh-database-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.db");
+
+        // Lay out item_tags the way it was before v23, tagging a cached item and an unknown one
+        let conn = AppDatabase::open_connection(&path, None).unwrap();
+        conn.execute_batch(
+            "PRAGMA foreign_keys = OFF;
+             DROP TABLE item_tags;
+             CREATE TABLE item_tags (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 item_id INTEGER NOT NULL,
+                 tag TEXT NOT NULL,
+                 UNIQUE(item_id, tag)
+             );
+             INSERT INTO cached_items (id, name, price, url, cached_at)
+             VALUES (7, 'Knit', 500, 'https://booth.pm/ja/items/7', datetime('now'));
+             INSERT INTO item_tags (item_id, tag) VALUES (7, 'coat'), (8, 'gone');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let conn = AppDatabase::open_connection(&path, None).unwrap();
+        let favorite: (String, i64) = conn
+            .query_row(
+                "SELECT name, price FROM favorites WHERE item_id = 7",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(favorite, ("Knit".to_string(), 500));
+        let tags: Vec<i64> = conn
+            .prepare("SELECT item_id FROM item_tags")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(tags, vec![7]);
+        drop(conn);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
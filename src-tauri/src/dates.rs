@@ -0,0 +1,88 @@
+//! Validation of the dates users enter or import (purchase dates, rate dates, ranges,
+//! budget months).
+
+use crate::error::{AppError, AppResult};
+
+/// Accepts `YYYY-MM-DD`, optionally followed by a time (`YYYY-MM-DD HH:MM:SS`).
+pub fn validate_date(date: &str) -> AppResult<String> {
+    let trimmed = date.trim();
+    let day = trimmed.get(..10).unwrap_or_default();
+    let bytes = day.as_bytes();
+    let shape_ok = bytes.len() == 10
+        && bytes[4] == b'-'
+        && bytes[7] == b'-'
+        && bytes
+            .iter()
+            .enumerate()
+            .all(|(i, b)| i == 4 || i == 7 || b.is_ascii_digit());
+    let year: u32 = day.get(..4).and_then(|y| y.parse().ok()).unwrap_or(0);
+    let month: u32 = day.get(5..7).and_then(|m| m.parse().ok()).unwrap_or(0);
+    let dom: u32 = day.get(8..10).and_then(|d| d.parse().ok()).unwrap_or(0);
+    let leap = matches!((year % 4, year % 100, year % 400), (0, 1.., _) | (_, _, 0));
+    let days_in_month = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => 0,
+    };
+    if !shape_ok || !(1..=days_in_month).contains(&dom) {
+        return Err(AppError::ParseError(format!(
+            "Invalid date '{}' (expected YYYY-MM-DD)",
+            date
+        )));
+    }
+    Ok(trimmed.to_string())
+}
+
+/// Accepts `YYYY-MM`.
+pub fn validate_month(month: &str) -> AppResult<String> {
+    let month = month.trim();
+    if month.len() != 7 || validate_date(&format!("{}-01", month)).is_err() {
+        return Err(AppError::ParseError(format!(
+            "Invalid month '{}' (expected YYYY-MM)",
+            month
+        )));
+    }
+    Ok(month.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_date_accepts_dates_and_datetimes() {
+        assert_eq!(validate_date("2024-03-09").unwrap(), "2024-03-09");
+        assert_eq!(
+            validate_date(" 2024-03-09 12:30:00 ").unwrap(),
+            "2024-03-09 12:30:00"
+        );
+        assert!(validate_date("2024-02-29").is_ok());
+    }
+
+    #[test]
+    fn validate_date_rejects_malformed_input() {
+        for bad in [
+            "2024/03/09",
+            "2024-13-01",
+            "2024-00-10",
+            "24-03-09",
+            "",
+            "2024-03-9",
+            "2024-02-31",
+            "2023-02-29",
+            "2024-04-31",
+        ] {
+            assert!(validate_date(bad).is_err(), "{bad} should be rejected");
+        }
+    }
+
+    #[test]
+    fn validates_months() {
+        assert_eq!(validate_month(" 2024-03 ").unwrap(), "2024-03");
+        for bad in ["2024-13", "2024-00", "2024-3", "2024-03-01", "2024/03", ""] {
+            assert!(validate_month(bad).is_err(), "{bad} should be rejected");
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! Optional SQLCipher encryption of profile databases.
+//!
+//! A database is either plain SQLite or encrypted with a passphrase; which one is read
+//! from the file header, so nothing outside the file records it. The passphrase can be
+//! kept in the OS keyring (one entry per profile) to open the database without a prompt.
+//! Converting between the two rewrites the whole file with `sqlcipher_export` into a
+//! temporary file next to it, which then replaces the original.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+
+use crate::database::AppDatabase;
+use crate::error::{AppError, AppResult};
+
+const KEYRING_SERVICE: &str = "com.boothhunter.desktop";
+const PLAIN_HEADER: &[u8; 16] = b"SQLite format 3\0";
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// How the database of a profile can be opened right now.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Access {
+    Plain,
+    /// Encrypted, with the passphrase from the keyring
+    Passphrase(String),
+    /// Encrypted and no passphrase is stored; the user has to enter it
+    NeedsPassphrase,
+}
+
+impl Access {
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            Access::Passphrase(key) => Some(key),
+            _ => None,
+        }
+    }
+}
+
+// ── Helpers ────────────────────────────────────────────
+
+/// Whether the file exists and is not plain SQLite. A missing or empty file is plain,
+/// since opening it creates a plain database.
+pub fn is_encrypted(db_path: &Path) -> bool {
+    let mut header = [0u8; 16];
+    match std::fs::File::open(db_path).and_then(|mut f| f.read_exact(&mut header)) {
+        Ok(()) => &header != PLAIN_HEADER,
+        Err(_) => false,
+    }
+}
+
+pub fn check_passphrase(passphrase: &str) -> AppResult<()> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(AppError::ParseError(format!(
+            "Passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LEN
+        )));
+    }
+    Ok(())
+}
+
+pub fn access(profile_id: &str, db_path: &Path) -> Access {
+    if !is_encrypted(db_path) {
+        return Access::Plain;
+    }
+    match stored_passphrase(profile_id) {
+        Some(key) => Access::Passphrase(key),
+        None => Access::NeedsPassphrase,
+    }
+}
+
+fn with_suffix(db_path: &Path, suffix: &str) -> PathBuf {
+    let mut path = db_path.to_path_buf().into_os_string();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Fails with "Wrong passphrase" unless `passphrase` opens the database.
+pub fn verify_passphrase(db_path: &Path, passphrase: &str) -> AppResult<()> {
+    let conn = Connection::open(db_path)?;
+    conn.pragma_update(None, "key", passphrase)?;
+    conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |_| Ok(()))
+        .map_err(|_| AppError::ParseError("Wrong passphrase".to_string()))
+}
+
+/// Make `db` use the database of a profile, with the passphrase from the keyring if it is
+/// encrypted. Without a (working) stored passphrase `db` is left locked.
+pub fn open_profile(db: &AppDatabase, profile_id: &str, db_path: &Path) -> AppResult<()> {
+    match access(profile_id, db_path) {
+        Access::NeedsPassphrase => db.lock(),
+        access => match db.switch_to(db_path, access.key()) {
+            // The keyring has an outdated passphrase; ask for the current one
+            Err(AppError::Locked) => db.lock(),
+            result => result,
+        },
+    }
+}
+
+// ── Keyring ────────────────────────────────────────────
+
+fn keyring_entry(profile_id: &str) -> AppResult<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, profile_id)
+        .map_err(|e| AppError::Database(format!("Keyring unavailable: {}", e)))
+}
+
+pub fn stored_passphrase(profile_id: &str) -> Option<String> {
+    match keyring_entry(profile_id).map(|entry| entry.get_password()) {
+        Ok(Ok(key)) => Some(key),
+        Ok(Err(keyring::Error::NoEntry)) => None,
+        Ok(Err(e)) => {
+            log::warn!("Failed to read passphrase from keyring: {}", e);
+            None
+        }
+        Err(e) => {
+            log::warn!("{}", e);
+            None
+        }
+    }
+}
+
+pub fn store_passphrase(profile_id: &str, passphrase: &str) -> AppResult<()> {
+    keyring_entry(profile_id)?
+        .set_password(passphrase)
+        .map_err(|e| AppError::Database(format!("Failed to save passphrase: {}", e)))
+}
+
+pub fn forget_passphrase(profile_id: &str) -> AppResult<()> {
+    match keyring_entry(profile_id)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(AppError::Database(format!(
+            "Failed to remove passphrase: {}",
+            e
+        ))),
+    }
+}
+
+// ── Conversion ─────────────────────────────────────────
+
+/// Rewrite the database at `db_path` with `to_key` (`None` for plain SQLite). The file
+/// must not be open elsewhere; on error the original is left untouched.
+fn convert(db_path: &Path, from_key: Option<&str>, to_key: Option<&str>) -> AppResult<()> {
+    let tmp = with_suffix(db_path, ".convert");
+    let _ = std::fs::remove_file(&tmp);
+
+    let export = || -> AppResult<()> {
+        let conn = Connection::open(db_path)?;
+        if let Some(key) = from_key {
+            conn.pragma_update(None, "key", key)?;
+        }
+        conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |_| Ok(()))
+            .map_err(|_| AppError::ParseError("Wrong passphrase".to_string()))?;
+        // Fold the WAL into the main file so the export sees every committed row
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        conn.execute(
+            "ATTACH DATABASE ?1 AS converted KEY ?2",
+            params![tmp.to_string_lossy(), to_key.unwrap_or("")],
+        )?;
+        conn.query_row("SELECT sqlcipher_export('converted')", [], |_| Ok(()))?;
+        conn.execute_batch("DETACH DATABASE converted")?;
+        Ok(())
+    };
+    if let Err(e) = export() {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e);
+    }
+
+    for suffix in ["-wal", "-shm"] {
+        let _ = std::fs::remove_file(with_suffix(db_path, suffix));
+    }
+    std::fs::rename(&tmp, db_path)
+        .map_err(|e| AppError::Database(format!("Failed to replace database: {}", e)))
+}
+
+pub fn encrypt(db_path: &Path, passphrase: &str) -> AppResult<()> {
+    check_passphrase(passphrase)?;
+    if is_encrypted(db_path) {
+        return Err(AppError::ParseError(
+            "Database is already encrypted".to_string(),
+        ));
+    }
+    convert(db_path, None, Some(passphrase))
+}
+
+pub fn decrypt(db_path: &Path, passphrase: &str) -> AppResult<()> {
+    if !is_encrypted(db_path) {
+        return Err(AppError::ParseError(
+            "Database is not encrypted".to_string(),
+        ));
+    }
+    convert(db_path, Some(passphrase), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_plain_to_encrypted_and_back() {
+        let dir = std::env::temp_dir().join(format!("bh-encryption-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "PRAGMA journal_mode=WAL; CREATE TABLE notes (text TEXT);
+                 INSERT INTO notes VALUES ('secret');",
+            )
+            .unwrap();
+        }
+        assert!(!is_encrypted(&path));
+        assert!(encrypt(&path, "short").is_err());
+
+        encrypt(&path, "correct horse").unwrap();
+        assert!(is_encrypted(&path));
+        assert!(!std::fs::read(&path)
+            .unwrap()
+            .windows(6)
+            .any(|w| w == b"secret"));
+        assert!(decrypt(&path, "wrong passphrase").is_err());
+
+        decrypt(&path, "correct horse").unwrap();
+        assert!(!is_encrypted(&path));
+        let text: String = Connection::open(&path)
+            .unwrap()
+            .query_row("SELECT text FROM notes", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(text, "secret");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,455 @@
+//! Shops and categories as rows of their own, referenced by `favorites` and `cached_items`.
+//!
+//! Booth identifies a shop by its subdomain (`https://<shop>.booth.pm`); the display name
+//! can change at any time. Items store the name they were fetched with, and the name is
+//! resolved to a shop row here: by subdomain when the item URL reveals it, otherwise to a
+//! row known by name alone. Different shops can share a display name, so an item moves
+//! from a name-only row to a subdomain shop only once its own URL shows that subdomain.
+//!
+//! Saving a favorite resolves its shop and category the same way, so favorites are written
+//! here too; migrations that favorite cached items use the same path as the commands.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Deserialize;
+
+use crate::booth::models::{has_adult_marker, BoothItem};
+use crate::booth::shop_parser::normalize_shop;
+use crate::error::{AppError, AppResult};
+
+fn clean(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// Subdomain of the shop an item URL (`https://<shop>.booth.pm/items/<id>`) belongs to.
+pub fn subdomain_from_item_url(url: &str) -> Option<String> {
+    url.contains(".booth.pm/")
+        .then(|| normalize_shop(url).ok())
+        .flatten()
+}
+
+/// Shop subdomain of an item, from the item JSON or else its URL.
+pub fn item_shop_subdomain(item: &BoothItem) -> Option<String> {
+    item.shop_subdomain
+        .clone()
+        .or_else(|| subdomain_from_item_url(&item.url))
+}
+
+/// Id of the shop with this subdomain and/or name, creating or updating the row as needed.
+/// Without a subdomain only shops known by name alone match, since two shops can share a
+/// display name.
+pub fn ensure_shop(
+    conn: &Connection,
+    name: Option<&str>,
+    subdomain: Option<&str>,
+) -> AppResult<Option<i64>> {
+    let name = clean(name);
+    let Some(subdomain) = clean(subdomain) else {
+        let Some(name) = name else {
+            return Ok(None);
+        };
+        let existing: Option<i64> = conn
+            .query_row(
+                "SELECT id FROM shops WHERE name = ?1 AND subdomain IS NULL ORDER BY id LIMIT 1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if existing.is_some() {
+            return Ok(existing);
+        }
+        conn.execute("INSERT INTO shops (name) VALUES (?1)", params![name])?;
+        return Ok(Some(conn.last_insert_rowid()));
+    };
+
+    let by_subdomain: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM shops WHERE subdomain = ?1",
+            params![subdomain],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let id = match by_subdomain {
+        Some(id) => {
+            if let Some(name) = name {
+                conn.execute(
+                    "UPDATE shops SET name = ?1 WHERE id = ?2 AND name IS NOT ?1",
+                    params![name, id],
+                )?;
+            }
+            id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO shops (subdomain, name) VALUES (?1, ?2)",
+                params![subdomain, name],
+            )?;
+            conn.last_insert_rowid()
+        }
+    };
+    if let Some(name) = name {
+        adopt_proven_items(conn, id, name, subdomain)?;
+    }
+    Ok(Some(id))
+}
+
+/// Move the items of name-only shops called `name` whose URL shows `subdomain` over to
+/// shop `id`, and drop name-only rows left without items. Items whose URL does not show
+/// the subdomain stay put; they may belong to another shop with the same name.
+fn adopt_proven_items(conn: &Connection, id: i64, name: &str, subdomain: &str) -> AppResult<()> {
+    let candidates: Vec<(i64, i64, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT c.id, c.shop_id, c.url FROM cached_items c
+             JOIN shops s ON s.id = c.shop_id
+             WHERE s.name = ?1 AND s.subdomain IS NULL",
+        )?;
+        let rows = stmt
+            .query_map(params![name], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+    for (item_id, name_only, url) in candidates {
+        if subdomain_from_item_url(&url).as_deref() != Some(subdomain) {
+            continue;
+        }
+        conn.execute(
+            "UPDATE cached_items SET shop_id = ?1 WHERE id = ?2",
+            params![id, item_id],
+        )?;
+        conn.execute(
+            "UPDATE favorites SET shop_id = ?1 WHERE item_id = ?2 AND shop_id = ?3",
+            params![id, item_id, name_only],
+        )?;
+    }
+    conn.execute(
+        "DELETE FROM shops
+         WHERE name = ?1 AND subdomain IS NULL
+           AND NOT EXISTS (SELECT 1 FROM favorites f WHERE f.shop_id = shops.id)
+           AND NOT EXISTS (SELECT 1 FROM cached_items c WHERE c.shop_id = shops.id)",
+        params![name],
+    )?;
+    Ok(())
+}
+
+pub fn ensure_category(conn: &Connection, name: Option<&str>) -> AppResult<Option<i64>> {
+    let Some(name) = clean(name) else {
+        return Ok(None);
+    };
+    conn.execute(
+        "INSERT OR IGNORE INTO categories (name) VALUES (?1)",
+        params![name],
+    )?;
+    Ok(Some(conn.query_row(
+        "SELECT id FROM categories WHERE name = ?1",
+        params![name],
+        |row| row.get(0),
+    )?))
+}
+
+/// Link favorites and cached items that only have the shop and category as text.
+/// Cached items go first so their URLs can supply shop subdomains.
+pub fn backfill(conn: &Connection) -> AppResult<()> {
+    let cached: Vec<(i64, Option<String>, Option<String>, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT id, shop_name, category_name, url FROM cached_items
+             WHERE (shop_id IS NULL AND NULLIF(shop_name, '') IS NOT NULL)
+                OR (category_id IS NULL AND NULLIF(category_name, '') IS NOT NULL)",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+    for (id, shop_name, category_name, url) in cached {
+        let subdomain = subdomain_from_item_url(&url);
+        let shop_id = ensure_shop(conn, shop_name.as_deref(), subdomain.as_deref())?;
+        let category_id = ensure_category(conn, category_name.as_deref())?;
+        conn.execute(
+            "UPDATE cached_items SET shop_id = ?1, category_id = ?2 WHERE id = ?3",
+            params![shop_id, category_id, id],
+        )?;
+    }
+
+    let favorites: Vec<(i64, Option<String>, Option<String>)> = {
+        let mut stmt = conn.prepare(
+            "SELECT item_id, shop_name, category_name FROM favorites
+             WHERE (shop_id IS NULL AND NULLIF(shop_name, '') IS NOT NULL)
+                OR (category_id IS NULL AND NULLIF(category_name, '') IS NOT NULL)",
+        )?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+    for (item_id, shop_name, category_name) in favorites {
+        // The cached copy of the item may already know the exact shop
+        let cached_shop: Option<i64> = conn
+            .query_row(
+                "SELECT shop_id FROM cached_items WHERE id = ?1",
+                params![item_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+        let shop_id = match cached_shop {
+            Some(id) => Some(id),
+            None => ensure_shop(conn, shop_name.as_deref(), None)?,
+        };
+        let category_id = ensure_category(conn, category_name.as_deref())?;
+        conn.execute(
+            "UPDATE favorites SET shop_id = ?1, category_id = ?2 WHERE item_id = ?3",
+            params![shop_id, category_id, item_id],
+        )?;
+    }
+    Ok(())
+}
+
+// ── Favorites ──────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct AddFavoriteParams {
+    pub item_id: i64,
+    pub name: String,
+    pub price: i64,
+    #[serde(default)]
+    pub price_max: Option<i64>,
+    pub thumbnail_url: Option<String>,
+    pub category_name: Option<String>,
+    pub shop_name: Option<String>,
+    #[serde(default)]
+    pub is_adult: bool,
+}
+
+/// JSON columns written by older versions may be missing or malformed; they read as empty.
+fn json_list<T: serde::de::DeserializeOwned>(value: Option<String>) -> Vec<T> {
+    value
+        .and_then(|v| serde_json::from_str(&v).ok())
+        .unwrap_or_default()
+}
+
+/// A cached item as [`BoothItem`], whatever version of the app cached it.
+pub fn get_cached(conn: &Connection, item_id: i64) -> AppResult<Option<BoothItem>> {
+    let item = conn
+        .query_row(
+            "SELECT c.id, c.name, c.description, c.price, c.category_name, c.shop_name, c.url,
+                    c.images_json, c.tags_json, c.wish_count, c.variations_json, c.is_adult,
+                    c.published_at, c.updated_at, s.subdomain, s.thumbnail_url, c.event_name,
+                    c.is_sold_out, c.is_end_of_sale
+             FROM cached_items c LEFT JOIN shops s ON s.id = c.shop_id
+             WHERE c.id = ?1",
+            params![item_id],
+            |row| {
+                Ok(BoothItem {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    description: row.get(2)?,
+                    price: row.get(3)?,
+                    category_name: row.get(4)?,
+                    shop_name: row.get(5)?,
+                    url: row.get(6)?,
+                    images: json_list(row.get(7)?),
+                    tags: json_list(row.get(8)?),
+                    wish_lists_count: row.get(9)?,
+                    variations: json_list(row.get(10)?),
+                    is_adult: row.get(11)?,
+                    published_at: row.get(12)?,
+                    updated_at: row.get(13)?,
+                    shop_subdomain: row.get(14)?,
+                    shop_thumbnail_url: row.get(15)?,
+                    event_name: row.get(16)?,
+                    is_sold_out: row.get(17)?,
+                    is_end_of_sale: row.get(18)?,
+                    blurred: false,
+                })
+            },
+        )
+        .optional()?;
+    Ok(item)
+}
+
+/// Returns whether the item became a favorite for the first time; a restore is not a new
+/// find, so only then does it convert a search.
+pub fn insert_favorite(conn: &Connection, params: &AddFavoriteParams) -> AppResult<bool> {
+    // Prefer the shop the cached copy was linked to; its URL told us the exact shop
+    let (cached_shop, is_adult, published_at): (Option<i64>, bool, Option<String>) = conn
+        .query_row(
+            "SELECT shop_id, is_adult, published_at FROM cached_items WHERE id = ?1",
+            params![params.item_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?
+        .unwrap_or_default();
+    let shop_id = match cached_shop {
+        Some(id) => Some(id),
+        None => ensure_shop(conn, params.shop_name.as_deref(), None)?,
+    };
+    let category_id = ensure_category(conn, params.category_name.as_deref())?;
+    let is_adult = is_adult || params.is_adult || has_adult_marker(&params.name);
+    let known: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM favorites WHERE item_id = ?1)",
+        params![params.item_id],
+        |row| row.get(0),
+    )?;
+    // Re-adding a trashed favorite restores it along with its tags and memberships
+    let added = conn.execute(
+        "INSERT INTO favorites
+         (item_id, name, price, thumbnail_url, category_name, shop_name, added_at, shop_id,
+          category_id, price_max, is_adult, published_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now'), ?7, ?8, ?9, ?10, ?11)
+         ON CONFLICT(item_id) DO UPDATE SET deleted_at = NULL
+         WHERE favorites.deleted_at IS NOT NULL",
+        params![
+            params.item_id,
+            params.name,
+            params.price,
+            params.thumbnail_url,
+            params.category_name,
+            params.shop_name,
+            shop_id,
+            category_id,
+            params.price_max.filter(|max| *max > params.price),
+            is_adult,
+            published_at,
+        ],
+    )?;
+    Ok(added > 0 && !known)
+}
+
+/// Favorite a cached item, or restore it from the trash. Returns whether it is a new
+/// favorite; fails with `NotFound` when the item was never cached.
+pub fn favorite_cached(conn: &Connection, item_id: i64) -> AppResult<bool> {
+    let item = get_cached(conn, item_id)?
+        .ok_or_else(|| AppError::NotFound(format!("Favorite {}", item_id)))?;
+    insert_favorite(
+        conn,
+        &AddFavoriteParams {
+            item_id,
+            price: item.min_price(),
+            price_max: Some(item.max_price()),
+            thumbnail_url: item.images.first().cloned(),
+            is_adult: item.looks_adult(),
+            name: item.name,
+            category_name: item.category_name,
+            shop_name: item.shop_name,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE shops (id INTEGER PRIMARY KEY AUTOINCREMENT, subdomain TEXT UNIQUE,
+                                 name TEXT, followed INTEGER NOT NULL DEFAULT 0);
+             CREATE TABLE categories (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL UNIQUE);
+             CREATE TABLE favorites (item_id INTEGER, shop_name TEXT, category_name TEXT,
+                                     shop_id INTEGER, category_id INTEGER);
+             CREATE TABLE cached_items (id INTEGER, shop_name TEXT, category_name TEXT, url TEXT,
+                                        shop_id INTEGER, category_id INTEGER);",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn extracts_subdomain_from_item_urls() {
+        assert_eq!(
+            subdomain_from_item_url("https://cozy.booth.pm/items/12").as_deref(),
+            Some("cozy")
+        );
+        assert_eq!(
+            subdomain_from_item_url("https://booth.pm/ja/items/12"),
+            None
+        );
+    }
+
+    #[test]
+    fn renamed_shop_keeps_its_row() {
+        let conn = conn();
+        let linked = ensure_shop(&conn, Some("Cozy"), Some("cozy")).unwrap();
+        let renamed = ensure_shop(&conn, Some("Cozy Studio"), Some("cozy")).unwrap();
+        assert_eq!(renamed, linked);
+    }
+
+    #[test]
+    fn shared_names_do_not_merge_shops() {
+        let conn = conn();
+        let by_name = ensure_shop(&conn, Some("Cozy"), None).unwrap();
+        conn.execute(
+            "INSERT INTO favorites VALUES (1, 'Cozy', NULL, ?1, NULL)",
+            params![by_name],
+        )
+        .unwrap();
+        let cozy = ensure_shop(&conn, Some("Cozy"), Some("cozy")).unwrap();
+        let other = ensure_shop(&conn, Some("Cozy"), Some("cozy-atelier")).unwrap();
+        assert_ne!(cozy, by_name);
+        assert_ne!(cozy, other);
+        assert_eq!(ensure_shop(&conn, Some("Cozy"), None).unwrap(), by_name);
+    }
+
+    #[test]
+    fn items_move_once_their_url_shows_the_shop() {
+        let conn = conn();
+        let by_name = ensure_shop(&conn, Some("Cozy"), None).unwrap();
+        conn.execute_batch(&format!(
+            "INSERT INTO favorites VALUES (1, 'Cozy', NULL, {id}, NULL);
+             INSERT INTO favorites VALUES (2, 'Cozy', NULL, {id}, NULL);
+             INSERT INTO cached_items VALUES (1, 'Cozy', NULL, 'https://cozy.booth.pm/items/1', {id}, NULL);",
+            id = by_name.unwrap()
+        ))
+        .unwrap();
+        let cozy = ensure_shop(&conn, Some("Cozy"), Some("cozy")).unwrap();
+        let shop_of = |item: i64| -> Option<i64> {
+            conn.query_row(
+                "SELECT shop_id FROM favorites WHERE item_id = ?1",
+                params![item],
+                |r| r.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!((shop_of(1), shop_of(2)), (cozy, by_name));
+
+        // The name-only row goes away once none of its items are left
+        conn.execute("DELETE FROM favorites WHERE item_id = 2", [])
+            .unwrap();
+        ensure_shop(&conn, Some("Cozy"), Some("cozy")).unwrap();
+        let shops: i64 = conn
+            .query_row("SELECT COUNT(*) FROM shops", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(shops, 1);
+    }
+
+    #[test]
+    fn backfill_links_by_url_and_keeps_unproven_names_apart() {
+        let conn = conn();
+        conn.execute_batch(
+            "INSERT INTO favorites VALUES (1, 'Cozy', '3D衣装', NULL, NULL);
+             INSERT INTO favorites VALUES (2, 'Cozy', NULL, NULL, NULL);
+             INSERT INTO cached_items VALUES (2, 'Cozy', '3D衣装', 'https://cozy.booth.pm/items/2', NULL, NULL);",
+        )
+        .unwrap();
+        backfill(&conn).unwrap();
+        let shops: i64 = conn
+            .query_row("SELECT COUNT(*) FROM shops", [], |r| r.get(0))
+            .unwrap();
+        let linked: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM favorites f JOIN shops s ON s.id = f.shop_id
+                 WHERE s.subdomain = 'cozy'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!((shops, linked), (2, 1));
+        let categories: i64 = conn
+            .query_row("SELECT COUNT(*) FROM categories", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(categories, 1);
+    }
+}
@@ -10,6 +10,15 @@ pub enum AppError {
 
     #[error("Database error: {0}")]
     Database(String),
+
+    #[error("Network error: {0}")]
+    Network(String),
+
+    #[error("{0} is already running")]
+    Busy(String),
+
+    #[error("Database is encrypted; enter the passphrase to open it")]
+    Locked,
 }
 
 impl From<rusqlite::Error> for AppError {
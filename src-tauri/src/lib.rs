@@ -1,13 +1,24 @@
+mod assets;
 mod booth;
 mod commands;
+mod currency;
 mod database;
+mod dates;
+mod encryption;
+mod entities;
 mod error;
+mod profiles;
+mod settings;
+mod sync;
 
 use tauri::{Emitter, Manager};
 use tauri_plugin_updater::UpdaterExt;
 
+use commands::refresh::RefreshJob;
+use commands::shops::ShopCheckJob;
 use commands::updater::{PendingUpdate, UpdateInfo};
 use database::AppDatabase;
+use profiles::ProfileManager;
 
 async fn check_for_update(app: tauri::AppHandle) {
     let updater = match app.updater() {
@@ -49,10 +60,14 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_http::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_process::init())
         .manage(PendingUpdate::default())
+        .manage(RefreshJob::default())
+        .manage(ShopCheckJob::default())
         .invoke_handler(tauri::generate_handler![
             commands::db::cache_items,
+            commands::db::get_cached_item,
             commands::db::save_search_history,
             commands::db::get_favorites,
             commands::db::add_favorite,
@@ -60,6 +75,27 @@ pub fn run() {
             commands::db::get_popular_avatars,
             commands::db::check_avatars_need_update,
             commands::db::update_popular_avatar,
+            commands::encryption::get_database_status,
+            commands::encryption::unlock_database,
+            commands::encryption::encrypt_database,
+            commands::encryption::decrypt_database,
+            commands::encryption::forget_database_passphrase,
+            commands::alerts::get_price_alert_rules,
+            commands::alerts::add_price_alert_rule,
+            commands::alerts::set_price_alert_rule_enabled,
+            commands::alerts::delete_price_alert_rule,
+            commands::alerts::get_price_alerts,
+            commands::alerts::mark_price_alerts_read,
+            commands::alerts::clear_price_alerts,
+            commands::alerts::set_item_alerts_muted,
+            commands::alerts::get_muted_alert_items,
+            commands::budget::get_budget_history,
+            commands::budget::get_monthly_budget,
+            commands::budget::set_monthly_budget,
+            commands::budget::delete_monthly_budget,
+            commands::budget::set_purchase_priority,
+            commands::budget::get_budget_progress,
+            commands::budget::plan_purchases,
             commands::collections::get_collections,
             commands::collections::create_collection,
             commands::collections::rename_collection,
@@ -74,17 +110,88 @@ pub fn run() {
             commands::collections::get_all_user_tags,
             commands::collections::get_all_item_tags_batch,
             commands::collections::get_all_item_collections_batch,
+            commands::content_policy::get_content_policy,
+            commands::content_policy::set_content_policy,
+            commands::content_policy::apply_content_policy,
+            commands::currency::get_exchange_rates,
+            commands::currency::set_exchange_rate,
+            commands::currency::delete_exchange_rate,
+            commands::currency::import_exchange_rates,
+            commands::currency::fetch_exchange_rates,
+            commands::currency::convert_prices,
+            commands::currency::get_statistics_in_currency,
+            commands::local_files::get_local_scan_dirs,
+            commands::local_files::scan_local_files,
+            commands::local_files::get_local_files,
+            commands::local_files::get_all_local_files_batch,
+            commands::local_files::link_local_file,
+            commands::local_files::inspect_local_packages,
+            commands::local_files::get_package_inspections,
+            commands::local_files::get_package_asset_paths,
+            commands::maintenance::run_maintenance,
+            commands::owned::get_owned_items,
+            commands::owned::get_owned_item_ids,
+            commands::owned::add_owned_item,
+            commands::owned::update_owned_item,
+            commands::owned::remove_owned_item,
+            commands::profiles::list_profiles,
+            commands::profiles::get_active_profile,
+            commands::profiles::create_profile,
+            commands::profiles::rename_profile,
+            commands::profiles::delete_profile,
+            commands::profiles::switch_profile,
+            commands::profiles::copy_profile_data,
+            commands::purchase_import::preview_purchase_import,
+            commands::purchase_import::import_purchase_history,
+            commands::refresh::refresh_favorites,
+            commands::refresh::cancel_refresh_favorites,
+            commands::refresh::get_favorite_changes,
+            commands::refresh::check_item_availability,
+            commands::search_analytics::get_search_trends,
+            commands::search_analytics::get_zero_result_keywords,
+            commands::search_analytics::get_keyword_conversion,
+            commands::search_history::get_recent_searches,
+            commands::search_history::delete_search_keyword,
+            commands::search_history::delete_search_history_matching,
+            commands::search_history::clear_search_history,
+            commands::search_history::get_search_history_paused,
+            commands::search_history::set_search_history_paused,
+            commands::settings::get_settings,
+            commands::settings::update_settings,
+            commands::shops::get_shops,
+            commands::shops::follow_shop,
+            commands::shops::unfollow_shop,
+            commands::shops::check_followed_shops,
+            commands::shops::get_shop_items,
+            commands::shops::get_new_shop_items,
+            commands::shops::mark_shop_visited,
+            commands::shops::get_shop_favorites,
+            commands::shops::get_shop_favorites_batch,
             commands::stats::get_all_statistics,
+            commands::sync::get_sync_status,
+            commands::sync::set_sync_folder,
+            commands::sync::sync_library,
             commands::translation::get_cached_translation,
             commands::translation::save_cached_translation,
+            commands::trash::get_trash,
+            commands::trash::restore_from_trash,
+            commands::trash::empty_trash,
+            commands::trash::get_trash_retention_days,
+            commands::trash::set_trash_retention_days,
             commands::updater::install_update,
         ])
         .setup(|app| {
-            // Initialize database
+            // Open the database of the last used profile
             let app_data_dir = app.path().app_data_dir()?;
-            let db = AppDatabase::initialize(app_data_dir)
+            let profiles = ProfileManager::load(app_data_dir)
+                .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
+            // An encrypted database stays locked until the passphrase is entered
+            let profile = profiles.active()?;
+            let db = AppDatabase::locked()?;
+            encryption::open_profile(&db, &profile.id, &profiles.db_path(&profile.id))
                 .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
             app.manage(db);
+            app.manage(profiles);
 
             #[cfg(desktop)]
             {
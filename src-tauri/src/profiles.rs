@@ -0,0 +1,506 @@
+//! Profiles: separate databases for people sharing one installation.
+//!
+//! The profile list and the active profile live in `profiles.json` in the app data dir,
+//! outside any database. The `default` profile keeps using `boothhunter.db`, so data from
+//! before profiles existed stays where it was; other profiles get `profiles/<id>.db`.
+//! Settings are stored in each database and so are per profile as well.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::database::{AppDatabase, DEFAULT_DB_FILE};
+use crate::encryption;
+use crate::entities;
+use crate::error::{AppError, AppResult};
+use crate::sync;
+
+pub const DEFAULT_PROFILE_ID: &str = "default";
+const PROFILES_FILE: &str = "profiles.json";
+const MAX_NAME_LEN: usize = 40;
+
+// ── Types ──────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    /// Stable id, also the database file name
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileSummary {
+    pub id: String,
+    pub name: String,
+    pub active: bool,
+    /// Encrypted with SQLCipher
+    pub encrypted: bool,
+    /// Size of the database file, excluding the WAL
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CopySummary {
+    pub favorites: usize,
+    pub tags: usize,
+    pub collections: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Registry {
+    active: String,
+    profiles: Vec<Profile>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Registry {
+            active: DEFAULT_PROFILE_ID.to_string(),
+            profiles: vec![Profile {
+                id: DEFAULT_PROFILE_ID.to_string(),
+                name: "Default".to_string(),
+            }],
+        }
+    }
+}
+
+impl Registry {
+    fn find(&self, id: &str) -> AppResult<&Profile> {
+        self.profiles
+            .iter()
+            .find(|p| p.id == id)
+            .ok_or_else(|| AppError::NotFound(format!("profile {}", id)))
+    }
+
+    fn find_mut(&mut self, id: &str) -> AppResult<&mut Profile> {
+        self.profiles
+            .iter_mut()
+            .find(|p| p.id == id)
+            .ok_or_else(|| AppError::NotFound(format!("profile {}", id)))
+    }
+}
+
+// ── Helpers ────────────────────────────────────────────
+
+fn check_name(registry: &Registry, name: &str, except: Option<&str>) -> AppResult<String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(AppError::ParseError(format!(
+            "Profile name must be 1 to {} characters",
+            MAX_NAME_LEN
+        )));
+    }
+    let taken = registry
+        .profiles
+        .iter()
+        .any(|p| Some(p.id.as_str()) != except && p.name.to_lowercase() == name.to_lowercase());
+    if taken {
+        return Err(AppError::ParseError(format!(
+            "A profile named '{}' already exists",
+            name
+        )));
+    }
+    Ok(name.to_string())
+}
+
+/// File-safe id derived from the name, e.g. "Mina's Room" -> `minas-room`.
+fn profile_id(registry: &Registry, name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if (c.is_whitespace() || c == '-' || c == '_')
+            && !slug.is_empty()
+            && !slug.ends_with('-')
+        {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    let base = if slug.is_empty() { "profile" } else { slug };
+    let mut id = base.to_string();
+    let mut n = 2;
+    while registry.profiles.iter().any(|p| p.id == id) {
+        id = format!("{}-{}", base, n);
+        n += 1;
+    }
+    id
+}
+
+/// Registry listing every database under `profiles/`, named after its id. Used when
+/// `profiles.json` cannot be read, so no profile is left out of the next save.
+fn rebuild_registry(data_dir: &Path) -> Registry {
+    let mut registry = Registry::default();
+    let Ok(entries) = std::fs::read_dir(data_dir.join("profiles")) else {
+        return registry;
+    };
+    let mut ids: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "db"))
+        .filter_map(|p| p.file_stem().and_then(|s| s.to_str()).map(str::to_string))
+        .filter(|id| id != DEFAULT_PROFILE_ID)
+        .collect();
+    ids.sort();
+    registry.profiles.extend(ids.into_iter().map(|id| Profile {
+        name: id.clone(),
+        id,
+    }));
+    registry
+}
+
+/// Column names of a table except the given ones, joined for an INSERT ... SELECT.
+fn columns_except(conn: &Connection, table: &str, except: &[&str]) -> AppResult<String> {
+    let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1, 'main')")?;
+    let names = stmt
+        .query_map(params![table], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(names
+        .into_iter()
+        .filter(|n| !except.contains(&n.as_str()))
+        .collect::<Vec<_>>()
+        .join(", "))
+}
+
+// ── Profile manager ────────────────────────────────────
+
+pub struct ProfileManager {
+    data_dir: PathBuf,
+    registry: Mutex<Registry>,
+}
+
+impl ProfileManager {
+    /// Read `profiles.json`. Without one the list is rebuilt from the databases on disk,
+    /// which is just the default profile on a fresh install; an unreadable one is kept as
+    /// `profiles.json.bak` first.
+    pub fn load(data_dir: PathBuf) -> AppResult<Self> {
+        let path = data_dir.join(PROFILES_FILE);
+        let mut registry = match std::fs::read_to_string(&path) {
+            Ok(json) => match serde_json::from_str::<Registry>(&json) {
+                Ok(registry) => registry,
+                Err(e) => {
+                    log::warn!("Rebuilding unreadable {}: {}", PROFILES_FILE, e);
+                    let backup = data_dir.join(format!("{}.bak", PROFILES_FILE));
+                    std::fs::copy(&path, &backup).map_err(|e| {
+                        AppError::Database(format!("Failed to back up profiles: {}", e))
+                    })?;
+                    rebuild_registry(&data_dir)
+                }
+            },
+            Err(_) => rebuild_registry(&data_dir),
+        };
+        if registry.profiles.is_empty() {
+            registry = Registry::default();
+        }
+        if registry.find(&registry.active).is_err() {
+            registry.active = registry.profiles[0].id.clone();
+        }
+        Ok(ProfileManager {
+            data_dir,
+            registry: Mutex::new(registry),
+        })
+    }
+
+    fn registry(&self) -> AppResult<MutexGuard<'_, Registry>> {
+        self.registry
+            .lock()
+            .map_err(|e| AppError::Database(format!("Lock poisoned: {}", e)))
+    }
+
+    /// Write the registry through a temp file so a crash never leaves it half written.
+    fn save(&self, registry: &Registry) -> AppResult<()> {
+        let write = || -> std::io::Result<()> {
+            std::fs::create_dir_all(&self.data_dir)?;
+            let tmp = self.data_dir.join(format!("{}.tmp", PROFILES_FILE));
+            let json = serde_json::to_string_pretty(registry)?;
+            std::fs::write(&tmp, json)?;
+            std::fs::rename(tmp, self.data_dir.join(PROFILES_FILE))
+        };
+        write().map_err(|e| AppError::Database(format!("Failed to save profiles: {}", e)))
+    }
+
+    pub fn db_path(&self, id: &str) -> PathBuf {
+        if id == DEFAULT_PROFILE_ID {
+            self.data_dir.join(DEFAULT_DB_FILE)
+        } else {
+            self.data_dir.join("profiles").join(format!("{}.db", id))
+        }
+    }
+
+    pub fn active(&self) -> AppResult<Profile> {
+        let registry = self.registry()?;
+        registry.find(&registry.active).cloned()
+    }
+
+    pub fn get(&self, id: &str) -> AppResult<Profile> {
+        self.registry()?.find(id).cloned()
+    }
+
+    pub fn list(&self) -> AppResult<Vec<ProfileSummary>> {
+        let registry = self.registry()?;
+        Ok(registry
+            .profiles
+            .iter()
+            .map(|p| ProfileSummary {
+                id: p.id.clone(),
+                name: p.name.clone(),
+                active: p.id == registry.active,
+                encrypted: encryption::is_encrypted(&self.db_path(&p.id)),
+                size_bytes: std::fs::metadata(self.db_path(&p.id))
+                    .map(|m| m.len())
+                    .unwrap_or(0),
+            })
+            .collect())
+    }
+
+    /// Add a profile and create its (empty, migrated) database.
+    pub fn create(&self, name: &str) -> AppResult<Profile> {
+        let mut registry = self.registry()?;
+        let name = check_name(&registry, name, None)?;
+        let profile = Profile {
+            id: profile_id(&registry, &name),
+            name,
+        };
+        AppDatabase::open_connection(&self.db_path(&profile.id), None)?;
+        registry.profiles.push(profile.clone());
+        self.save(&registry)?;
+        Ok(profile)
+    }
+
+    pub fn rename(&self, id: &str, name: &str) -> AppResult<Profile> {
+        let mut registry = self.registry()?;
+        let name = check_name(&registry, name, Some(id))?;
+        let profile = registry.find_mut(id)?;
+        profile.name = name;
+        let profile = profile.clone();
+        self.save(&registry)?;
+        Ok(profile)
+    }
+
+    /// Remove a profile and its database. The active profile cannot be deleted.
+    pub fn delete(&self, id: &str) -> AppResult<()> {
+        let mut registry = self.registry()?;
+        registry.find(id)?;
+        if registry.active == id {
+            return Err(AppError::ParseError(
+                "Switch to another profile before deleting this one".to_string(),
+            ));
+        }
+        registry.profiles.retain(|p| p.id != id);
+        self.save(&registry)?;
+        if let Err(e) = encryption::forget_passphrase(id) {
+            log::warn!("{}", e);
+        }
+
+        let db_path = self.db_path(id);
+        for suffix in ["", "-wal", "-shm"] {
+            let mut file = db_path.clone().into_os_string();
+            file.push(suffix);
+            if let Err(e) = std::fs::remove_file(&file) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!("Failed to remove {:?}: {}", file, e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Remember `id` as the profile to open on the next start.
+    pub fn set_active(&self, id: &str) -> AppResult<Profile> {
+        let mut registry = self.registry()?;
+        let profile = registry.find(id)?.clone();
+        registry.active = profile.id.clone();
+        self.save(&registry)?;
+        Ok(profile)
+    }
+}
+
+// ── Copying ────────────────────────────────────────────
+
+/// Copy favorites (all, or the given item ids) with their cached details, tags and
+/// collection memberships from one profile database to another. Favorites the target
+/// already has are left as they are; collections are matched by name. Shop and category
+/// links are rebuilt in the target, since their ids differ between databases.
+/// `source_key` and `target_key` are the passphrases of encrypted databases.
+pub fn copy_favorites(
+    source: &Path,
+    source_key: Option<&str>,
+    target: &Path,
+    target_key: Option<&str>,
+    item_ids: Option<&[i64]>,
+) -> AppResult<CopySummary> {
+    drop(AppDatabase::open_connection(target, target_key)?);
+    let mut conn = AppDatabase::open_connection(source, source_key)?;
+    // Without KEY an attached database would get the source's key
+    conn.execute(
+        "ATTACH DATABASE ?1 AS dst KEY ?2",
+        params![target.to_string_lossy(), target_key.unwrap_or("")],
+    )?;
+    let copied = copy_attached(&mut conn, item_ids);
+    conn.execute_batch("DETACH DATABASE dst")?;
+    let summary = copied?;
+
+    let target = AppDatabase::open_connection(target, target_key)?;
+    entities::backfill(&target)?;
+    Ok(summary)
+}
+
+fn copy_attached(conn: &mut Connection, item_ids: Option<&[i64]>) -> AppResult<CopySummary> {
+    let tx = conn.transaction()?;
+    tx.execute_batch("CREATE TEMP TABLE copy_ids (item_id INTEGER PRIMARY KEY)")?;
+    match item_ids {
+        Some(ids) => {
+            for id in ids {
+                tx.execute(
+                    "INSERT OR IGNORE INTO copy_ids (item_id) SELECT item_id FROM main.favorites
+                     WHERE item_id = ?1 AND deleted_at IS NULL",
+                    params![id],
+                )?;
+            }
+        }
+        None => {
+            tx.execute(
+                "INSERT INTO copy_ids (item_id)
+                 SELECT item_id FROM main.favorites WHERE deleted_at IS NULL",
+                [],
+            )?;
+        }
+    }
+
+    let mut summary = CopySummary::default();
+    let cols = columns_except(&tx, "favorites", &["id", "shop_id", "category_id"])?;
+    summary.favorites = tx.execute(
+        &format!(
+            "INSERT OR IGNORE INTO dst.favorites ({0}) SELECT {0} FROM main.favorites
+             WHERE item_id IN (SELECT item_id FROM copy_ids)",
+            cols
+        ),
+        [],
+    )?;
+    let cols = columns_except(&tx, "cached_items", &["shop_id", "category_id"])?;
+    tx.execute(
+        &format!(
+            "INSERT OR IGNORE INTO dst.cached_items ({0}) SELECT {0} FROM main.cached_items
+             WHERE id IN (SELECT item_id FROM copy_ids)",
+            cols
+        ),
+        [],
+    )?;
+    summary.tags = tx.execute(
+        "INSERT OR IGNORE INTO dst.item_tags (item_id, tag) SELECT item_id, tag FROM main.item_tags
+         WHERE item_id IN (SELECT item_id FROM copy_ids)",
+        [],
+    )?;
+
+    let collections: Vec<(i64, String, Option<String>, Option<i64>)> = {
+        let mut stmt = tx.prepare(
+            "SELECT id, name, color, sort_order FROM main.collections c
+             WHERE deleted_at IS NULL AND EXISTS (
+                 SELECT 1 FROM main.collection_items ci
+                 WHERE ci.collection_id = c.id AND ci.item_id IN (SELECT item_id FROM copy_ids))",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+    for (id, name, color, sort_order) in collections {
+        let existing: Option<i64> = tx
+            .query_row(
+                "SELECT id FROM dst.collections WHERE name = ?1 AND deleted_at IS NULL
+                 ORDER BY id LIMIT 1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let target_id = match existing {
+            Some(target_id) => target_id,
+            None => {
+                tx.execute(
+                    "INSERT INTO dst.collections (name, color, sort_order, uuid)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![name, color, sort_order, sync::new_uuid()],
+                )?;
+                summary.collections += 1;
+                tx.last_insert_rowid()
+            }
+        };
+        tx.execute(
+            "INSERT OR IGNORE INTO dst.collection_items (collection_id, item_id, added_at)
+             SELECT ?1, item_id, added_at FROM main.collection_items
+             WHERE collection_id = ?2 AND item_id IN (SELECT item_id FROM copy_ids)",
+            params![target_id, id],
+        )?;
+    }
+
+    tx.execute_batch("DROP TABLE temp.copy_ids")?;
+    tx.commit()?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bh-profiles-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn ids_are_file_safe_and_unique() {
+        let mut registry = Registry::default();
+        assert_eq!(profile_id(&registry, "Mina's Room"), "minas-room");
+        assert_eq!(profile_id(&registry, "민아"), "profile");
+        assert_eq!(profile_id(&registry, "Default"), "default-2");
+        registry.profiles.push(Profile {
+            id: "profile".to_string(),
+            name: "x".to_string(),
+        });
+        assert_eq!(profile_id(&registry, "?!"), "profile-2");
+    }
+
+    #[test]
+    fn unreadable_registry_is_backed_up_and_rebuilt() {
+        let dir = temp_dir("broken");
+        std::fs::create_dir_all(dir.join("profiles")).unwrap();
+        std::fs::write(dir.join("profiles").join("mina.db"), b"").unwrap();
+        std::fs::write(dir.join(PROFILES_FILE), b"{\"active\": ").unwrap();
+
+        let manager = ProfileManager::load(dir.clone()).unwrap();
+        let ids: Vec<String> = manager.list().unwrap().into_iter().map(|p| p.id).collect();
+        assert_eq!(
+            ids,
+            vec![DEFAULT_PROFILE_ID.to_string(), "mina".to_string()]
+        );
+        assert!(dir.join(format!("{}.bak", PROFILES_FILE)).exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn registry_persists_and_protects_active_profile() {
+        let dir = temp_dir("registry");
+        let manager = ProfileManager::load(dir.clone()).unwrap();
+        assert_eq!(manager.active().unwrap().id, DEFAULT_PROFILE_ID);
+
+        let mina = manager.create("Mina").unwrap();
+        assert!(manager.db_path(&mina.id).exists());
+        assert!(manager.create(" mina ").is_err());
+        manager.rename(&mina.id, "Mina B").unwrap();
+        manager.set_active(&mina.id).unwrap();
+        assert!(manager.delete(&mina.id).is_err());
+
+        let reloaded = ProfileManager::load(dir.clone()).unwrap();
+        assert_eq!(reloaded.active().unwrap().name, "Mina B");
+        reloaded.set_active(DEFAULT_PROFILE_ID).unwrap();
+        reloaded.delete(&mina.id).unwrap();
+        assert!(!reloaded.db_path(&mina.id).exists());
+        assert_eq!(reloaded.list().unwrap().len(), 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
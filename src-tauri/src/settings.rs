@@ -0,0 +1,462 @@
+//! Typed application settings, stored one key per field in `app_settings`.
+//!
+//! Keys that existed before this module (trash retention, search history pause, content
+//! policy) keep their names and value formats, so their dedicated commands keep working.
+//! Missing or invalid stored values read as the default. `settings_version` records the
+//! layout the values were written with, so later versions can convert them in place.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::booth::client::RATE_LIMIT;
+use crate::database::{self, AppDatabase, DEFAULT_TRASH_RETENTION_DAYS, TRASH_RETENTION_KEY};
+use crate::error::{AppError, AppResult};
+
+pub const SETTINGS_VERSION: i64 = 1;
+const VERSION_KEY: &str = "settings_version";
+
+const LANGUAGE_KEY: &str = "language";
+const TRANSLATION_SOURCE_KEY: &str = "translation_source";
+const TRANSLATION_TARGET_KEY: &str = "translation_target";
+const REQUEST_INTERVAL_KEY: &str = "request_interval_ms";
+const BACKGROUND_INTERVAL_KEY: &str = "background_request_interval_ms";
+const CACHE_TTL_KEY: &str = "cache_ttl_days";
+const TRANSLATION_TTL_KEY: &str = "translation_ttl_days";
+const AVATAR_REFRESH_KEY: &str = "avatar_refresh_days";
+/// `app_settings` key; while `"1"`, searches are not recorded.
+pub const SEARCH_HISTORY_PAUSED_KEY: &str = "search_history_paused";
+/// `app_settings` key holding the [`ContentPolicy`] for R-18 items.
+pub const CONTENT_POLICY_KEY: &str = "adult_content_policy";
+
+/// UI languages the frontend ships translations for.
+pub const LANGUAGES: &[&str] = &["ko", "en"];
+
+/// Booth answers faster request rates with 429s.
+const MIN_REQUEST_INTERVAL_MS: u64 = 500;
+
+/// How R-18 items are presented. Hidden by default so the app is safe to use at work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentPolicy {
+    Show,
+    /// Listed with `blurred` set so the UI can cover thumbnails and names
+    Blur,
+    #[default]
+    Hide,
+}
+
+impl ContentPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentPolicy::Show => "show",
+            ContentPolicy::Blur => "blur",
+            ContentPolicy::Hide => "hide",
+        }
+    }
+
+    /// Unknown or missing values fall back to the safe default.
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "show" => ContentPolicy::Show,
+            "blur" => ContentPolicy::Blur,
+            _ => ContentPolicy::Hide,
+        }
+    }
+
+    /// Whether an item is listed, and if so whether it is blurred.
+    pub fn apply(self, adult: bool) -> Option<bool> {
+        match (self, adult) {
+            (_, false) | (ContentPolicy::Show, true) => Some(false),
+            (ContentPolicy::Blur, true) => Some(true),
+            (ContentPolicy::Hide, true) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Settings {
+    /// UI language; `None` follows the system language
+    pub language: Option<String>,
+    /// Language codes for description translation
+    pub translation_source: String,
+    pub translation_target: String,
+    /// Delay between requests to Booth, for searches and background jobs
+    pub request_interval_ms: u64,
+    /// Delay between requests of low-priority updates (popular avatar counts)
+    pub background_request_interval_ms: u64,
+    /// Cached search results older than this are evicted on startup
+    pub cache_ttl_days: i64,
+    pub translation_ttl_days: i64,
+    /// Popular avatar counts are refreshed once they are this old
+    pub avatar_refresh_days: i64,
+    pub trash_retention_days: i64,
+    pub search_history_paused: bool,
+    pub content_policy: ContentPolicy,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            language: None,
+            translation_source: "ja".to_string(),
+            translation_target: "ko".to_string(),
+            request_interval_ms: RATE_LIMIT.as_millis() as u64,
+            background_request_interval_ms: 1500,
+            cache_ttl_days: 30,
+            translation_ttl_days: 90,
+            avatar_refresh_days: 7,
+            trash_retention_days: DEFAULT_TRASH_RETENTION_DAYS,
+            search_history_paused: false,
+            content_policy: ContentPolicy::default(),
+        }
+    }
+}
+
+/// Fields to change; `None` keeps the current value. An empty `language` resets it to
+/// the system language.
+#[derive(Debug, Default, Deserialize)]
+pub struct SettingsPatch {
+    pub language: Option<String>,
+    pub translation_source: Option<String>,
+    pub translation_target: Option<String>,
+    pub request_interval_ms: Option<u64>,
+    pub background_request_interval_ms: Option<u64>,
+    pub cache_ttl_days: Option<i64>,
+    pub translation_ttl_days: Option<i64>,
+    pub avatar_refresh_days: Option<i64>,
+    pub trash_retention_days: Option<i64>,
+    pub search_history_paused: Option<bool>,
+    pub content_policy: Option<ContentPolicy>,
+}
+
+// ── Validation ─────────────────────────────────────────
+
+fn days_in_range(days: i64, max: i64) -> bool {
+    (1..=max).contains(&days)
+}
+
+fn interval_in_range(ms: u64, max: u64) -> bool {
+    (MIN_REQUEST_INTERVAL_MS..=max).contains(&ms)
+}
+
+fn check_days(field: &str, days: i64, max: i64) -> AppResult<i64> {
+    if !days_in_range(days, max) {
+        return Err(AppError::ParseError(format!(
+            "{} must be between 1 and {} days",
+            field, max
+        )));
+    }
+    Ok(days)
+}
+
+fn check_interval(field: &str, ms: u64, max: u64) -> AppResult<u64> {
+    if !interval_in_range(ms, max) {
+        return Err(AppError::ParseError(format!(
+            "{} must be between {} and {} ms",
+            field, MIN_REQUEST_INTERVAL_MS, max
+        )));
+    }
+    Ok(ms)
+}
+
+fn check_language(language: String) -> AppResult<String> {
+    if !LANGUAGES.contains(&language.as_str()) {
+        return Err(AppError::ParseError(format!(
+            "Unsupported language: '{}'",
+            language
+        )));
+    }
+    Ok(language)
+}
+
+/// Translation codes like `ja`, `ko` or `zh_HANT`.
+fn check_translation_code(code: String) -> AppResult<String> {
+    let valid = (2..=8).contains(&code.len())
+        && code
+            .bytes()
+            .all(|b| b.is_ascii_alphabetic() || b == b'_' || b == b'-');
+    if !valid {
+        return Err(AppError::ParseError(format!(
+            "Not a language code: '{}'",
+            code
+        )));
+    }
+    Ok(code)
+}
+
+// ── Storage ────────────────────────────────────────────
+
+fn read<T: FromStr>(conn: &Connection, key: &str) -> AppResult<Option<T>> {
+    Ok(
+        database::get_setting(conn, key)?.and_then(|v| match v.parse() {
+            Ok(parsed) => Some(parsed),
+            Err(_) => {
+                log::warn!("Ignoring invalid setting {} = '{}'", key, v);
+                None
+            }
+        }),
+    )
+}
+
+impl Settings {
+    pub fn load(conn: &Connection) -> AppResult<Self> {
+        let d = Settings::default();
+        let bool_value = |v: Option<String>| v.map(|v| v == "1");
+        Ok(Settings {
+            language: read::<String>(conn, LANGUAGE_KEY)?.and_then(|v| check_language(v).ok()),
+            translation_source: read(conn, TRANSLATION_SOURCE_KEY)?
+                .and_then(|v| check_translation_code(v).ok())
+                .unwrap_or(d.translation_source),
+            translation_target: read(conn, TRANSLATION_TARGET_KEY)?
+                .and_then(|v| check_translation_code(v).ok())
+                .unwrap_or(d.translation_target),
+            request_interval_ms: read(conn, REQUEST_INTERVAL_KEY)?
+                .filter(|v| interval_in_range(*v, 10_000))
+                .unwrap_or(d.request_interval_ms),
+            background_request_interval_ms: read(conn, BACKGROUND_INTERVAL_KEY)?
+                .filter(|v| interval_in_range(*v, 60_000))
+                .unwrap_or(d.background_request_interval_ms),
+            cache_ttl_days: read(conn, CACHE_TTL_KEY)?
+                .filter(|v| days_in_range(*v, 365))
+                .unwrap_or(d.cache_ttl_days),
+            translation_ttl_days: read(conn, TRANSLATION_TTL_KEY)?
+                .filter(|v| days_in_range(*v, 365))
+                .unwrap_or(d.translation_ttl_days),
+            avatar_refresh_days: read(conn, AVATAR_REFRESH_KEY)?
+                .filter(|v| days_in_range(*v, 90))
+                .unwrap_or(d.avatar_refresh_days),
+            trash_retention_days: read(conn, TRASH_RETENTION_KEY)?
+                .filter(|v| days_in_range(*v, 365))
+                .unwrap_or(d.trash_retention_days),
+            search_history_paused: bool_value(read(conn, SEARCH_HISTORY_PAUSED_KEY)?)
+                .unwrap_or(d.search_history_paused),
+            content_policy: read::<String>(conn, CONTENT_POLICY_KEY)?
+                .map(|v| ContentPolicy::from_db(&v))
+                .unwrap_or(d.content_policy),
+        })
+    }
+
+    /// Validate and apply a patch. Returns the names of the fields that changed.
+    pub fn apply(&mut self, patch: SettingsPatch) -> AppResult<Vec<&'static str>> {
+        let mut next = self.clone();
+        if let Some(language) = patch.language {
+            next.language = match language.trim() {
+                "" => None,
+                lang => Some(check_language(lang.to_string())?),
+            };
+        }
+        if let Some(code) = patch.translation_source {
+            next.translation_source = check_translation_code(code.trim().to_string())?;
+        }
+        if let Some(code) = patch.translation_target {
+            next.translation_target = check_translation_code(code.trim().to_string())?;
+        }
+        if next.translation_source == next.translation_target {
+            return Err(AppError::ParseError(
+                "Translation source and target must differ".to_string(),
+            ));
+        }
+        if let Some(ms) = patch.request_interval_ms {
+            next.request_interval_ms = check_interval("Request interval", ms, 10_000)?;
+        }
+        if let Some(ms) = patch.background_request_interval_ms {
+            next.background_request_interval_ms =
+                check_interval("Background request interval", ms, 60_000)?;
+        }
+        if let Some(days) = patch.cache_ttl_days {
+            next.cache_ttl_days = check_days("Cache lifetime", days, 365)?;
+        }
+        if let Some(days) = patch.translation_ttl_days {
+            next.translation_ttl_days = check_days("Translation cache lifetime", days, 365)?;
+        }
+        if let Some(days) = patch.avatar_refresh_days {
+            next.avatar_refresh_days = check_days("Avatar refresh interval", days, 90)?;
+        }
+        if let Some(days) = patch.trash_retention_days {
+            next.trash_retention_days = check_days("Retention", days, 365)?;
+        }
+        if let Some(paused) = patch.search_history_paused {
+            next.search_history_paused = paused;
+        }
+        if let Some(policy) = patch.content_policy {
+            next.content_policy = policy;
+        }
+
+        let changed = self.diff(&next);
+        *self = next;
+        Ok(changed)
+    }
+
+    /// Names of the fields that differ between `self` and `other`.
+    pub fn diff(&self, other: &Settings) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        macro_rules! diff {
+            ($($field:ident),*) => {
+                $(if other.$field != self.$field { changed.push(stringify!($field)); })*
+            };
+        }
+        diff!(
+            language,
+            translation_source,
+            translation_target,
+            request_interval_ms,
+            background_request_interval_ms,
+            cache_ttl_days,
+            translation_ttl_days,
+            avatar_refresh_days,
+            trash_retention_days,
+            search_history_paused,
+            content_policy
+        );
+        changed
+    }
+
+    pub fn save(&self, conn: &Connection) -> AppResult<()> {
+        let set = |key: &str, value: String| database::set_setting(conn, key, &value);
+        set(LANGUAGE_KEY, self.language.clone().unwrap_or_default())?;
+        set(TRANSLATION_SOURCE_KEY, self.translation_source.clone())?;
+        set(TRANSLATION_TARGET_KEY, self.translation_target.clone())?;
+        set(REQUEST_INTERVAL_KEY, self.request_interval_ms.to_string())?;
+        set(
+            BACKGROUND_INTERVAL_KEY,
+            self.background_request_interval_ms.to_string(),
+        )?;
+        set(CACHE_TTL_KEY, self.cache_ttl_days.to_string())?;
+        set(TRANSLATION_TTL_KEY, self.translation_ttl_days.to_string())?;
+        set(AVATAR_REFRESH_KEY, self.avatar_refresh_days.to_string())?;
+        set(TRASH_RETENTION_KEY, self.trash_retention_days.to_string())?;
+        set(
+            SEARCH_HISTORY_PAUSED_KEY,
+            if self.search_history_paused { "1" } else { "0" }.to_string(),
+        )?;
+        set(CONTENT_POLICY_KEY, self.content_policy.as_str().to_string())?;
+        set(VERSION_KEY, SETTINGS_VERSION.to_string())
+    }
+
+    pub fn request_interval(&self) -> Duration {
+        Duration::from_millis(self.request_interval_ms)
+    }
+}
+
+/// Bring stored settings up to [`SETTINGS_VERSION`]. Values written by a newer version
+/// are left alone; fields this version does not know are simply not read.
+pub fn migrate(conn: &Connection) -> AppResult<()> {
+    let version: i64 = read(conn, VERSION_KEY)?.unwrap_or(0);
+    if version > SETTINGS_VERSION {
+        log::warn!(
+            "Settings were written by a newer version ({} > {})",
+            version,
+            SETTINGS_VERSION
+        );
+        return Ok(());
+    }
+    // v1: first typed layout. Earlier keys are read as they are; an empty language
+    // already means "system language".
+    if version < 1 {
+        database::set_setting(conn, VERSION_KEY, &SETTINGS_VERSION.to_string())?;
+    }
+    Ok(())
+}
+
+/// Delay before the next request of a background job. Read on every request, so a change
+/// made while a job runs applies right away.
+pub fn request_interval(db: &AppDatabase) -> Duration {
+    db.conn()
+        .and_then(|conn| Settings::load(&conn))
+        .map(|s| s.request_interval())
+        .unwrap_or(RATE_LIMIT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hides_by_default_and_on_unknown_values() {
+        assert_eq!(ContentPolicy::default(), ContentPolicy::Hide);
+        assert_eq!(ContentPolicy::from_db("nsfw"), ContentPolicy::Hide);
+        for p in [
+            ContentPolicy::Show,
+            ContentPolicy::Blur,
+            ContentPolicy::Hide,
+        ] {
+            assert_eq!(ContentPolicy::from_db(p.as_str()), p);
+        }
+    }
+
+    #[test]
+    fn policy_decides_listing_and_blur() {
+        assert_eq!(ContentPolicy::Hide.apply(false), Some(false));
+        assert_eq!(ContentPolicy::Hide.apply(true), None);
+        assert_eq!(ContentPolicy::Blur.apply(true), Some(true));
+        assert_eq!(ContentPolicy::Show.apply(true), Some(false));
+    }
+
+    fn conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE app_settings (key TEXT PRIMARY KEY, value TEXT NOT NULL,
+                                        updated_at TEXT DEFAULT (datetime('now')));",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn missing_and_invalid_values_read_as_defaults() {
+        let conn = conn();
+        assert_eq!(Settings::load(&conn).unwrap(), Settings::default());
+        database::set_setting(&conn, REQUEST_INTERVAL_KEY, "fast").unwrap();
+        database::set_setting(&conn, CACHE_TTL_KEY, "0").unwrap();
+        database::set_setting(&conn, TRASH_RETENTION_KEY, "14").unwrap();
+        database::set_setting(&conn, SEARCH_HISTORY_PAUSED_KEY, "1").unwrap();
+        let s = Settings::load(&conn).unwrap();
+        assert_eq!((s.request_interval_ms, s.cache_ttl_days), (1000, 30));
+        assert_eq!(s.trash_retention_days, 14);
+        assert!(s.search_history_paused);
+    }
+
+    #[test]
+    fn patches_are_validated_and_diffed() {
+        let conn = conn();
+        let mut s = Settings::default();
+        let changed = s
+            .apply(SettingsPatch {
+                language: Some("en".to_string()),
+                request_interval_ms: Some(2000),
+                cache_ttl_days: Some(30),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(changed, vec!["language", "request_interval_ms"]);
+        s.save(&conn).unwrap();
+        assert_eq!(Settings::load(&conn).unwrap(), s);
+
+        for bad in [
+            SettingsPatch {
+                request_interval_ms: Some(100),
+                ..Default::default()
+            },
+            SettingsPatch {
+                language: Some("fr".to_string()),
+                ..Default::default()
+            },
+            SettingsPatch {
+                translation_target: Some("ja".to_string()),
+                ..Default::default()
+            },
+        ] {
+            assert!(s.clone().apply(bad).is_err());
+        }
+        let changed = s
+            .apply(SettingsPatch {
+                language: Some(String::new()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!((changed, s.language), (vec!["language"], None));
+    }
+}
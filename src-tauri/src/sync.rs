@@ -0,0 +1,1075 @@
+//! Library sync between devices through a shared folder (Syncthing, Dropbox, a NAS).
+//!
+//! Each device appends its changes to `<folder>/<device id>.jsonl` and only reads the logs
+//! of the others, so a sync tool never sees two writers on one file. Favorites with their
+//! notes, tags, collections and collection memberships are merged field by field: the
+//! newest write wins, with the device ID breaking ties. Deletes are a `removed` field like
+//! any other (a tombstone), so an older add can not bring a row back.
+//!
+//! Rows are addressed by IDs all devices agree on: the Booth item ID for favorites and
+//! tags, and a UUID for collections, whose local IDs differ between databases. Local
+//! changes are found by comparing the library with `sync_fields`, the merged value of
+//! every field as of the last sync, so the commands editing the library need not know
+//! about sync. Triggers keep the time of the last write to every synced field in
+//! `sync_edits`, and a change is stamped with that time rather than the sync's.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::database;
+use crate::entities::{ensure_category, ensure_shop};
+use crate::error::{AppError, AppResult};
+
+/// `app_settings` key holding the shared folder; empty when sync is off.
+pub const SYNC_FOLDER_KEY: &str = "sync_folder";
+const DEVICE_ID_KEY: &str = "sync_device_id";
+const LOG_EXTENSION: &str = "jsonl";
+
+/// Field marking a deleted row; a tombstone is this field set to `true`.
+const REMOVED: &str = "removed";
+
+const FAVORITE_FIELDS: &[&str] = &[
+    "name",
+    "price",
+    "price_max",
+    "thumbnail_url",
+    "category_name",
+    "shop_name",
+    "is_adult",
+    "added_at",
+    "note",
+];
+const COLLECTION_FIELDS: &[&str] = &["name", "color", "sort_order"];
+
+/// The current time in SQL, in the milliseconds of `Change::at`.
+const NOW_MS: &str = "CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)";
+
+// ── Types ──────────────────────────────────────────────
+
+/// Kinds of synced rows, in the order they are written: tags and memberships need their
+/// favorite and collection to exist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Entity {
+    /// Keyed by Booth item ID
+    Favorite,
+    /// Keyed by UUID
+    Collection,
+    /// Keyed by `<item id>/<tag>`
+    Tag,
+    /// Keyed by `<collection UUID>/<item id>`
+    CollectionItem,
+}
+
+impl Entity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Entity::Favorite => "favorite",
+            Entity::Collection => "collection",
+            Entity::Tag => "tag",
+            Entity::CollectionItem => "collection_item",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "favorite" => Some(Entity::Favorite),
+            "collection" => Some(Entity::Collection),
+            "tag" => Some(Entity::Tag),
+            "collection_item" => Some(Entity::CollectionItem),
+            _ => None,
+        }
+    }
+}
+
+/// One line of a device's log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Change {
+    /// Milliseconds since the epoch when the field was written, raised past the version
+    /// it replaced
+    pub at: i64,
+    pub entity: Entity,
+    pub key: String,
+    pub field: String,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    /// Local changes appended to this device's log
+    pub exported: usize,
+    /// Changes of other devices that replaced the local value
+    pub imported: usize,
+    /// Other devices whose logs are in the folder
+    pub devices: usize,
+}
+
+type FieldKey = (Entity, String, String);
+
+/// The merged value of a field and the write it came from.
+struct Version {
+    value: Value,
+    at: i64,
+    device: String,
+}
+
+// ── Helpers ────────────────────────────────────────────
+
+pub fn new_uuid() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// Give collections without one (created before sync existed) a UUID.
+pub fn assign_uuids(conn: &Connection) -> AppResult<()> {
+    let ids: Vec<i64> = {
+        let mut stmt = conn.prepare("SELECT id FROM collections WHERE uuid IS NULL")?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+    for id in ids {
+        conn.execute(
+            "UPDATE collections SET uuid = ?1 WHERE id = ?2",
+            params![new_uuid(), id],
+        )?;
+    }
+    Ok(())
+}
+
+/// Statements recording a write to `field` of the row `key` names, when `when` holds.
+/// The outer statement's conflict policy overrides a trigger's, so an `OR REPLACE`
+/// would not survive an upsert; the old stamp is deleted first instead.
+fn stamp(entity: Entity, key: &str, field: &str, when: &str) -> String {
+    format!(
+        "DELETE FROM sync_edits
+         WHERE entity = '{entity}' AND key = {key} AND field = '{field}' AND ({when});
+         INSERT INTO sync_edits (entity, key, field, at)
+         SELECT '{entity}', {key}, '{field}', {now} WHERE {key} IS NOT NULL AND ({when});",
+        entity = entity.as_str(),
+        key = key,
+        field = field,
+        now = NOW_MS,
+        when = when
+    )
+}
+
+/// Create `sync_edits` and the triggers that fill it from every write to a synced field.
+pub fn track_edits(conn: &Connection) -> AppResult<()> {
+    let mut sql = String::from(
+        "CREATE TABLE IF NOT EXISTS sync_edits (
+             entity  TEXT NOT NULL,
+             key     TEXT NOT NULL,
+             field   TEXT NOT NULL,
+             at      INTEGER NOT NULL,
+             PRIMARY KEY (entity, key, field)
+         );",
+    );
+    let trigger = |name: &str, event: &str, table: &str, body: Vec<String>| {
+        format!(
+            "CREATE TRIGGER IF NOT EXISTS sync_edits_{}_{} AFTER {} ON {} BEGIN {} END;",
+            table,
+            name,
+            event,
+            table,
+            body.concat()
+        )
+    };
+    let rows = [
+        (
+            Entity::Favorite,
+            "favorites",
+            "CAST({row}.item_id AS TEXT)",
+            FAVORITE_FIELDS,
+        ),
+        (
+            Entity::Collection,
+            "collections",
+            "{row}.uuid",
+            COLLECTION_FIELDS,
+        ),
+    ];
+    for (entity, table, key, fields) in rows {
+        let (new, old) = (key.replace("{row}", "NEW"), key.replace("{row}", "OLD"));
+        let all = fields.iter().chain([&REMOVED]);
+        sql.push_str(&trigger(
+            "insert",
+            "INSERT",
+            table,
+            all.map(|f| stamp(entity, &new, f, "1")).collect(),
+        ));
+        let mut updates: Vec<String> = fields
+            .iter()
+            .map(|f| stamp(entity, &new, f, &format!("NEW.{0} IS NOT OLD.{0}", f)))
+            .collect();
+        updates.push(stamp(
+            entity,
+            &new,
+            REMOVED,
+            "(NEW.deleted_at IS NULL) IS NOT (OLD.deleted_at IS NULL)",
+        ));
+        sql.push_str(&trigger("update", "UPDATE", table, updates));
+        sql.push_str(&trigger(
+            "delete",
+            "DELETE",
+            table,
+            vec![stamp(entity, &old, REMOVED, "1")],
+        ));
+    }
+    let links = [
+        (
+            Entity::Tag,
+            "item_tags",
+            "{row}.item_id || '/' || {row}.tag",
+        ),
+        (
+            Entity::CollectionItem,
+            "collection_items",
+            "(SELECT uuid FROM collections WHERE id = {row}.collection_id) || '/' || {row}.item_id",
+        ),
+    ];
+    for (entity, table, key) in links {
+        for (name, row) in [("insert", "NEW"), ("delete", "OLD")] {
+            sql.push_str(&trigger(
+                name,
+                &name.to_uppercase(),
+                table,
+                vec![stamp(entity, &key.replace("{row}", row), REMOVED, "1")],
+            ));
+        }
+    }
+    conn.execute_batch(&sql)?;
+    Ok(())
+}
+
+/// When each synced field was last written here.
+fn edits(conn: &Connection) -> AppResult<BTreeMap<FieldKey, i64>> {
+    let mut stmt = conn.prepare("SELECT entity, key, field, at FROM sync_edits")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(entity, key, field, at)| Some(((Entity::from_db(&entity)?, key, field), at)))
+        .collect())
+}
+
+/// Whether a change names a row and carries a value its column can hold. Logs are
+/// written by other versions of the app and sometimes by hand, so neither is a given.
+fn valid(change: &Change) -> bool {
+    let item = |id: &str| id.parse::<i64>().is_ok();
+    let key_ok = match change.entity {
+        Entity::Favorite => item(&change.key),
+        Entity::Collection => !change.key.is_empty(),
+        Entity::Tag => change
+            .key
+            .split_once('/')
+            .is_some_and(|(id, tag)| item(id) && !tag.is_empty()),
+        Entity::CollectionItem => change
+            .key
+            .split_once('/')
+            .is_some_and(|(uuid, id)| !uuid.is_empty() && item(id)),
+    };
+    let value = &change.value;
+    let value_ok = match (change.entity, change.field.as_str()) {
+        (_, REMOVED) => value.is_boolean(),
+        (Entity::Favorite | Entity::Collection, "name") => value.is_string(),
+        (Entity::Favorite, "price") => value.is_i64(),
+        (Entity::Favorite, "is_adult") => value.is_boolean() || value.is_i64(),
+        (Entity::Favorite, "price_max") | (Entity::Collection, "sort_order") => {
+            value.is_null() || value.is_i64()
+        }
+        (
+            Entity::Favorite,
+            "thumbnail_url" | "category_name" | "shop_name" | "added_at" | "note",
+        )
+        | (Entity::Collection, "color") => value.is_null() || value.is_string(),
+        _ => false,
+    };
+    key_ok && value_ok
+}
+
+/// ID of this database in the sync folder, created on first use.
+pub fn device_id(conn: &Connection) -> AppResult<String> {
+    if let Some(id) = database::get_setting(conn, DEVICE_ID_KEY)? {
+        return Ok(id);
+    }
+    let id = new_uuid();
+    database::set_setting(conn, DEVICE_ID_KEY, &id)?;
+    Ok(id)
+}
+
+pub fn sync_folder(conn: &Connection) -> AppResult<Option<PathBuf>> {
+    Ok(database::get_setting(conn, SYNC_FOLDER_KEY)?
+        .filter(|folder| !folder.is_empty())
+        .map(PathBuf::from))
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn to_json(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => Value::from(f),
+        ValueRef::Text(t) => Value::from(String::from_utf8_lossy(t).into_owned()),
+        ValueRef::Null | ValueRef::Blob(_) => Value::Null,
+    }
+}
+
+fn to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+// ── Library state ──────────────────────────────────────
+
+/// Read rows of `sql`: the key, one column per field, and whether the row is removed.
+fn read_rows(
+    conn: &Connection,
+    sql: &str,
+    entity: Entity,
+    fields: &[&str],
+    out: &mut BTreeMap<FieldKey, Value>,
+) -> AppResult<()> {
+    let mut stmt = conn.prepare(sql)?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let key: String = row.get(0)?;
+        for (i, field) in fields.iter().enumerate() {
+            out.insert(
+                (entity, key.clone(), field.to_string()),
+                to_json(row.get_ref(i + 1)?),
+            );
+        }
+        let removed: bool = row.get(fields.len() + 1)?;
+        out.insert((entity, key, REMOVED.to_string()), Value::Bool(removed));
+    }
+    Ok(())
+}
+
+/// The library as synced fields. Trashed rows are `removed` but keep their fields, so
+/// restoring one on another device brings back its latest values.
+fn snapshot(conn: &Connection) -> AppResult<BTreeMap<FieldKey, Value>> {
+    let mut fields = BTreeMap::new();
+    read_rows(
+        conn,
+        "SELECT CAST(item_id AS TEXT), name, price, price_max, thumbnail_url, category_name,
+                shop_name, is_adult, added_at, note, deleted_at IS NOT NULL
+         FROM favorites",
+        Entity::Favorite,
+        FAVORITE_FIELDS,
+        &mut fields,
+    )?;
+    read_rows(
+        conn,
+        "SELECT uuid, name, color, sort_order, deleted_at IS NOT NULL
+         FROM collections WHERE uuid IS NOT NULL",
+        Entity::Collection,
+        COLLECTION_FIELDS,
+        &mut fields,
+    )?;
+    read_rows(
+        conn,
+        "SELECT item_id || '/' || tag, 0 FROM item_tags",
+        Entity::Tag,
+        &[],
+        &mut fields,
+    )?;
+    read_rows(
+        conn,
+        "SELECT c.uuid || '/' || ci.item_id, 0
+         FROM collection_items ci JOIN collections c ON c.id = ci.collection_id
+         WHERE c.uuid IS NOT NULL",
+        Entity::CollectionItem,
+        &[],
+        &mut fields,
+    )?;
+    Ok(fields)
+}
+
+fn recorded(conn: &Connection) -> AppResult<BTreeMap<FieldKey, Version>> {
+    let mut stmt = conn.prepare("SELECT entity, key, field, value, at, device FROM sync_fields")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(4)?,
+                row.get::<_, String>(5)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(entity, key, field, value, at, device)| {
+            let version = Version {
+                value: serde_json::from_str(&value).unwrap_or(Value::Null),
+                at,
+                device,
+            };
+            Some(((Entity::from_db(&entity)?, key, field), version))
+        })
+        .collect())
+}
+
+fn record(conn: &Connection, (entity, key, field): &FieldKey, version: &Version) -> AppResult<()> {
+    conn.execute(
+        "INSERT INTO sync_fields (entity, key, field, value, at, device)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(entity, key, field) DO UPDATE SET
+             value = excluded.value, at = excluded.at, device = excluded.device",
+        params![
+            entity.as_str(),
+            key,
+            field,
+            version.value.to_string(),
+            version.at,
+            version.device
+        ],
+    )?;
+    Ok(())
+}
+
+/// Changes that bring the merged state in line with the library: fields whose value
+/// differs, and tombstones for rows that are gone. Each is stamped with its last write,
+/// or `now` for writes made before edits were tracked.
+fn local_changes(
+    conn: &Connection,
+    recorded: &BTreeMap<FieldKey, Version>,
+    now: i64,
+) -> AppResult<Vec<Change>> {
+    let current = snapshot(conn)?;
+    let edits = edits(conn)?;
+    // Past the version it replaces, so a clock behind another device's still wins here
+    let at = |key: &FieldKey| {
+        let edited = edits.get(key).copied().unwrap_or(now);
+        recorded.get(key).map_or(edited, |v| edited.max(v.at + 1))
+    };
+    let mut changes: Vec<Change> = current
+        .iter()
+        .filter(|(key, value)| recorded.get(*key).map(|v| &v.value) != Some(*value))
+        .map(|(field_key @ (entity, key, field), value)| Change {
+            at: at(field_key),
+            entity: *entity,
+            key: key.clone(),
+            field: field.clone(),
+            value: value.clone(),
+        })
+        .collect();
+
+    let present = |entity: Entity, key: &str| {
+        current.contains_key(&(entity, key.to_string(), REMOVED.to_string()))
+    };
+    for (field_key, version) in recorded {
+        let (entity, key, field) = field_key;
+        if field != REMOVED || version.value != Value::Bool(false) || present(*entity, key) {
+            continue;
+        }
+        // Tags and memberships live next to their favorite and collection. Without those
+        // here they were never written, or went along with a purge; neither is a delete.
+        let deleted = match entity {
+            Entity::Favorite | Entity::Collection => true,
+            Entity::Tag => key
+                .split_once('/')
+                .is_some_and(|(item, _)| present(Entity::Favorite, item)),
+            Entity::CollectionItem => key.split_once('/').is_some_and(|(collection, item)| {
+                present(Entity::Collection, collection) && present(Entity::Favorite, item)
+            }),
+        };
+        if deleted {
+            changes.push(Change {
+                at: at(field_key),
+                entity: *entity,
+                key: key.clone(),
+                field: REMOVED.to_string(),
+                value: Value::Bool(true),
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// Write the merged fields of a row that exists here, moving it to or out of the trash.
+fn update_row(
+    conn: &Connection,
+    table: &str,
+    id_column: &str,
+    id: SqlValue,
+    fields: Vec<(&str, SqlValue)>,
+    removed: bool,
+) -> AppResult<()> {
+    let mut sets: Vec<String> = fields.iter().map(|(f, _)| format!("{} = ?", f)).collect();
+    sets.push(format!(
+        "deleted_at = CASE WHEN {} THEN COALESCE(deleted_at, datetime('now')) END",
+        removed as i32
+    ));
+    conn.execute(
+        &format!(
+            "UPDATE {} SET {} WHERE {} = ?",
+            table,
+            sets.join(", "),
+            id_column
+        ),
+        params_from_iter(fields.into_iter().map(|(_, v)| v).chain([id])),
+    )?;
+    Ok(())
+}
+
+fn insert_row(conn: &Connection, table: &str, fields: Vec<(&str, SqlValue)>) -> AppResult<()> {
+    let columns: Vec<&str> = fields.iter().map(|(f, _)| *f).collect();
+    conn.execute(
+        &format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            table,
+            columns.join(", "),
+            vec!["?"; columns.len()].join(", ")
+        ),
+        params_from_iter(fields.into_iter().map(|(_, v)| v)),
+    )?;
+    Ok(())
+}
+
+/// Write the merged state of one row into the library.
+fn materialize(
+    conn: &Connection,
+    recorded: &BTreeMap<FieldKey, Version>,
+    entity: Entity,
+    key: &str,
+) -> AppResult<()> {
+    let value = |field: &str| {
+        recorded
+            .get(&(entity, key.to_string(), field.to_string()))
+            .map(|v| &v.value)
+    };
+    let present = |fields: &[&'static str]| -> Vec<(&'static str, SqlValue)> {
+        fields
+            .iter()
+            .filter_map(|f| value(f).map(|v| (*f, to_sql(v))))
+            .collect()
+    };
+    let Some(removed) = value(REMOVED).and_then(Value::as_bool) else {
+        return Ok(());
+    };
+
+    match entity {
+        Entity::Favorite => {
+            let Ok(item_id) = key.parse::<i64>() else {
+                return Ok(());
+            };
+            let exists: bool = conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM favorites WHERE item_id = ?1)",
+                params![item_id],
+                |row| row.get(0),
+            )?;
+            let fields = present(FAVORITE_FIELDS);
+            if exists {
+                update_row(
+                    conn,
+                    "favorites",
+                    "item_id",
+                    item_id.into(),
+                    fields,
+                    removed,
+                )?;
+            } else if !removed && value("name").is_some() && value("price").is_some() {
+                let text = |field: &str| value(field).and_then(Value::as_str);
+                let cached_shop: Option<i64> = conn
+                    .query_row(
+                        "SELECT shop_id FROM cached_items WHERE id = ?1",
+                        params![item_id],
+                        |row| row.get(0),
+                    )
+                    .optional()?
+                    .flatten();
+                let shop_id = match cached_shop {
+                    Some(id) => Some(id),
+                    None => ensure_shop(conn, text("shop_name"), None)?,
+                };
+                let category_id = ensure_category(conn, text("category_name"))?;
+                let mut columns = vec![
+                    ("item_id", SqlValue::from(item_id)),
+                    ("shop_id", shop_id.into()),
+                    ("category_id", category_id.into()),
+                ];
+                columns.extend(fields);
+                insert_row(conn, "favorites", columns)?;
+            }
+        }
+        Entity::Collection => {
+            let id: Option<i64> = conn
+                .query_row(
+                    "SELECT id FROM collections WHERE uuid = ?1",
+                    params![key],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let fields = present(COLLECTION_FIELDS);
+            match id {
+                Some(id) => update_row(conn, "collections", "id", id.into(), fields, removed)?,
+                None if !removed && value("name").is_some() => {
+                    let mut columns = vec![("uuid", SqlValue::from(key.to_string()))];
+                    columns.extend(fields);
+                    insert_row(conn, "collections", columns)?;
+                }
+                None => {}
+            }
+        }
+        Entity::Tag => {
+            let Some((item_id, tag)) = key.split_once('/') else {
+                return Ok(());
+            };
+            if removed {
+                conn.execute(
+                    "DELETE FROM item_tags WHERE item_id = ?1 AND tag = ?2",
+                    params![item_id, tag],
+                )?;
+            } else {
+                conn.execute(
+                    "INSERT OR IGNORE INTO item_tags (item_id, tag)
+                     SELECT item_id, ?2 FROM favorites WHERE item_id = ?1",
+                    params![item_id, tag],
+                )?;
+            }
+        }
+        Entity::CollectionItem => {
+            let Some((uuid, item_id)) = key.split_once('/') else {
+                return Ok(());
+            };
+            if removed {
+                conn.execute(
+                    "DELETE FROM collection_items
+                     WHERE item_id = ?2 AND collection_id = (SELECT id FROM collections WHERE uuid = ?1)",
+                    params![uuid, item_id],
+                )?;
+            } else {
+                conn.execute(
+                    "INSERT OR IGNORE INTO collection_items (collection_id, item_id)
+                     SELECT c.id, f.item_id FROM collections c, favorites f
+                     WHERE c.uuid = ?1 AND f.item_id = ?2",
+                    params![uuid, item_id],
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// ── Logs ───────────────────────────────────────────────
+
+fn log_path(folder: &Path, device: &str) -> PathBuf {
+    folder.join(format!("{}.{}", device, LOG_EXTENSION))
+}
+
+/// Complete lines of a log; a trailing partial line is still being written or synced.
+fn read_log(path: &Path) -> AppResult<Vec<String>> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| AppError::Database(format!("Failed to read {}: {}", path.display(), e)))?;
+    let complete = text.rfind('\n').map_or("", |end| &text[..end]);
+    Ok(complete.lines().map(str::to_string).collect())
+}
+
+fn ends_mid_line(path: &Path) -> bool {
+    let mut last = [0u8; 1];
+    File::open(path)
+        .and_then(|mut file| {
+            file.seek(SeekFrom::End(-1))?;
+            file.read_exact(&mut last)
+        })
+        .is_ok()
+        && last[0] != b'\n'
+}
+
+fn append(path: &Path, mut changes: Vec<Change>) -> AppResult<()> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    // A reader that sees `removed: false` has seen the other fields of the row as well
+    changes.sort_by_key(|change| change.field == REMOVED);
+    let mut text = String::new();
+    // Close a line cut off by a crash, so it is skipped as one malformed line
+    if ends_mid_line(path) {
+        text.push('\n');
+    }
+    for change in &changes {
+        text.push_str(&serde_json::to_string(change).unwrap_or_default());
+        text.push('\n');
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .map_err(|e| AppError::Database(format!("Failed to write sync log: {}", e)))
+}
+
+/// Logs of the other devices in the folder.
+fn other_logs(folder: &Path, device: &str) -> AppResult<Vec<(String, PathBuf)>> {
+    let entries = std::fs::read_dir(folder)
+        .map_err(|e| AppError::Database(format!("Failed to read sync folder: {}", e)))?;
+    let mut logs: Vec<(String, PathBuf)> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == LOG_EXTENSION))
+        .filter_map(|path| Some((path.file_stem()?.to_str()?.to_string(), path)))
+        .filter(|(stem, _)| stem != device)
+        .collect();
+    logs.sort();
+    Ok(logs)
+}
+
+// ── Sync ───────────────────────────────────────────────
+
+/// Publish local changes to `folder` and merge the logs of the other devices into the
+/// library.
+pub fn sync(conn: &mut Connection, folder: &Path) -> AppResult<SyncReport> {
+    if !folder.is_dir() {
+        return Err(AppError::NotFound(format!(
+            "Sync folder {}",
+            folder.display()
+        )));
+    }
+    let tx = conn.transaction()?;
+    assign_uuids(&tx)?;
+    let device = device_id(&tx)?;
+    let mut recorded = recorded(&tx)?;
+    let mut report = SyncReport::default();
+
+    let own_log = log_path(folder, &device);
+    let mut publish = Vec::new();
+    if !own_log.exists() {
+        // A new folder, or the log got lost: publish the merged state so others catch up
+        publish.extend(recorded.iter().map(|((entity, key, field), v)| Change {
+            at: v.at,
+            entity: *entity,
+            key: key.clone(),
+            field: field.clone(),
+            value: v.value.clone(),
+        }));
+    }
+    let changes = local_changes(&tx, &recorded, now_ms())?;
+    for change in &changes {
+        let version = Version {
+            value: change.value.clone(),
+            at: change.at,
+            device: device.clone(),
+        };
+        let key = (change.entity, change.key.clone(), change.field.clone());
+        record(&tx, &key, &version)?;
+        recorded.insert(key, version);
+    }
+    report.exported = changes.len();
+    publish.extend(changes);
+    // Every write is in the merged state now; the triggers stamp the next ones afresh
+    tx.execute("DELETE FROM sync_edits", [])?;
+    // Written before the commit: should the commit fail, the next sync only repeats them
+    append(&own_log, publish)?;
+
+    let mut touched = BTreeSet::new();
+    for (other, path) in other_logs(folder, &device)? {
+        report.devices += 1;
+        let lines = read_log(&path)?;
+        let applied: usize = tx
+            .query_row(
+                "SELECT applied FROM sync_devices WHERE device = ?1",
+                params![other],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?
+            .unwrap_or(0) as usize;
+        // A log shorter than what was merged has been replaced; merging again is harmless
+        let start = if lines.len() < applied { 0 } else { applied };
+        if start == lines.len() {
+            continue;
+        }
+        for line in &lines[start..] {
+            let change: Change = match serde_json::from_str(line) {
+                Ok(change) => change,
+                Err(e) => {
+                    log::warn!("Skipping malformed change from {}: {}", other, e);
+                    continue;
+                }
+            };
+            if !valid(&change) {
+                log::warn!(
+                    "Skipping invalid change from {}: {} {} {} = {}",
+                    other,
+                    change.entity.as_str(),
+                    change.key,
+                    change.field,
+                    change.value
+                );
+                continue;
+            }
+            let key = (change.entity, change.key, change.field);
+            let newer = match recorded.get(&key) {
+                Some(v) => (change.at, other.as_str()) > (v.at, v.device.as_str()),
+                None => true,
+            };
+            if !newer {
+                continue;
+            }
+            let version = Version {
+                value: change.value,
+                at: change.at,
+                device: other.clone(),
+            };
+            record(&tx, &key, &version)?;
+            touched.insert((key.0, key.1.clone()));
+            recorded.insert(key, version);
+            report.imported += 1;
+        }
+        tx.execute(
+            "INSERT INTO sync_devices (device, applied, last_seen_at)
+             VALUES (?1, ?2, datetime('now'))
+             ON CONFLICT(device) DO UPDATE SET
+                 applied = excluded.applied, last_seen_at = excluded.last_seen_at",
+            params![other, lines.len() as i64],
+        )?;
+    }
+
+    // Rows that (re)appear take along tags and memberships that could not be written
+    // without them
+    let dependents: Vec<(Entity, String)> = recorded
+        .keys()
+        .filter(|(entity, key, field)| {
+            field == REMOVED
+                && match entity {
+                    Entity::Tag => key.split_once('/').is_some_and(|(item, _)| {
+                        touched.contains(&(Entity::Favorite, item.to_string()))
+                    }),
+                    Entity::CollectionItem => {
+                        key.split_once('/').is_some_and(|(collection, item)| {
+                            touched.contains(&(Entity::Collection, collection.to_string()))
+                                || touched.contains(&(Entity::Favorite, item.to_string()))
+                        })
+                    }
+                    _ => false,
+                }
+        })
+        .map(|(entity, key, _)| (*entity, key.clone()))
+        .collect();
+    touched.extend(dependents);
+    for (entity, key) in &touched {
+        materialize(&tx, &recorded, *entity, key)?;
+    }
+
+    tx.commit()?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::AppDatabase;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bh-sync-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn note(conn: &Connection, item_id: i64) -> (String, Option<String>, bool) {
+        conn.query_row(
+            "SELECT name, note, deleted_at IS NOT NULL FROM favorites WHERE item_id = ?1",
+            params![item_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .unwrap()
+    }
+
+    fn tags(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn
+            .prepare("SELECT item_id || '/' || tag FROM item_tags ORDER BY 1")
+            .unwrap();
+        let rows = stmt
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        rows
+    }
+
+    #[test]
+    fn merges_two_devices_through_a_folder() {
+        let (dir_a, dir_b, folder) = (temp_dir("a"), temp_dir("b"), temp_dir("folder"));
+        let mut a = AppDatabase::open_connection(&dir_a.join("test.db"), None).unwrap();
+        let mut b = AppDatabase::open_connection(&dir_b.join("test.db"), None).unwrap();
+
+        a.execute_batch(
+            "INSERT INTO favorites (item_id, name, price, note) VALUES (1, 'Cozy', 500, 'fits');
+             INSERT INTO favorites (item_id, name, price) VALUES (2, 'Hair', 300);
+             INSERT INTO item_tags (item_id, tag) VALUES (1, 'outfit'), (2, 'hair');
+             INSERT INTO collections (name, uuid) VALUES ('Wishlist', 'c1');
+             INSERT INTO collection_items (collection_id, item_id) VALUES (1, 1);",
+        )
+        .unwrap();
+        assert_eq!(sync(&mut a, &folder).unwrap().exported, 27);
+        let report = sync(&mut b, &folder).unwrap();
+        assert_eq!((report.exported, report.devices), (0, 1));
+        assert_eq!(
+            note(&b, 1),
+            ("Cozy".to_string(), Some("fits".to_string()), false)
+        );
+        assert_eq!(tags(&b), vec!["1/outfit", "2/hair"]);
+        let collected: i64 = b
+            .query_row(
+                "SELECT COUNT(*) FROM collection_items ci JOIN collections c
+                 ON c.id = ci.collection_id WHERE c.uuid = 'c1' AND c.name = 'Wishlist'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(collected, 1);
+
+        // Edits to different fields of one favorite both survive
+        a.execute(
+            "UPDATE favorites SET name = 'Cozy Set' WHERE item_id = 1",
+            [],
+        )
+        .unwrap();
+        b.execute(
+            "UPDATE favorites SET note = 'too big' WHERE item_id = 1",
+            [],
+        )
+        .unwrap();
+        // Deletes travel as tombstones
+        a.execute(
+            "UPDATE favorites SET deleted_at = datetime('now') WHERE item_id = 2",
+            [],
+        )
+        .unwrap();
+        b.execute("DELETE FROM item_tags WHERE tag = 'outfit'", [])
+            .unwrap();
+        sync(&mut a, &folder).unwrap();
+        sync(&mut b, &folder).unwrap();
+        assert_eq!(sync(&mut a, &folder).unwrap().exported, 0);
+        for conn in [&a, &b] {
+            assert_eq!(
+                note(conn, 1),
+                ("Cozy Set".to_string(), Some("too big".to_string()), false)
+            );
+            assert!(note(conn, 2).2);
+            assert_eq!(tags(conn), vec!["2/hair"]);
+        }
+
+        // The later write to the same field wins
+        b.execute("UPDATE favorites SET note = 'from b' WHERE item_id = 1", [])
+            .unwrap();
+        sync(&mut b, &folder).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        a.execute("UPDATE favorites SET note = 'from a' WHERE item_id = 1", [])
+            .unwrap();
+        sync(&mut a, &folder).unwrap();
+        sync(&mut b, &folder).unwrap();
+        assert_eq!(note(&b, 1).1, Some("from a".to_string()));
+
+        for dir in [dir_a, dir_b, folder] {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+
+    #[test]
+    fn changes_carry_their_edit_time() {
+        let (dir_a, dir_b, folder) = (temp_dir("edit-a"), temp_dir("edit-b"), temp_dir("edit"));
+        let mut a = AppDatabase::open_connection(&dir_a.join("test.db"), None).unwrap();
+        let mut b = AppDatabase::open_connection(&dir_b.join("test.db"), None).unwrap();
+        a.execute(
+            "INSERT INTO favorites (item_id, name, price) VALUES (1, 'Cozy', 500)",
+            [],
+        )
+        .unwrap();
+        sync(&mut a, &folder).unwrap();
+        sync(&mut b, &folder).unwrap();
+
+        // A edits first but syncs last; the later edit on B still wins
+        a.execute("UPDATE favorites SET note = 'from a' WHERE item_id = 1", [])
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        b.execute("UPDATE favorites SET note = 'from b' WHERE item_id = 1", [])
+            .unwrap();
+        sync(&mut b, &folder).unwrap();
+        sync(&mut a, &folder).unwrap();
+        sync(&mut b, &folder).unwrap();
+        for conn in [&a, &b] {
+            assert_eq!(note(conn, 1).1, Some("from b".to_string()));
+        }
+
+        for dir in [dir_a, dir_b, folder] {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+
+    #[test]
+    fn skips_changes_with_values_of_the_wrong_type() {
+        let (dir, folder) = (temp_dir("invalid"), temp_dir("invalid-folder"));
+        let mut conn = AppDatabase::open_connection(&dir.join("test.db"), None).unwrap();
+        let lines = [
+            r#"{"at":1,"entity":"favorite","key":"5","field":"name","value":null}"#,
+            r#"{"at":1,"entity":"favorite","key":"5","field":"price","value":"x"}"#,
+            r#"{"at":1,"entity":"favorite","key":"5","field":"removed","value":false}"#,
+            r#"{"at":1,"entity":"favorite","key":"6","field":"name","value":"Hair"}"#,
+            r#"{"at":1,"entity":"favorite","key":"6","field":"price","value":300}"#,
+            r#"{"at":1,"entity":"favorite","key":"6","field":"removed","value":false}"#,
+        ];
+        std::fs::write(folder.join("other.jsonl"), lines.join("\n") + "\n").unwrap();
+
+        assert_eq!(sync(&mut conn, &folder).unwrap().imported, 4);
+        assert_eq!(note(&conn, 6), ("Hair".to_string(), None, false));
+        let favorites: i64 = conn
+            .query_row("SELECT COUNT(*) FROM favorites", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(favorites, 1);
+        let applied: i64 = conn
+            .query_row(
+                "SELECT applied FROM sync_devices WHERE device = 'other'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(applied, 6);
+
+        for dir in [dir, folder] {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+
+    #[test]
+    fn skips_partial_lines() {
+        let dir = temp_dir("partial");
+        let path = dir.join("device.jsonl");
+        std::fs::write(&path, "{\"a\":1}\n{\"b\"").unwrap();
+        assert_eq!(read_log(&path).unwrap(), vec!["{\"a\":1}"]);
+        assert!(ends_mid_line(&path));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -6,14 +6,26 @@ import { Toaster } from 'sonner';
 import { TooltipProvider } from '@/components/ui/tooltip';
 import { SearchProvider } from './lib/SearchContext';
 import { I18nProvider } from './lib/i18n';
+import {
+  applySettings,
+  getDatabaseStatus,
+  getSettings,
+  getSyncStatus,
+  syncLibrary,
+} from './lib/booth-api';
+import { setTranslationLanguages } from './hooks/useTranslation';
+import type { Profile, Settings, SettingsChanged, SyncReport } from './lib/types';
 import ErrorBoundary from './components/common/ErrorBoundary';
 import UpdateToast from './components/common/UpdateToast';
+import UnlockDialog from './components/common/UnlockDialog';
 import AppLayout from './components/layout/AppLayout';
 import SearchPage from './pages/SearchPage';
 import FavoritesPage from './pages/FavoritesPage';
 import StatsPage from './pages/StatsPage';
 import ItemDetailPage from './pages/ItemDetailPage';
 
+const SYNC_INTERVAL_MS = 5 * 60 * 1000;
+
 interface UpdateInfo {
   version: string;
   body: string | null;
@@ -30,8 +42,20 @@ const queryClient = new QueryClient({
   },
 });
 
+function applyAppSettings(settings: Settings) {
+  applySettings(settings);
+  setTranslationLanguages(settings.translation_source, settings.translation_target);
+}
+
+function loadSettings() {
+  getSettings()
+    .then(applyAppSettings)
+    .catch((e) => console.error('Failed to load settings:', e));
+}
+
 function App() {
   const [updateInfo, setUpdateInfo] = useState<UpdateInfo | null>(null);
+  const [locked, setLocked] = useState(false);
 
   useEffect(() => {
     const unlisten = listen<UpdateInfo>('update-available', (event) => {
@@ -43,6 +67,55 @@ function App() {
     };
   }, []);
 
+  // Another profile's database is open now (or was unlocked); everything cached came
+  // from the previous one, and its settings may differ
+  useEffect(() => {
+    const checkLocked = () =>
+      getDatabaseStatus()
+        .then((status) => setLocked(status.locked))
+        .catch((e) => console.error('Failed to get database status:', e));
+    checkLocked();
+    const unlisten = listen<Profile>('profile-changed', () => {
+      queryClient.invalidateQueries();
+      checkLocked();
+      loadSettings();
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Keep request intervals and the translation pair in line with settings
+  useEffect(() => {
+    loadSettings();
+    const unlisten = listen<SettingsChanged>('settings-changed', (event) => {
+      applyAppSettings(event.payload.settings);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Sync the library with the shared folder, if one is set, on start and periodically
+  useEffect(() => {
+    const sync = () =>
+      getSyncStatus()
+        .then((status) => (status.folder ? syncLibrary() : null))
+        .catch((e) => console.error('Library sync failed:', e));
+    sync();
+    const timer = setInterval(sync, SYNC_INTERVAL_MS);
+    const unlisten = listen<SyncReport>('library-synced', () => {
+      queryClient.invalidateQueries();
+    });
+
+    return () => {
+      clearInterval(timer);
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   return (
     <QueryClientProvider client={queryClient}>
       <I18nProvider>
@@ -64,6 +137,7 @@ function App() {
           </TooltipProvider>
         </ErrorBoundary>
         {updateInfo && <UpdateToast update={updateInfo} onDismiss={() => setUpdateInfo(null)} />}
+        {locked && <UnlockDialog onUnlocked={() => setLocked(false)} />}
       </I18nProvider>
     </QueryClientProvider>
   );
@@ -0,0 +1,85 @@
+import { useState } from 'react';
+import { Lock } from 'lucide-react';
+import { useI18n } from '../../lib/i18n';
+import { unlockDatabase } from '../../lib/booth-api';
+import { Button } from '@/components/ui/button';
+import { Input } from '@/components/ui/input';
+import { Checkbox } from '@/components/ui/checkbox';
+import {
+  Dialog,
+  DialogContent,
+  DialogDescription,
+  DialogFooter,
+  DialogHeader,
+  DialogTitle,
+} from '@/components/ui/dialog';
+
+interface Props {
+  onUnlocked: () => void;
+}
+
+/** Asks for the passphrase of an encrypted profile database; cannot be dismissed. */
+export default function UnlockDialog({ onUnlocked }: Props) {
+  const { t } = useI18n();
+  const [passphrase, setPassphrase] = useState('');
+  const [remember, setRemember] = useState(false);
+  const [unlocking, setUnlocking] = useState(false);
+  const [error, setError] = useState(false);
+
+  const handleUnlock = async () => {
+    setUnlocking(true);
+    setError(false);
+    try {
+      await unlockDatabase(passphrase, remember);
+      setPassphrase('');
+      onUnlocked();
+    } catch (e) {
+      console.error('Unlock failed:', e);
+      setError(true);
+    } finally {
+      setUnlocking(false);
+    }
+  };
+
+  return (
+    <Dialog open>
+      <DialogContent
+        onEscapeKeyDown={(e) => e.preventDefault()}
+        onPointerDownOutside={(e) => e.preventDefault()}
+      >
+        <DialogHeader>
+          <DialogTitle className="flex items-center gap-2">
+            <Lock className="w-4 h-4" />
+            {t.unlock.title}
+          </DialogTitle>
+          <DialogDescription>{t.unlock.description}</DialogDescription>
+        </DialogHeader>
+        <form
+          onSubmit={(e) => {
+            e.preventDefault();
+            handleUnlock();
+          }}
+          className="space-y-3"
+        >
+          <Input
+            type="password"
+            autoFocus
+            placeholder={t.unlock.passphrase}
+            value={passphrase}
+            onChange={(e) => setPassphrase(e.target.value)}
+          />
+          <label className="flex items-center gap-2 text-sm text-gray-600">
+            <Checkbox checked={remember} onCheckedChange={(v) => setRemember(v === true)} />
+            {t.unlock.remember}
+          </label>
+          {error && <p className="text-xs text-red-500">{t.unlock.wrong}</p>}
+          <DialogFooter>
+            <Button type="submit" disabled={unlocking || passphrase === ''}>
+              {t.unlock.unlock}
+            </Button>
+          </DialogFooter>
+        </form>
+      </DialogContent>
+    </Dialog>
+  );
+}
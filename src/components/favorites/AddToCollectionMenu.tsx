@@ -56,7 +56,10 @@ export default function AddToCollectionMenu({
             </button>
           </DropdownMenuTrigger>
         </TooltipTrigger>
-        <TooltipContent>{t.collections.addToCollection}</TooltipContent>
+        <TooltipContent>
+          <p>{t.collections.addToCollection}</p>
+          <p className="text-[10px] opacity-80">{t.collections.restoresTrashed}</p>
+        </TooltipContent>
         <DropdownMenuContent align="end" className="min-w-[160px]">
           {collections.map((col) => (
             <DropdownMenuItem
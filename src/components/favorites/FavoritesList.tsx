@@ -46,6 +46,8 @@ export default memo(function FavoritesList({ items }: Props) {
   const handleSetTags = useCallback(
     async (itemId: number, tags: string[]) => {
       await setItemTags(itemId, tags);
+      // Tagging a cached or trashed item favorites it
+      qc.invalidateQueries({ queryKey: ['favorites'] });
       qc.invalidateQueries({ queryKey: ['all-item-tags-batch'] });
       qc.invalidateQueries({ queryKey: ['all-user-tags'] });
     },
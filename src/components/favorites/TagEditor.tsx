@@ -87,6 +87,7 @@ export default function TagEditor({ itemId, tags, allUserTags, onSetTags }: Prop
             onBlur={() => { blurTimeoutRef.current = setTimeout(() => setShowSuggestions(false), 150); }}
             onKeyDown={handleKeyDown}
             placeholder={tags.length === 0 ? t.tags.addTag : '+'}
+            title={t.tags.restoresTrashed}
             maxLength={100}
             className="w-20 px-1 py-0.5 text-xs border-none outline-none bg-transparent"
           />
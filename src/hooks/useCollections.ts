@@ -44,6 +44,8 @@ export function useCollections() {
     mutationFn: (params: { collectionId: number; itemId: number }) =>
       addToCollection(params.collectionId, params.itemId),
     onSuccess: () => {
+      // Collecting a cached or trashed item favorites it
+      qc.invalidateQueries({ queryKey: ['favorites'] });
       qc.invalidateQueries({ queryKey: ['collections'] });
       qc.invalidateQueries({ queryKey: ['collection-items'] });
       qc.invalidateQueries({ queryKey: ['item-collections'] });
@@ -7,6 +7,12 @@ import {
 } from '../lib/booth-api';
 import type { BoothItem, FavoriteItem } from '../lib/types';
 
+/** Most expensive variation, when it differs from the listed (cheapest) price. */
+function maxPrice(item: BoothItem): number | null {
+  const max = Math.max(item.price, ...(item.variations ?? []).map((v) => v.price));
+  return max > item.price ? max : null;
+}
+
 export function useFavorites() {
   const queryClient = useQueryClient();
 
@@ -28,9 +34,11 @@ export function useFavorites() {
         item_id: item.id,
         name: item.name,
         price: item.price,
+        price_max: maxPrice(item),
         thumbnail_url: item.images[0] || null,
         category_name: item.category_name,
         shop_name: item.shop_name,
+        is_adult: item.is_adult ?? false,
       });
     },
     onMutate: async (item: BoothItem) => {
@@ -42,11 +50,20 @@ export function useFavorites() {
           item_id: item.id,
           name: item.name,
           price: item.price,
+          price_max: maxPrice(item),
           thumbnail_url: item.images[0] || null,
           category_name: item.category_name,
           shop_name: item.shop_name,
           added_at: new Date().toISOString(),
           note: null,
+          availability: 'available',
+          last_checked_at: null,
+          purchase_priority: null,
+          shop_id: null,
+          shop_url: null,
+          is_adult: item.is_adult ?? false,
+          published_at: item.published_at ?? null,
+          blurred: item.blurred ?? false,
         },
         ...old,
       ]);
@@ -21,7 +21,7 @@ export function usePopularAvatars() {
   const refetchRef = useRef(query.refetch);
   refetchRef.current = query.refetch;
 
-  // Background update if data is stale (>7 days)
+  // Background update if data is older than the avatar refresh setting
   useEffect(() => {
     const controller = new AbortController();
     (async () => {
@@ -61,9 +61,19 @@ export function useSearch() {
       if (!params) throw new Error('No params');
       const result = await searchBooth(params);
       cacheItems(result.items).catch((e) => console.error('Failed to cache items:', e));
-      saveSearchHistory(params.keyword).catch((e) =>
-        console.error('Failed to save search history:', e),
-      );
+      const filters = {
+        category: params.category,
+        sort: params.sort,
+        only_free: params.only_free,
+        price_min: params.price_min,
+        price_max: params.price_max,
+      };
+      saveSearchHistory(
+        params.keyword,
+        result.total_count ?? result.items.length,
+        filters,
+        result.items.map((item) => item.id),
+      ).catch((e) => console.error('Failed to save search history:', e));
       return result;
     },
     enabled: !!params,
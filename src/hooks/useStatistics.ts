@@ -1,10 +1,11 @@
 import { useQuery } from '@tanstack/react-query';
 import { getAllStatistics } from '../lib/booth-api';
+import type { StatisticsParams } from '../lib/types';
 
-export function useStatistics() {
+export function useStatistics(params?: StatisticsParams) {
   const query = useQuery({
-    queryKey: ['all-statistics'],
-    queryFn: getAllStatistics,
+    queryKey: ['all-statistics', params ?? null],
+    queryFn: () => getAllStatistics(params),
   });
 
   const data = query.data;
@@ -17,6 +18,7 @@ export function useStatistics() {
     searches: data?.searches ?? [],
     monthly: data?.monthly ?? [],
     shops: data?.shops ?? [],
+    comparison: data?.comparison ?? null,
     isLoading: query.isLoading,
     isError: query.isError,
   };
@@ -7,6 +7,17 @@ const memoryCache = new Map<string, string>();
 // Track in-flight requests to prevent duplicate API calls
 const inflight = new Map<string, Promise<string>>();
 
+// Language pair from settings; the backend drops cached translations when it changes
+let sourceLang = 'ja';
+let targetLang = 'ko';
+
+export function setTranslationLanguages(source: string, target: string): void {
+  if (source === sourceLang && target === targetLang) return;
+  sourceLang = source;
+  targetLang = target;
+  memoryCache.clear();
+}
+
 // Lingva API instances (fallback chain)
 const LINGVA_INSTANCES = [
   'https://lingva.lunar.icu',
@@ -82,7 +93,7 @@ export function useTranslation() {
           let lastError: Error | null = null;
           for (const base of LINGVA_INSTANCES) {
             try {
-              const url = `${base}/api/v1/ja/ko/${encoded}`;
+              const url = `${base}/api/v1/${sourceLang}/${targetLang}/${encoded}`;
               const resp = await fetch(url, {
                 method: 'GET',
                 headers: { Accept: 'application/json' },
@@ -3,25 +3,69 @@ import { invoke } from '@tauri-apps/api/core';
 import { parseSearchHtml, parseItemDetailHtml } from './booth-parser';
 import type {
   BoothItem,
+  ItemVariation,
   SearchParams,
   SearchResult,
+  ContentPolicy,
   FavoriteItem,
+  FavoriteSort,
   Collection,
   AllStatistics,
+  AlertRule,
+  AlertRuleParams,
+  PriceAlert,
+  Shop,
+  ShopItem,
+  BudgetMonth,
+  MonthlyBudget,
+  PurchasePlan,
+  TrashEntry,
+  TrashKind,
+  ZeroResultKeyword,
+  ConvertedPrices,
+  CurrencyStatistics,
+  ExchangeRate,
+  FavoriteChange,
+  KeywordConversion,
+  RecentSearch,
+  ItemAvailability,
+  LocalFile,
+  LocalScanSummary,
+  OwnedItem,
+  OwnedItemParams,
+  PackageInspection,
+  PackageInspectSummary,
+  PurchaseImportFile,
+  PurchaseImportReport,
+  RateImportReport,
+  SearchTrend,
+  SearchTrendGranularity,
+  Settings,
+  SettingsPatch,
+  Profile,
+  ProfileSummary,
+  CopySummary,
+  DatabaseStatus,
+  MaintenanceReport,
+  SyncStatus,
+  SyncReport,
+  StatisticsParams,
 } from './types';
 
 // ── Rate limiters (separate queues for different priorities) ──
 
-const RATE_LIMIT_MS = 1000;
+/** Delays from the `request_interval_ms` settings; updated by `applySettings` */
+let requestIntervalMs = 1000;
+let backgroundIntervalMs = 1500;
 const HEADERS = {
   'User-Agent':
     'Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36',
 };
 
-function createRateLimiter(delayMs: number) {
+function createRateLimiter(delayMs: () => number) {
   let queue: Promise<void> = Promise.resolve();
   return async function limitedFetch(url: string): Promise<Response> {
-    const ticket = queue.then(() => new Promise<void>((r) => setTimeout(r, delayMs)));
+    const ticket = queue.then(() => new Promise<void>((r) => setTimeout(r, delayMs())));
     queue = ticket;
     await ticket;
     return fetch(url, { headers: HEADERS });
@@ -29,17 +73,23 @@ function createRateLimiter(delayMs: number) {
 }
 
 /** Primary limiter for user-initiated searches and item detail fetches */
-const rateLimitedFetch = createRateLimiter(RATE_LIMIT_MS);
+const rateLimitedFetch = createRateLimiter(() => requestIntervalMs);
 
 /** Separate limiter for wish-count enrichment — runs independently of user searches */
-const enrichFetch = createRateLimiter(RATE_LIMIT_MS);
+const enrichFetch = createRateLimiter(() => requestIntervalMs);
 
 /** Separate limiter for background avatar updates — never blocks user activity */
-const backgroundFetch = createRateLimiter(1500);
+const backgroundFetch = createRateLimiter(() => backgroundIntervalMs);
+
+/** Apply request intervals from settings; queued requests use them from their next delay */
+export function applySettings(settings: Settings): void {
+  requestIntervalMs = settings.request_interval_ms;
+  backgroundIntervalMs = settings.background_request_interval_ms;
+}
 
 // ── URL builder (ported from Rust client.rs) ─────────
 
-function buildSearchUrl(params: SearchParams): string {
+function buildSearchUrl(params: SearchParams, policy: ContentPolicy): string {
   const page = Math.min(params.page ?? 1, 10_000);
   const keyword = params.keyword.trim();
   const keywordEmpty = keyword === '';
@@ -55,6 +105,9 @@ function buildSearchUrl(params: SearchParams): string {
     url = `https://booth.pm/ja/items?q=${encodeURIComponent(keyword)}&page=${page}`;
   }
 
+  // Booth leaves R-18 items out of results unless asked to include them
+  if (policy !== 'hide') url += '&adult=include';
+
   const validSorts = ['new', 'popular', 'price_asc', 'price_desc'];
   if (params.sort && validSorts.includes(params.sort)) {
     url += `&sort=${params.sort}`;
@@ -79,14 +132,16 @@ export { backgroundFetch };
 // ── Search API (direct fetch + JS parse) ─────────────
 
 export async function searchBooth(params: SearchParams): Promise<SearchResult> {
-  const url = buildSearchUrl(params);
+  const policy = await getContentPolicy();
+  const url = buildSearchUrl(params, policy);
   const resp = await rateLimitedFetch(url);
 
   if (resp.status === 429) throw new Error('Rate limited by Booth.pm');
   if (!resp.ok) throw new Error(`Search returned ${resp.status}`);
 
   const html = await resp.text();
-  const { items, totalCount } = parseSearchHtml(html);
+  const { items: parsed, totalCount } = parseSearchHtml(html);
+  const items = await applyContentPolicy(parsed);
 
   return {
     items,
@@ -155,21 +210,49 @@ function jsonToBoothItem(data: Record<string, unknown>): BoothItem | null {
 
   const category = data.category as Record<string, unknown> | undefined;
   const shop = data.shop as Record<string, unknown> | undefined;
+  const event = data.event as Record<string, unknown> | undefined;
+  const text = (v: unknown) => (typeof v === 'string' && v ? v : null);
 
   const wishListsCount =
     typeof data.wish_lists_count === 'number' ? data.wish_lists_count : undefined;
 
+  const variations: ItemVariation[] = [];
+  if (Array.isArray(data.variations)) {
+    for (const v of data.variations as Record<string, unknown>[]) {
+      const soldOut = v.is_empty_stock === true || v.status === 'soldout';
+      variations.push({
+        name: typeof v.name === 'string' ? v.name : '',
+        price: parsePrice(v.price),
+        type: v.type === 'digital' || v.type == null ? 'download' : 'physical',
+        stock: soldOut ? 0 : typeof v.small_stock === 'number' ? v.small_stock : null,
+      });
+    }
+  }
+  // The top-level price is display text ("¥ 500~") when variations differ
+  const price = variations.length
+    ? Math.min(...variations.map((v) => v.price))
+    : parsePrice(data.price);
+
   return {
     id,
     name: (data.name as string) || '',
     description: (data.description as string) || null,
-    price: parsePrice(data.price),
+    price,
     category_name: (category?.name as string) || null,
     shop_name: (shop?.name as string) || null,
     url: (data.url as string) || `https://booth.pm/ja/items/${id}`,
     images,
     tags,
     wish_lists_count: wishListsCount,
+    variations,
+    is_adult: data.is_adult === true,
+    published_at: text(data.published_at),
+    updated_at: text(data.updated_at),
+    shop_subdomain: text(shop?.subdomain),
+    shop_thumbnail_url: text(shop?.thumbnail_url),
+    event_name: text(event?.name),
+    is_sold_out: data.is_sold_out === true,
+    is_end_of_sale: data.is_end_of_sale === true,
   };
 }
 
@@ -220,23 +303,39 @@ export async function cacheItems(items: BoothItem[]): Promise<void> {
   return invoke('cache_items', { items });
 }
 
-export async function saveSearchHistory(keyword: string): Promise<void> {
-  return invoke('save_search_history', { keyword });
+export async function getCachedItem(itemId: number): Promise<BoothItem | null> {
+  return invoke<BoothItem | null>('get_cached_item', { itemId });
+}
+
+export async function saveSearchHistory(
+  keyword: string,
+  resultCount?: number | null,
+  filters?: Record<string, unknown> | null,
+  itemIds?: number[] | null,
+): Promise<void> {
+  return invoke('save_search_history', {
+    keyword,
+    resultCount: resultCount ?? null,
+    filters: filters ?? null,
+    itemIds: itemIds ?? null,
+  });
 }
 
 // ── Favorites (unchanged — Rust invoke) ──────────────
 
-export async function getFavorites(): Promise<FavoriteItem[]> {
-  return invoke<FavoriteItem[]>('get_favorites');
+export async function getFavorites(sort?: FavoriteSort): Promise<FavoriteItem[]> {
+  return invoke<FavoriteItem[]>('get_favorites', { sort: sort ?? null });
 }
 
 export async function addFavorite(params: {
   item_id: number;
   name: string;
   price: number;
+  price_max?: number | null;
   thumbnail_url: string | null;
   category_name: string | null;
   shop_name: string | null;
+  is_adult?: boolean;
 }): Promise<void> {
   return invoke('add_favorite', { params });
 }
@@ -245,6 +344,30 @@ export async function removeFavorite(itemId: number): Promise<void> {
   return invoke('remove_favorite', { itemId });
 }
 
+/** Starts a background refresh; listen for `favorites-refresh-progress` / `-finished` events. */
+export async function refreshFavorites(itemIds?: number[]): Promise<number> {
+  return invoke<number>('refresh_favorites', { itemIds: itemIds ?? null });
+}
+
+export async function cancelRefreshFavorites(): Promise<void> {
+  return invoke('cancel_refresh_favorites');
+}
+
+/** Fetches one item now and records whether it is still available. */
+export async function checkItemAvailability(itemId: number): Promise<ItemAvailability> {
+  return invoke<ItemAvailability>('check_item_availability', { itemId });
+}
+
+export async function getFavoriteChanges(
+  itemId?: number,
+  limit?: number,
+): Promise<FavoriteChange[]> {
+  return invoke<FavoriteChange[]>('get_favorite_changes', {
+    itemId: itemId ?? null,
+    limit: limit ?? null,
+  });
+}
+
 // ── Popular Avatars (unchanged — Rust invoke) ────────
 
 export interface PopularAvatar {
@@ -292,6 +415,7 @@ export async function deleteCollection(id: number): Promise<void> {
   return invoke('delete_collection', { id });
 }
 
+/** Collecting a cached item favorites it; collecting a trashed favorite restores it. */
 export async function addToCollection(collectionId: number, itemId: number): Promise<void> {
   return invoke('add_to_collection', { collectionId, itemId });
 }
@@ -306,6 +430,7 @@ export async function getCollectionItems(collectionId: number): Promise<Favorite
 
 // ── Item Tags ────────────────────────────────────────
 
+/** Tagging a cached item favorites it; tagging a trashed favorite restores it. */
 export async function setItemTags(itemId: number, tags: string[]): Promise<void> {
   return invoke('set_item_tags', { itemId, tags });
 }
@@ -322,10 +447,427 @@ export async function getAllItemCollectionsBatch(): Promise<Record<number, numbe
   return invoke<Record<number, number[]>>('get_all_item_collections_batch');
 }
 
+// ── Owned library ────────────────────────────────────
+
+export async function getOwnedItems(): Promise<OwnedItem[]> {
+  return invoke<OwnedItem[]>('get_owned_items');
+}
+
+export async function getOwnedItemIds(): Promise<number[]> {
+  return invoke<number[]>('get_owned_item_ids');
+}
+
+export async function addOwnedItem(params: OwnedItemParams): Promise<number> {
+  return invoke<number>('add_owned_item', { params });
+}
+
+export async function updateOwnedItem(id: number, params: OwnedItemParams): Promise<void> {
+  return invoke('update_owned_item', { id, params });
+}
+
+export async function removeOwnedItem(id: number): Promise<void> {
+  return invoke('remove_owned_item', { id });
+}
+
+/** Parses saved Booth library/order pages or emails without importing them. */
+export async function previewPurchaseImport(paths: string[]): Promise<PurchaseImportFile[]> {
+  return invoke<PurchaseImportFile[]>('preview_purchase_import', { paths });
+}
+
+export async function importPurchaseHistory(paths: string[]): Promise<PurchaseImportReport> {
+  return invoke<PurchaseImportReport>('import_purchase_history', { paths });
+}
+
+// ── Local files ──────────────────────────────────────
+
+export async function getLocalScanDirs(): Promise<string[]> {
+  return invoke<string[]>('get_local_scan_dirs');
+}
+
+/** Scans `dirs` (remembered for next time), or the last scanned directories when omitted. */
+export async function scanLocalFiles(dirs?: string[]): Promise<LocalScanSummary> {
+  return invoke<LocalScanSummary>('scan_local_files', { dirs: dirs ?? null });
+}
+
+export async function getLocalFiles(itemId?: number): Promise<LocalFile[]> {
+  return invoke<LocalFile[]>('get_local_files', { itemId: itemId ?? null });
+}
+
+export async function getAllLocalFilesBatch(): Promise<Record<number, LocalFile[]>> {
+  return invoke<Record<number, LocalFile[]>>('get_all_local_files_batch');
+}
+
+/** Links a file to an item by hand; `null` marks it as unrelated. */
+export async function linkLocalFile(id: number, itemId: number | null): Promise<void> {
+  return invoke('link_local_file', { id, itemId });
+}
+
+/** Reads linked .unitypackage files for target avatars, shaders and prefabs. */
+export async function inspectLocalPackages(force = false): Promise<PackageInspectSummary> {
+  return invoke<PackageInspectSummary>('inspect_local_packages', { force });
+}
+
+export async function getPackageInspections(itemId?: number): Promise<PackageInspection[]> {
+  return invoke<PackageInspection[]>('get_package_inspections', { itemId: itemId ?? null });
+}
+
+export async function getPackageAssetPaths(localFileId: number): Promise<string[]> {
+  return invoke<string[]>('get_package_asset_paths', { localFileId });
+}
+
+// ── Currency ─────────────────────────────────────────
+
+export async function getExchangeRates(currency?: string): Promise<ExchangeRate[]> {
+  return invoke<ExchangeRate[]>('get_exchange_rates', { currency: currency ?? null });
+}
+
+/** Records that one unit of `currency` was worth `jpyPerUnit` JPY on `rateDate`. */
+export async function setExchangeRate(
+  currency: string,
+  rateDate: string,
+  jpyPerUnit: number,
+): Promise<void> {
+  return invoke('set_exchange_rate', { currency, rateDate, jpyPerUnit });
+}
+
+export async function deleteExchangeRate(currency: string, rateDate: string): Promise<void> {
+  return invoke('delete_exchange_rate', { currency, rateDate });
+}
+
+/** Imports a `date,currency,jpy_per_unit` CSV file. */
+export async function importExchangeRates(path: string): Promise<RateImportReport> {
+  return invoke<RateImportReport>('import_exchange_rates', { path });
+}
+
+export async function fetchExchangeRates(currencies: string[]): Promise<ExchangeRate[]> {
+  return invoke<ExchangeRate[]>('fetch_exchange_rates', { currencies });
+}
+
+export async function convertPrices(
+  prices: number[],
+  currency: string,
+  date?: string,
+): Promise<ConvertedPrices> {
+  return invoke<ConvertedPrices>('convert_prices', { prices, currency, date: date ?? null });
+}
+
+export async function getStatisticsInCurrency(currency: string): Promise<CurrencyStatistics> {
+  return invoke<CurrencyStatistics>('get_statistics_in_currency', { currency });
+}
+
+// ── Search analytics ─────────────────────────────────
+
+export async function getSearchTrends(
+  granularity: SearchTrendGranularity,
+  days?: number,
+): Promise<SearchTrend[]> {
+  return invoke<SearchTrend[]>('get_search_trends', { granularity, days: days ?? null });
+}
+
+export async function getZeroResultKeywords(limit?: number): Promise<ZeroResultKeyword[]> {
+  return invoke<ZeroResultKeyword[]>('get_zero_result_keywords', { limit: limit ?? null });
+}
+
+export async function getKeywordConversion(
+  limit?: number,
+  minSearches?: number,
+): Promise<KeywordConversion[]> {
+  return invoke<KeywordConversion[]>('get_keyword_conversion', {
+    limit: limit ?? null,
+    minSearches: minSearches ?? null,
+  });
+}
+
+// ── Search history ───────────────────────────────────
+
+export async function getRecentSearches(limit?: number): Promise<RecentSearch[]> {
+  return invoke<RecentSearch[]>('get_recent_searches', { limit: limit ?? null });
+}
+
+export async function deleteSearchKeyword(keyword: string): Promise<number> {
+  return invoke<number>('delete_search_keyword', { keyword });
+}
+
+/** `*` and `?` are wildcards; without them the pattern matches anywhere in the keyword. */
+export async function deleteSearchHistoryMatching(pattern: string): Promise<number> {
+  return invoke<number>('delete_search_history_matching', { pattern });
+}
+
+export async function clearSearchHistory(): Promise<number> {
+  return invoke<number>('clear_search_history');
+}
+
+export async function getSearchHistoryPaused(): Promise<boolean> {
+  return invoke<boolean>('get_search_history_paused');
+}
+
+export async function setSearchHistoryPaused(paused: boolean): Promise<void> {
+  return invoke<void>('set_search_history_paused', { paused });
+}
+
+// ── Budget ───────────────────────────────────────────
+
+export async function getBudgetHistory(): Promise<MonthlyBudget[]> {
+  return invoke<MonthlyBudget[]>('get_budget_history');
+}
+
+export async function getMonthlyBudget(month?: string): Promise<number | null> {
+  return invoke<number | null>('get_monthly_budget', { month: month ?? null });
+}
+
+/** Applies from `fromMonth` (YYYY-MM, default this month) onwards. */
+export async function setMonthlyBudget(amount: number, fromMonth?: string): Promise<void> {
+  return invoke<void>('set_monthly_budget', { amount, fromMonth: fromMonth ?? null });
+}
+
+export async function deleteMonthlyBudget(effectiveMonth: string): Promise<void> {
+  return invoke<void>('delete_monthly_budget', { effectiveMonth });
+}
+
+/** 1 (low) to 5 (high); `null` takes the item off the plan. */
+export async function setPurchasePriority(itemId: number, priority: number | null): Promise<void> {
+  return invoke<void>('set_purchase_priority', { itemId, priority });
+}
+
+export async function getBudgetProgress(months?: number): Promise<BudgetMonth[]> {
+  return invoke<BudgetMonth[]>('get_budget_progress', { months: months ?? null });
+}
+
+/** Defaults to what is left of this month's budget. */
+export async function planPurchases(budget?: number): Promise<PurchasePlan> {
+  return invoke<PurchasePlan>('plan_purchases', { budget: budget ?? null });
+}
+
+// ── Price alerts ─────────────────────────────────────
+
+export async function getPriceAlertRules(): Promise<AlertRule[]> {
+  return invoke<AlertRule[]>('get_price_alert_rules');
+}
+
+export async function addPriceAlertRule(params: AlertRuleParams): Promise<number> {
+  return invoke<number>('add_price_alert_rule', { params });
+}
+
+export async function setPriceAlertRuleEnabled(id: number, enabled: boolean): Promise<void> {
+  return invoke<void>('set_price_alert_rule_enabled', { id, enabled });
+}
+
+export async function deletePriceAlertRule(id: number): Promise<void> {
+  return invoke<void>('delete_price_alert_rule', { id });
+}
+
+/** Alerts are also pushed as `price-alert` events when a refresh triggers them. */
+export async function getPriceAlerts(unreadOnly?: boolean, limit?: number): Promise<PriceAlert[]> {
+  return invoke<PriceAlert[]>('get_price_alerts', {
+    unreadOnly: unreadOnly ?? null,
+    limit: limit ?? null,
+  });
+}
+
+/** Marks all alerts read when `ids` is omitted. */
+export async function markPriceAlertsRead(ids?: number[]): Promise<void> {
+  return invoke<void>('mark_price_alerts_read', { ids: ids ?? null });
+}
+
+export async function clearPriceAlerts(): Promise<void> {
+  return invoke<void>('clear_price_alerts');
+}
+
+export async function setItemAlertsMuted(itemId: number, muted: boolean): Promise<void> {
+  return invoke<void>('set_item_alerts_muted', { itemId, muted });
+}
+
+export async function getMutedAlertItems(): Promise<number[]> {
+  return invoke<number[]>('get_muted_alert_items');
+}
+
+// ── Shops ────────────────────────────────────────────
+
+export async function getShops(followedOnly?: boolean): Promise<Shop[]> {
+  return invoke<Shop[]>('get_shops', { followedOnly: followedOnly ?? null });
+}
+
+/** Accepts a shop subdomain or any URL on the shop. Returns the shop id. */
+export async function followShop(shop: string): Promise<number> {
+  return invoke<number>('follow_shop', { shop });
+}
+
+export async function unfollowShop(id: number): Promise<void> {
+  return invoke<void>('unfollow_shop', { id });
+}
+
+/** Starts a background check; listen for `shops-check-progress` / `-finished` events. */
+export async function checkFollowedShops(shopIds?: number[]): Promise<number> {
+  return invoke<number>('check_followed_shops', { shopIds: shopIds ?? null });
+}
+
+export async function getShopItems(shopId: number): Promise<ShopItem[]> {
+  return invoke<ShopItem[]>('get_shop_items', { shopId });
+}
+
+/** New items of one shop, or of all followed shops when `shopId` is omitted. */
+export async function getNewShopItems(shopId?: number): Promise<ShopItem[]> {
+  return invoke<ShopItem[]>('get_new_shop_items', { shopId: shopId ?? null });
+}
+
+export async function markShopVisited(shopId: number): Promise<void> {
+  return invoke<void>('mark_shop_visited', { shopId });
+}
+
+export async function getShopFavorites(shopId: number): Promise<FavoriteItem[]> {
+  return invoke<FavoriteItem[]>('get_shop_favorites', { shopId });
+}
+
+export async function getShopFavoritesBatch(): Promise<Record<number, number[]>> {
+  return invoke<Record<number, number[]>>('get_shop_favorites_batch');
+}
+
+// ── Content policy ───────────────────────────────────
+
+export async function getContentPolicy(): Promise<ContentPolicy> {
+  return invoke<ContentPolicy>('get_content_policy');
+}
+
+/** Changing the policy also marks avatar item counts for an update. */
+export async function setContentPolicy(policy: ContentPolicy): Promise<void> {
+  return invoke('set_content_policy', { policy });
+}
+
+/** Drops or blurs R-18 items per the policy. */
+export async function applyContentPolicy(items: BoothItem[]): Promise<BoothItem[]> {
+  return invoke<BoothItem[]>('apply_content_policy', { items });
+}
+
+// ── Settings ─────────────────────────────────────────
+
+export async function getSettings(): Promise<Settings> {
+  return invoke<Settings>('get_settings');
+}
+
+/** Validates and stores the given fields, then emits `settings-changed`. */
+export async function updateSettings(patch: SettingsPatch): Promise<Settings> {
+  return invoke<Settings>('update_settings', { patch });
+}
+
+// ── Profiles ─────────────────────────────────────────
+
+export async function listProfiles(): Promise<ProfileSummary[]> {
+  return invoke<ProfileSummary[]>('list_profiles');
+}
+
+export async function getActiveProfile(): Promise<Profile> {
+  return invoke<Profile>('get_active_profile');
+}
+
+export async function createProfile(name: string): Promise<Profile> {
+  return invoke<Profile>('create_profile', { name });
+}
+
+export async function renameProfile(id: string, name: string): Promise<Profile> {
+  return invoke<Profile>('rename_profile', { id, name });
+}
+
+/** The active profile cannot be deleted. */
+export async function deleteProfile(id: string): Promise<void> {
+  return invoke('delete_profile', { id });
+}
+
+/** Opens the profile's database without a restart; emits `profile-changed`. */
+export async function switchProfile(id: string): Promise<Profile> {
+  return invoke<Profile>('switch_profile', { id });
+}
+
+/** Copies favorites (all, or `itemIds`) with tags and collections to another profile. */
+export async function copyProfileData(
+  sourceId: string,
+  targetId: string,
+  itemIds?: number[],
+): Promise<CopySummary> {
+  return invoke<CopySummary>('copy_profile_data', {
+    sourceId,
+    targetId,
+    itemIds: itemIds ?? null,
+  });
+}
+
+// ── Database encryption ──────────────────────────────
+
+export async function getDatabaseStatus(): Promise<DatabaseStatus> {
+  return invoke<DatabaseStatus>('get_database_status');
+}
+
+/** With `remember`, the passphrase is saved in the OS keyring. Emits `profile-changed`. */
+export async function unlockDatabase(
+  passphrase: string,
+  remember: boolean,
+): Promise<DatabaseStatus> {
+  return invoke<DatabaseStatus>('unlock_database', { passphrase, remember });
+}
+
+export async function encryptDatabase(
+  passphrase: string,
+  remember: boolean,
+): Promise<DatabaseStatus> {
+  return invoke<DatabaseStatus>('encrypt_database', { passphrase, remember });
+}
+
+/** Also removes the passphrase from the keyring. */
+export async function decryptDatabase(passphrase: string): Promise<DatabaseStatus> {
+  return invoke<DatabaseStatus>('decrypt_database', { passphrase });
+}
+
+export async function forgetDatabasePassphrase(): Promise<DatabaseStatus> {
+  return invoke<DatabaseStatus>('forget_database_passphrase');
+}
+
+// ── Maintenance ──────────────────────────────────────
+
+/** Health check; `fix` removes orphans, broken references and expired rows, `vacuum` compacts the file. */
+export async function runMaintenance(fix: boolean, vacuum: boolean): Promise<MaintenanceReport> {
+  return invoke<MaintenanceReport>('run_maintenance', { fix, vacuum });
+}
+
+// ── Sync ─────────────────────────────────────────────
+
+export async function getSyncStatus(): Promise<SyncStatus> {
+  return invoke<SyncStatus>('get_sync_status');
+}
+
+/** Shared folder (Syncthing, Dropbox, a NAS); null turns sync off. */
+export async function setSyncFolder(folder: string | null): Promise<void> {
+  return invoke('set_sync_folder', { folder });
+}
+
+export async function syncLibrary(): Promise<SyncReport> {
+  return invoke<SyncReport>('sync_library');
+}
+
+// ── Trash ────────────────────────────────────────────
+
+export async function getTrash(): Promise<TrashEntry[]> {
+  return invoke<TrashEntry[]>('get_trash');
+}
+
+export async function restoreFromTrash(kind: TrashKind, id: number): Promise<void> {
+  return invoke('restore_from_trash', { kind, id });
+}
+
+export async function emptyTrash(): Promise<number> {
+  return invoke<number>('empty_trash');
+}
+
+export async function getTrashRetentionDays(): Promise<number> {
+  return invoke<number>('get_trash_retention_days');
+}
+
+export async function setTrashRetentionDays(days: number): Promise<void> {
+  return invoke('set_trash_retention_days', { days });
+}
+
 // ── Statistics ────────────────────────────────────────
 
-export async function getAllStatistics(): Promise<AllStatistics> {
-  return invoke<AllStatistics>('get_all_statistics');
+export async function getAllStatistics(params?: StatisticsParams): Promise<AllStatistics> {
+  return invoke<AllStatistics>('get_all_statistics', { params: params ?? null });
 }
 
 // ── Translation Cache ────────────────────────────────
@@ -82,6 +82,9 @@ function parseItemCard(el: HTMLElement): BoothItem | null {
 
   if (!name) return null;
 
+  // R-18 cards carry an adult badge; the backend also checks the name for markers
+  const isAdult = el.querySelector('[class*="adult"]') !== null;
+
   return {
     id,
     name,
@@ -92,6 +95,7 @@ function parseItemCard(el: HTMLElement): BoothItem | null {
     url: `https://booth.pm/ja/items/${id}`,
     images,
     tags: [],
+    is_adult: isAdult,
   };
 }
 
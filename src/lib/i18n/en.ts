@@ -97,6 +97,7 @@ export const en: Translations = {
     searchHistory: 'Search Keywords',
     monthlyFavorites: 'Monthly Favorites',
     noData: 'No data',
+    uncategorized: 'Uncategorized',
   },
   collections: {
     title: 'Collections',
@@ -110,6 +111,7 @@ export const en: Translations = {
     removeFrom: 'Remove from Collection',
     namePlaceholder: 'Collection name',
     addToCollection: 'Add to collection',
+    restoresTrashed: 'Collecting an item in the trash restores it',
     confirmDeleteTitle: 'Delete Collection',
     confirmDeleteDesc: 'Are you sure you want to delete this collection? This action cannot be undone.',
     deleteButton: 'Delete',
@@ -118,6 +120,7 @@ export const en: Translations = {
   },
   tags: {
     addTag: 'Add tag...',
+    restoresTrashed: 'Tagging an item in the trash restores it',
   },
   errors: {
     collectionCreate: 'Failed to create collection',
@@ -163,6 +166,14 @@ export const en: Translations = {
     dismiss: 'Later',
     error: 'An error occurred while updating',
   },
+  unlock: {
+    title: 'Unlock library',
+    description: 'The library of this profile is encrypted. Enter the passphrase to open it.',
+    passphrase: 'Passphrase',
+    remember: 'Remember on this computer',
+    unlock: 'Unlock',
+    wrong: 'Wrong passphrase',
+  },
   a11y: {
     skipToContent: 'Skip to content',
     mainNav: 'Main navigation',
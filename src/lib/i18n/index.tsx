@@ -1,4 +1,7 @@
 import { createContext, useContext, useState, useEffect, useMemo, type ReactNode } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { getSettings, updateSettings } from '../booth-api';
+import type { SettingsChanged } from '../types';
 import { ko } from './ko';
 import { en } from './en';
 import type { Language, Translations } from './types';
@@ -40,14 +43,41 @@ export function I18nProvider({ children }: { children: ReactNode }) {
   const setLanguage = (lang: Language) => {
     setLanguageState(lang);
     localStorage.setItem(STORAGE_KEY, lang);
+    updateSettings({ language: lang }).catch((e) =>
+      console.error('Failed to save language:', e),
+    );
   };
 
-  // Sync with localStorage on mount
+  // The language setting is authoritative; localStorage only avoids a flash on startup.
+  // A language chosen before settings existed is carried over once.
   useEffect(() => {
-    const stored = localStorage.getItem(STORAGE_KEY);
-    if (stored === 'ko' || stored === 'en') {
-      setLanguageState(stored);
-    }
+    getSettings()
+      .then((settings) => {
+        if (settings.language) {
+          setLanguageState(settings.language);
+          localStorage.setItem(STORAGE_KEY, settings.language);
+          return;
+        }
+        const stored = localStorage.getItem(STORAGE_KEY);
+        if (stored === 'ko' || stored === 'en') {
+          return updateSettings({ language: stored });
+        }
+      })
+      .catch((e) => console.error('Failed to load language setting:', e));
+
+    const unlisten = listen<SettingsChanged>('settings-changed', (event) => {
+      const lang = event.payload.settings.language;
+      if (lang) {
+        setLanguageState(lang);
+        localStorage.setItem(STORAGE_KEY, lang);
+      } else {
+        localStorage.removeItem(STORAGE_KEY);
+        setLanguageState(getInitialLanguage());
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
   }, []);
 
   const value = useMemo(
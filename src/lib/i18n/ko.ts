@@ -97,6 +97,7 @@ export const ko: Translations = {
     searchHistory: '검색 키워드',
     monthlyFavorites: '월별 즐겨찾기',
     noData: '데이터 없음',
+    uncategorized: '미분류',
   },
   collections: {
     title: '컬렉션',
@@ -110,6 +111,7 @@ export const ko: Translations = {
     removeFrom: '컬렉션에서 제거',
     namePlaceholder: '컬렉션 이름',
     addToCollection: '컬렉션에 추가',
+    restoresTrashed: '휴지통에 있는 아이템은 컬렉션에 추가하면 복원됩니다',
     confirmDeleteTitle: '컬렉션 삭제',
     confirmDeleteDesc: '이 컬렉션을 삭제하시겠습니까? 이 작업은 되돌릴 수 없습니다.',
     deleteButton: '삭제',
@@ -118,6 +120,7 @@ export const ko: Translations = {
   },
   tags: {
     addTag: '태그 추가...',
+    restoresTrashed: '휴지통에 있는 아이템은 태그를 추가하면 복원됩니다',
   },
   errors: {
     collectionCreate: '컬렉션 생성에 실패했습니다',
@@ -163,6 +166,14 @@ export const ko: Translations = {
     dismiss: '나중에',
     error: '업데이트 중 오류가 발생했습니다',
   },
+  unlock: {
+    title: '라이브러리 잠금 해제',
+    description: '이 프로필의 라이브러리는 암호화되어 있습니다. 암호를 입력하세요.',
+    passphrase: '암호',
+    remember: '이 컴퓨터에 기억하기',
+    unlock: '잠금 해제',
+    wrong: '암호가 올바르지 않습니다',
+  },
   a11y: {
     skipToContent: '본문으로 건너뛰기',
     mainNav: '메인 내비게이션',
@@ -97,6 +97,7 @@ export interface Translations {
     searchHistory: string;
     monthlyFavorites: string;
     noData: string;
+    uncategorized: string;
   };
   collections: {
     title: string;
@@ -110,6 +111,7 @@ export interface Translations {
     removeFrom: string;
     namePlaceholder: string;
     addToCollection: string;
+    restoresTrashed: string;
     confirmDeleteTitle: string;
     confirmDeleteDesc: string;
     deleteButton: string;
@@ -118,6 +120,7 @@ export interface Translations {
   };
   tags: {
     addTag: string;
+    restoresTrashed: string;
   };
   errors: {
     collectionCreate: string;
@@ -163,6 +166,14 @@ export interface Translations {
     dismiss: string;
     error: string;
   };
+  unlock: {
+    title: string;
+    description: string;
+    passphrase: string;
+    remember: string;
+    unlock: string;
+    wrong: string;
+  };
   a11y: {
     skipToContent: string;
     mainNav: string;
@@ -1,5 +1,7 @@
 import {
+  applyContentPolicy,
   backgroundFetch,
+  getContentPolicy,
   getPopularAvatars as getPopularAvatarsApi,
   checkAvatarsNeedUpdate,
   updatePopularAvatar,
@@ -19,18 +21,21 @@ export async function needsUpdate(): Promise<boolean> {
 
 export async function updateAvatarData(): Promise<void> {
   const avatars = await getPopularAvatars();
+  // Counts follow the content policy, like search results
+  const adultParam = (await getContentPolicy()) === 'hide' ? '' : '&adult=include';
 
   for (const avatar of avatars) {
     try {
       const keyword = `${avatar.name_ja} 対応`;
-      const url = `https://booth.pm/ja/browse/${encodeURIComponent('3D衣装')}?q=${encodeURIComponent(keyword)}&page=1`;
+      const url = `https://booth.pm/ja/browse/${encodeURIComponent('3D衣装')}?q=${encodeURIComponent(keyword)}&page=1${adultParam}`;
       const resp = await backgroundFetch(url);
       if (!resp.ok) continue;
 
       const html = await resp.text();
       const { items, totalCount } = parseSearchHtml(html);
+      const visible = (await applyContentPolicy(items)).filter((i) => !i.blurred);
 
-      const thumbnailUrl = items[0]?.images[0] ?? avatar.thumbnail_url;
+      const thumbnailUrl = visible[0]?.images[0] ?? avatar.thumbnail_url;
 
       await updatePopularAvatar(avatar.id, totalCount ?? items.length, thumbnailUrl);
     } catch (e) {
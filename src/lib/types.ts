@@ -9,6 +9,34 @@ export interface BoothItem {
   images: string[];
   tags: string[];
   wish_lists_count?: number;
+  /** Purchasable variations; absent for items parsed from search result HTML */
+  variations?: ItemVariation[];
+  /** R-18 item; the fields below are only known from the item JSON */
+  is_adult?: boolean;
+  published_at?: string | null;
+  updated_at?: string | null;
+  shop_subdomain?: string | null;
+  shop_thumbnail_url?: string | null;
+  /** Booth event the item is sold at, if any */
+  event_name?: string | null;
+  is_sold_out?: boolean;
+  /** The creator has ended sales, though the page is still up */
+  is_end_of_sale?: boolean;
+  /** Set by the content policy on R-18 items to blur */
+  blurred?: boolean;
+}
+
+/** How R-18 items are presented; `hide` is the default. */
+export type ContentPolicy = 'show' | 'blur' | 'hide';
+
+export type VariationType = 'download' | 'physical';
+
+export interface ItemVariation {
+  name: string;
+  price: number;
+  type: VariationType;
+  /** Remaining stock when Booth shows it (0 when sold out); null means no stock limit */
+  stock: number | null;
 }
 
 export interface SearchParams {
@@ -29,16 +57,71 @@ export interface SearchResult {
   current_page: number;
 }
 
+export type ItemAvailability = 'available' | 'deleted' | 'private' | 'adult_gated';
+
 export interface FavoriteItem {
   id: number;
   item_id: number;
   name: string;
+  /** Cheapest variation's price */
   price: number;
+  /** Most expensive variation's price, when the item has several prices */
+  price_max: number | null;
   thumbnail_url: string | null;
   category_name: string | null;
   shop_name: string | null;
   added_at: string;
   note: string | null;
+  availability: ItemAvailability;
+  last_checked_at: string | null;
+  /** 1 (low) to 5 (high) when planned for purchase */
+  purchase_priority: number | null;
+  shop_id: number | null;
+  /** Shop page, once the shop's subdomain is known */
+  shop_url: string | null;
+  is_adult: boolean;
+  /** When Booth first published the item */
+  published_at: string | null;
+  /** Set by the content policy on R-18 favorites to blur */
+  blurred: boolean;
+}
+
+/** Order of `getFavorites`; items with an unknown release date go last. */
+export type FavoriteSort = 'added' | 'published';
+
+// ── Favorite refresh ───────────────────────────────────
+
+export interface FieldChange {
+  field: string;
+  old_value: string | null;
+  new_value: string | null;
+}
+
+export interface FavoriteChange extends FieldChange {
+  id: number;
+  item_id: number;
+  name: string | null;
+  changed_at: string;
+}
+
+/** Payload of the `favorites-refresh-progress` event */
+export interface RefreshProgress {
+  done: number;
+  total: number;
+  itemId: number;
+  availability: ItemAvailability | null;
+  changes: FieldChange[];
+  error: string | null;
+}
+
+/** Payload of the `favorites-refresh-finished` event */
+export interface RefreshSummary {
+  total: number;
+  checked: number;
+  changed: number;
+  unavailable: number;
+  failed: number;
+  cancelled: boolean;
 }
 
 export interface Collection {
@@ -50,6 +133,442 @@ export interface Collection {
   item_count: number;
 }
 
+// ── Owned library ──────────────────────────────────────
+
+export interface OwnedItem {
+  id: number;
+  item_id: number;
+  /** Stored name, falling back to the favorite or cached item name */
+  name: string | null;
+  thumbnail_url: string | null;
+  shop_name: string | null;
+  variation: string | null;
+  /** null when imported from a page that does not show prices */
+  price_paid: number | null;
+  purchased_at: string | null;
+  license: string | null;
+  note: string | null;
+  is_favorite: boolean;
+  created_at: string;
+}
+
+export interface OwnedItemParams {
+  item_id: number;
+  name?: string | null;
+  variation?: string | null;
+  price_paid?: number | null;
+  /** YYYY-MM-DD */
+  purchased_at?: string | null;
+  license?: string | null;
+  note?: string | null;
+}
+
+export interface ParsedPurchase {
+  item_id: number;
+  name: string | null;
+  /** YYYY-MM-DD */
+  purchased_at: string | null;
+  price_paid: number | null;
+}
+
+export interface PurchaseImportFile {
+  path: string;
+  purchases: ParsedPurchase[];
+  error: string | null;
+}
+
+export interface PurchaseImportReport {
+  files: number;
+  parsed: number;
+  imported: number;
+  /** Purchases already in the library (same item and purchase date) */
+  duplicates: number;
+  /** Recorded purchases that gained their missing date or price */
+  completed: number;
+  errors: string[];
+}
+
+// ── Local files ────────────────────────────────────────
+
+export interface LocalFile {
+  id: number;
+  path: string;
+  file_name: string;
+  size_bytes: number;
+  /** Unix seconds */
+  modified_at: number | null;
+  item_id: number | null;
+  match_score: number | null;
+  match_reason: string | null;
+  /** Linked (or unlinked) by hand; rescans leave it alone */
+  is_manual: boolean;
+  scanned_at: string;
+}
+
+export interface LocalScanSummary {
+  directories: number;
+  files: number;
+  matched: number;
+  /** Previously recorded files that no longer exist */
+  removed: number;
+}
+
+export interface PackageInspection {
+  local_file_id: number;
+  item_id: number | null;
+  asset_count: number;
+  /** Avatar bases the package has folders/files for */
+  avatars: string[];
+  /** Shaders the package bundles or references by folder name */
+  shaders: string[];
+  has_prefab: boolean;
+  /** Set when the file could not be read as a unitypackage */
+  error: string | null;
+  inspected_at: string;
+}
+
+export interface PackageInspectSummary {
+  inspected: number;
+  failed: number;
+  /** Already inspected and unchanged since */
+  skipped: number;
+}
+
+// ── Currency ───────────────────────────────────────────
+
+export interface ExchangeRate {
+  currency: string;
+  /** YYYY-MM-DD */
+  rate_date: string;
+  /** JPY per one unit of `currency` */
+  jpy_per_unit: number;
+  /** `manual`, `import` or the name of the rate provider */
+  source: string;
+}
+
+export interface RateImportReport {
+  imported: number;
+  /** 1-based line numbers that could not be parsed */
+  bad_lines: number[];
+}
+
+export interface ConvertedPrices {
+  currency: string;
+  rate: ExchangeRate;
+  /** Same order as the requested JPY amounts */
+  amounts: number[];
+}
+
+export interface ConvertedMonthlySpend {
+  month: string;
+  count: number;
+  amount: number;
+}
+
+export interface CurrencyStatistics {
+  currency: string;
+  /** Latest rate, used for current prices */
+  rate: ExchangeRate;
+  total_value: number;
+  total_value_max: number;
+  avg_price: number;
+  wishlist_value: number;
+  /** Each purchase converted at the rate on its purchase date */
+  total_spent: number;
+  monthly_spend: ConvertedMonthlySpend[];
+}
+
+// ── Search analytics ───────────────────────────────────
+
+export type SearchTrendGranularity = 'day' | 'week';
+
+export interface SearchTrend {
+  /** YYYY-MM-DD for days, YYYY-Www for weeks */
+  period: string;
+  searches: number;
+  distinct_keywords: number;
+  zero_results: number;
+  conversions: number;
+}
+
+export interface ZeroResultKeyword {
+  keyword: string;
+  count: number;
+  last_searched_at: string;
+}
+
+export interface KeywordConversion {
+  keyword: string;
+  searches: number;
+  conversions: number;
+  /** conversions / searches, 0–1 */
+  conversion_rate: number;
+}
+
+// ── Search history ─────────────────────────────────────
+
+export interface RecentSearch {
+  keyword: string;
+  count: number;
+  last_searched_at: string;
+}
+
+// ── Budget ─────────────────────────────────────────────
+
+export interface MonthlyBudget {
+  /** YYYY-MM; applies from this month until the next entry */
+  effective_month: string;
+  amount: number;
+}
+
+export interface BudgetMonth {
+  month: string;
+  budget: number | null;
+  spent: number;
+  purchases: number;
+  remaining: number | null;
+  cumulative_budget: number;
+  cumulative_spent: number;
+}
+
+export interface PlannedItem {
+  item_id: number;
+  name: string;
+  price: number;
+  priority: number;
+  thumbnail_url: string | null;
+}
+
+export interface PurchasePlan {
+  budget: number;
+  selected: PlannedItem[];
+  deferred: PlannedItem[];
+  total_price: number;
+  total_priority: number;
+  remaining: number;
+  already_owned: number;
+  unavailable: number;
+}
+
+// ── Price alerts ───────────────────────────────────────
+
+/** `below`: price at or under `threshold` yen; `drop_percent`: fell by `threshold`%; `free`: now 0 */
+export type AlertKind = 'below' | 'drop_percent' | 'free';
+
+export interface AlertRule {
+  id: number;
+  kind: AlertKind;
+  threshold: number | null;
+  /** null applies to every favorite */
+  item_id: number | null;
+  enabled: boolean;
+  created_at: string;
+}
+
+export interface AlertRuleParams {
+  kind: AlertKind;
+  threshold?: number | null;
+  item_id?: number | null;
+}
+
+export interface PriceAlert {
+  id: number;
+  rule_id: number | null;
+  item_id: number;
+  name: string | null;
+  thumbnail_url: string | null;
+  kind: AlertKind;
+  threshold: number | null;
+  old_price: number;
+  new_price: number;
+  triggered_at: string;
+  read_at: string | null;
+}
+
+/** Payload of the `price-alert` event; field names match `PriceAlert` */
+export interface PriceAlertEvent {
+  id: number;
+  item_id: number;
+  name: string | null;
+  kind: AlertKind;
+  threshold: number | null;
+  old_price: number;
+  new_price: number;
+}
+
+// ── Shops ──────────────────────────────────────────────
+
+export interface Shop {
+  id: number;
+  /** Unknown for shops only seen by name on favorites */
+  subdomain: string | null;
+  name: string | null;
+  url: string | null;
+  thumbnail_url: string | null;
+  followed: boolean;
+  item_count: number;
+  /** Items first seen since the shop was last visited */
+  new_item_count: number;
+  favorite_count: number;
+  last_fetched_at: string | null;
+  last_visited_at: string | null;
+}
+
+export interface ShopItem {
+  shop_id: number;
+  item_id: number;
+  name: string | null;
+  price: number | null;
+  thumbnail_url: string | null;
+  first_seen_at: string;
+  last_seen_at: string;
+  is_new: boolean;
+  is_favorite: boolean;
+  /** Known from the cached copy or favorite, or from markers in the name */
+  is_adult: boolean;
+  /** Set by the content policy on R-18 items to blur */
+  blurred: boolean;
+}
+
+/** Payload of the `shops-check-progress` event */
+export interface ShopCheckProgress {
+  done: number;
+  total: number;
+  shopId: number;
+  subdomain: string;
+  newItems: number;
+  error: string | null;
+}
+
+/** Payload of the `shops-check-finished` event */
+export interface ShopCheckSummary {
+  total: number;
+  checked: number;
+  newItems: number;
+  failed: number;
+}
+
+// ── Settings ───────────────────────────────────────────
+
+export interface Settings {
+  /** null follows the system language */
+  language: 'ko' | 'en' | null;
+  translation_source: string;
+  translation_target: string;
+  /** Delay between requests to Booth (500–10000 ms) */
+  request_interval_ms: number;
+  /** Delay between low-priority requests such as avatar updates (500–60000 ms) */
+  background_request_interval_ms: number;
+  cache_ttl_days: number;
+  translation_ttl_days: number;
+  avatar_refresh_days: number;
+  trash_retention_days: number;
+  search_history_paused: boolean;
+  content_policy: ContentPolicy;
+}
+
+/** Fields to change; an empty `language` resets it to the system language. */
+export type SettingsPatch = Partial<Omit<Settings, 'language'>> & { language?: 'ko' | 'en' | '' };
+
+/** Payload of the `settings-changed` event */
+export interface SettingsChanged {
+  settings: Settings;
+  changed: (keyof Settings)[];
+}
+
+// ── Profiles ───────────────────────────────────────────
+
+/** Payload of the `profile-changed` event */
+export interface Profile {
+  id: string;
+  name: string;
+}
+
+export interface ProfileSummary extends Profile {
+  active: boolean;
+  encrypted: boolean;
+  size_bytes: number;
+}
+
+export interface DatabaseStatus {
+  profile_id: string;
+  encrypted: boolean;
+  /** Encrypted and waiting for the passphrase */
+  locked: boolean;
+  /** The passphrase is kept in the OS keyring */
+  passphrase_saved: boolean;
+}
+
+export interface CopySummary {
+  favorites: number;
+  tags: number;
+  collections: number;
+}
+
+// ── Maintenance ────────────────────────────────────────
+
+export interface ForeignKeyViolation {
+  table: string;
+  rowid: number | null;
+  parent: string;
+}
+
+export interface MaintenanceReport {
+  /** Empty when the database is intact; nothing is fixed or vacuumed otherwise */
+  integrity_errors: string[];
+  foreign_key_violations: ForeignKeyViolation[];
+  orphan_tags: number;
+  orphan_collection_items: number;
+  orphan_item_ids: number[];
+  fixed: boolean;
+  /** Expired cache rows and trash; only counted unless `fixed` */
+  evicted: { cached_items: number; translations: number; trash: number };
+  checkpointed_frames: number;
+  vacuumed: boolean;
+  size_before: number;
+  size_after: number;
+}
+
+// ── Sync ───────────────────────────────────────────────
+
+export interface SyncDevice {
+  device: string;
+  /** Lines of its log merged so far */
+  applied: number;
+  last_seen_at: string | null;
+}
+
+export interface SyncStatus {
+  /** null while sync is off */
+  folder: string | null;
+  device_id: string;
+  devices: SyncDevice[];
+}
+
+/** Also the payload of `library-synced` */
+export interface SyncReport {
+  exported: number;
+  imported: number;
+  devices: number;
+}
+
+// ── Trash ──────────────────────────────────────────────
+
+export type TrashKind = 'favorite' | 'collection';
+
+export interface TrashEntry {
+  kind: TrashKind;
+  /** item_id for favorites, collection id for collections */
+  id: number;
+  name: string;
+  thumbnail_url: string | null;
+  deleted_at: string;
+  /** Always false for collections */
+  is_adult: boolean;
+  /** Set by the content policy on R-18 favorites to blur */
+  blurred: boolean;
+}
+
 // ── Statistics ─────────────────────────────────────────
 
 export interface DashboardStats {
@@ -57,20 +576,47 @@ export interface DashboardStats {
   collections_count: number;
   tags_count: number;
   searches_count: number;
+  /** Sum of favorites' cheapest prices */
   total_value: number;
+  /** Sum of favorites' most expensive variations */
+  total_value_max: number;
   avg_price: number;
+  /** Favorites whose last check found them deleted, private or age-gated */
+  unavailable_count: number;
+  /** Favorites never checked or not checked in the last 30 days */
+  stale_check_count: number;
+  /** Distinct items in the owned library */
+  owned_count: number;
+  total_spent: number;
+  /** total_value minus favorites that are already owned */
+  wishlist_value: number;
 }
 
 export interface CategoryStat {
+  category_id: number | null;
+  /** Category name, or the localized "uncategorized" label */
   category: string;
+  uncategorized: boolean;
   count: number;
 }
 
 export interface PriceBucket {
+  /** Locale-independent id: `free` or the JPY range (`~500`, `501~1000`, `10000~`) */
+  key: string;
+  /** Inclusive JPY bounds; `max` is null for the open-ended top bucket */
+  min: number;
+  max: number | null;
   label: string;
   count: number;
 }
 
+export type StatsLocale = 'ja' | 'ko' | 'en';
+
+export interface AvailabilityStat {
+  availability: ItemAvailability;
+  count: number;
+}
+
 export interface TagStat {
   tag: string;
   count: number;
@@ -86,8 +632,16 @@ export interface MonthlyCount {
   count: number;
 }
 
+export interface MonthlySpend {
+  month: string;
+  count: number;
+  amount: number;
+}
+
 export interface ShopStat {
+  shop_id: number;
   shop: string;
+  url: string | null;
   count: number;
 }
 
@@ -95,8 +649,45 @@ export interface AllStatistics {
   stats: DashboardStats;
   categories: CategoryStat[];
   prices: PriceBucket[];
+  availability: AvailabilityStat[];
   tags: TagStat[];
   searches: SearchFrequency[];
   monthly: MonthlyCount[];
+  monthly_spend: MonthlySpend[];
   shops: ShopStat[];
+  comparison: PeriodComparison | null;
+}
+
+export interface PeriodSummary {
+  from: string;
+  to: string;
+  favorites_added: number;
+  value_added: number;
+  purchases: number;
+  spent: number;
+  searches: number;
+}
+
+export interface PeriodComparison {
+  current: PeriodSummary;
+  /** The equally long period ending the day before `current.from` */
+  previous: PeriodSummary;
+}
+
+export interface StatisticsParams {
+  /** Inclusive YYYY-MM-DD bounds on when favorites were added, purchases made and searches run */
+  from?: string | null;
+  to?: string | null;
+  collection_id?: number | null;
+  tag?: string | null;
+  /** Ascending upper bounds (JPY) of the price buckets; a leading 0 is the "free" bucket */
+  price_bounds?: number[] | null;
+  top_categories?: number | null;
+  top_tags?: number | null;
+  top_searches?: number | null;
+  top_shops?: number | null;
+  /** Summarize from..to against the period right before it (needs `from`) */
+  compare_previous?: boolean;
+  /** Language of label texts; keys and bounds are always locale-independent */
+  locale?: StatsLocale;
 }
@@ -173,8 +173,8 @@ export default function StatsPage() {
               <div className="space-y-2">
                 {categories.map((c) => (
                   <HorizontalBar
-                    key={c.category}
-                    label={c.category}
+                    key={c.uncategorized ? '' : c.category}
+                    label={c.uncategorized ? t.stats.uncategorized : c.category}
                     value={c.count}
                     maxValue={catMax}
                     color="bg-indigo-500"
@@ -192,10 +192,10 @@ export default function StatsPage() {
               <div className="space-y-2">
                 {prices.map((p) => {
                   const priceLabel =
-                    t.priceBuckets[p.label as keyof typeof t.priceBuckets] ?? `¥${p.label}`;
+                    t.priceBuckets[p.key as keyof typeof t.priceBuckets] ?? `¥${p.key}`;
                   return (
                     <HorizontalBar
-                      key={p.label}
+                      key={p.key}
                       label={priceLabel}
                       value={p.count}
                       maxValue={priceMax}
@@ -218,7 +218,7 @@ export default function StatsPage() {
               <div className="space-y-2">
                 {shops.map((s) => (
                   <HorizontalBar
-                    key={s.shop}
+                    key={s.shop_id}
                     label={s.shop}
                     value={s.count}
                     maxValue={shopMax}